@@ -0,0 +1,76 @@
+//! C ABI bindings for verifying mpt-lib proofs from non-Rust clients.
+//!
+//! Generate the matching header with [cbindgen](https://github.com/mozilla/cbindgen):
+//! ```shell
+//! cbindgen --config lib-ffi/cbindgen.toml --crate mpt-lib-ffi --output lib-ffi/include/mpt_lib.h lib-ffi
+//! ```
+
+use core::slice;
+
+use mpt_lib::mpt::verify_proof;
+use mpt_lib::rlp_encoding::keccak256;
+use mpt_lib::types::H256;
+
+/// Verify a Merkle Patricia Trie proof
+/// 
+/// `proof_nodes` and `proof_node_lens` are parallel arrays of `proof_len` RLP-encoded nodes
+/// from root to leaf. Returns `false` if any pointer is null or `root_len` is not 32.
+/// 
+/// # Safety
+/// Every non-null pointer must be valid for reads of its associated length.
+#[no_mangle]
+pub unsafe extern "C" fn mpt_verify_proof(
+    root: *const u8,
+    root_len: usize,
+    key: *const u8,
+    key_len: usize,
+    expected_value: *const u8,
+    value_len: usize,
+    proof_nodes: *const *const u8,
+    proof_node_lens: *const usize,
+    proof_len: usize,
+) -> bool {
+    if root.is_null() || key.is_null() || expected_value.is_null() {
+        return false;
+    }
+    if proof_nodes.is_null() || proof_node_lens.is_null() {
+        return false;
+    }
+    if root_len != 32 {
+        return false;
+    }
+    
+    let mut root_hash: H256 = [0u8; 32];
+    root_hash.copy_from_slice(slice::from_raw_parts(root, root_len));
+    let key = slice::from_raw_parts(key, key_len);
+    let expected_value = slice::from_raw_parts(expected_value, value_len);
+    
+    let node_ptrs = slice::from_raw_parts(proof_nodes, proof_len);
+    let node_lens = slice::from_raw_parts(proof_node_lens, proof_len);
+    
+    let mut proof = Vec::with_capacity(proof_len);
+    for (&ptr, &len) in node_ptrs.iter().zip(node_lens) {
+        if ptr.is_null() {
+            return false;
+        }
+        proof.push(slice::from_raw_parts(ptr, len).to_vec());
+    }
+    
+    verify_proof(&root_hash, key, expected_value, &proof)
+}
+
+/// Compute the Keccak256 hash of `data` into the 32-byte buffer `out`
+/// 
+/// Does nothing if either pointer is null.
+/// 
+/// # Safety
+/// `data` must be valid for reads of `data_len` bytes and `out` valid for writes of 32 bytes.
+#[no_mangle]
+pub unsafe extern "C" fn mpt_keccak256(data: *const u8, data_len: usize, out: *mut u8) {
+    if data.is_null() || out.is_null() {
+        return;
+    }
+    
+    let hash = keccak256(slice::from_raw_parts(data, data_len));
+    slice::from_raw_parts_mut(out, 32).copy_from_slice(&hash);
+}
@@ -0,0 +1,4 @@
+fn main() {
+    println!("cargo:rerun-if-changed=ffi_test.c");
+    cc::Build::new().file("ffi_test.c").compile("mpt_ffi_test");
+}
@@ -0,0 +1,59 @@
+/* C-side harness for mpt-lib-ffi, driven by src/lib.rs. */
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+bool mpt_verify_proof(const uint8_t *root, size_t root_len,
+                      const uint8_t *key, size_t key_len,
+                      const uint8_t *expected_value, size_t value_len,
+                      const uint8_t *const *proof_nodes, const size_t *proof_node_lens,
+                      size_t proof_len);
+void mpt_keccak256(const uint8_t *data, size_t data_len, uint8_t *out);
+
+/* Verify a single-node proof through the C ABI. */
+bool c_verify_single_node(const uint8_t *root, const uint8_t *key, size_t key_len,
+                          const uint8_t *value, size_t value_len,
+                          const uint8_t *node, size_t node_len) {
+    const uint8_t *nodes[1] = { node };
+    size_t lens[1] = { node_len };
+    return mpt_verify_proof(root, 32, key, key_len, value, value_len, nodes, lens, 1);
+}
+
+/* Returns 0 when every null-pointer case is rejected, or the index of the failing case. */
+int c_null_checks(const uint8_t *root, const uint8_t *key, size_t key_len,
+                  const uint8_t *value, size_t value_len,
+                  const uint8_t *node, size_t node_len) {
+    const uint8_t *nodes[1] = { node };
+    const uint8_t *null_nodes[1] = { NULL };
+    size_t lens[1] = { node_len };
+
+    if (mpt_verify_proof(NULL, 32, key, key_len, value, value_len, nodes, lens, 1)) return 1;
+    if (mpt_verify_proof(root, 32, NULL, key_len, value, value_len, nodes, lens, 1)) return 2;
+    if (mpt_verify_proof(root, 32, key, key_len, NULL, value_len, nodes, lens, 1)) return 3;
+    if (mpt_verify_proof(root, 32, key, key_len, value, value_len, NULL, lens, 1)) return 4;
+    if (mpt_verify_proof(root, 32, key, key_len, value, value_len, nodes, NULL, 1)) return 5;
+    if (mpt_verify_proof(root, 32, key, key_len, value, value_len, null_nodes, lens, 1)) return 6;
+    if (mpt_verify_proof(root, 31, key, key_len, value, value_len, nodes, lens, 1)) return 7;
+
+    /* keccak256 with null pointers must leave the output untouched */
+    uint8_t out[32];
+    memset(out, 0xaa, sizeof(out));
+    mpt_keccak256(NULL, 0, out);
+    for (size_t i = 0; i < sizeof(out); i++) {
+        if (out[i] != 0xaa) return 8;
+    }
+    mpt_keccak256(key, key_len, NULL);
+    return 0;
+}
+
+/* keccak256("hello") */
+int c_keccak256_hello(void) {
+    static const uint8_t expected[32] = {
+        0x1c, 0x8a, 0xff, 0x95, 0x06, 0x85, 0xc2, 0xed, 0x4b, 0xc3, 0x17, 0x4f, 0x34, 0x72, 0x28, 0x7b,
+        0x56, 0xd9, 0x51, 0x7b, 0x9c, 0x94, 0x81, 0x27, 0x31, 0x9a, 0x09, 0xa7, 0xa3, 0x6d, 0xea, 0xc8,
+    };
+    uint8_t out[32];
+    mpt_keccak256((const uint8_t *)"hello", 5, out);
+    return memcmp(out, expected, sizeof(out)) == 0;
+}
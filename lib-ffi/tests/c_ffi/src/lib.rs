@@ -0,0 +1,74 @@
+//! Drives the C harness in `ffi_test.c` against the mpt-lib-ffi bindings.
+//!
+//! Run with `cargo test -p mpt-ffi-tests`.
+
+// Link the exported symbols the C harness calls
+extern crate mpt_lib_ffi;
+
+#[cfg(test)]
+mod tests {
+    use mpt_lib::{encode_bytes, encode_list, encode_path, keccak256, to_nibbles, H256};
+
+    extern "C" {
+        fn c_verify_single_node(
+            root: *const u8,
+            key: *const u8,
+            key_len: usize,
+            value: *const u8,
+            value_len: usize,
+            node: *const u8,
+            node_len: usize,
+        ) -> bool;
+        fn c_null_checks(
+            root: *const u8,
+            key: *const u8,
+            key_len: usize,
+            value: *const u8,
+            value_len: usize,
+            node: *const u8,
+            node_len: usize,
+        ) -> i32;
+        fn c_keccak256_hello() -> i32;
+    }
+
+    fn leaf(key: &[u8], value: &[u8]) -> (H256, Vec<u8>) {
+        let node = encode_list(&[
+            encode_bytes(&encode_path(&to_nibbles(key), true)),
+            encode_bytes(value),
+        ]);
+        (keccak256(&node), node)
+    }
+
+    #[test]
+    fn test_c_verify_proof() {
+        let (root, node) = leaf(b"key", b"value");
+        let verify = |value: &[u8]| unsafe {
+            c_verify_single_node(
+                root.as_ptr(),
+                b"key".as_ptr(),
+                3,
+                value.as_ptr(),
+                value.len(),
+                node.as_ptr(),
+                node.len(),
+            )
+        };
+
+        assert!(verify(b"value"));
+        assert!(!verify(b"wrong"));
+    }
+
+    #[test]
+    fn test_c_null_checks() {
+        let (root, node) = leaf(b"key", b"value");
+        let failed_case = unsafe {
+            c_null_checks(root.as_ptr(), b"key".as_ptr(), 3, b"value".as_ptr(), 5, node.as_ptr(), node.len())
+        };
+        assert_eq!(failed_case, 0);
+    }
+
+    #[test]
+    fn test_c_keccak256() {
+        assert_eq!(unsafe { c_keccak256_hello() }, 1);
+    }
+}
@@ -0,0 +1,82 @@
+//! `wasm-bindgen` bindings for verifying mpt-lib proofs in the browser.
+//!
+//! Build with `make wasm` (see the workspace `Makefile`).
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use mpt_lib::mpt::verify_proof;
+use mpt_lib::rlp_encoding::keccak256;
+use mpt_lib::types::H256;
+
+/// Verify a Merkle Patricia Trie proof
+/// 
+/// `proof` must be a JS array of `Uint8Array` RLP-encoded nodes from root to leaf.
+/// Malformed arguments (a root that is not 32 bytes, a non-array proof, non-byte-array
+/// nodes) return `false`.
+#[wasm_bindgen]
+pub fn verify_proof_wasm(root: &[u8], key: &[u8], expected_value: &[u8], proof: JsValue) -> bool {
+    let root: H256 = match root.try_into() {
+        Ok(root) => root,
+        Err(_) => return false,
+    };
+    
+    let nodes = match proof.dyn_into::<Array>() {
+        Ok(nodes) => nodes,
+        Err(_) => return false,
+    };
+    
+    let mut proof_nodes = Vec::with_capacity(nodes.length() as usize);
+    for node in nodes.iter() {
+        match node.dyn_into::<Uint8Array>() {
+            Ok(bytes) => proof_nodes.push(bytes.to_vec()),
+            Err(_) => return false,
+        }
+    }
+    
+    verify_proof(&root, key, expected_value, &proof_nodes)
+}
+
+/// Compute Keccak256 hash
+#[wasm_bindgen]
+pub fn keccak256_wasm(data: &[u8]) -> Vec<u8> {
+    keccak256(data).to_vec()
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use mpt_lib::path::{encode_path, to_nibbles};
+    use mpt_lib::rlp_encoding::{encode_bytes, encode_list};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn leaf_proof(key: &[u8], value: &[u8]) -> (H256, Array) {
+        let leaf_rlp = encode_list(&[
+            encode_bytes(&encode_path(&to_nibbles(key), true)),
+            encode_bytes(value),
+        ]);
+        let proof = Array::new();
+        proof.push(&Uint8Array::from(leaf_rlp.as_slice()));
+        (keccak256(&leaf_rlp), proof)
+    }
+
+    #[wasm_bindgen_test]
+    fn test_verify_proof_wasm() {
+        let (root, proof) = leaf_proof(b"key", b"value");
+        assert!(verify_proof_wasm(&root, b"key", b"value", proof.clone().into()));
+        assert!(!verify_proof_wasm(&root, b"key", b"wrong", proof.into()));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_verify_proof_wasm_malformed() {
+        let (root, proof) = leaf_proof(b"key", b"value");
+        assert!(!verify_proof_wasm(&root[..31], b"key", b"value", proof.into()));
+        assert!(!verify_proof_wasm(&root, b"key", b"value", JsValue::from_str("not an array")));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_keccak256_wasm() {
+        assert_eq!(keccak256_wasm(b"hello"), keccak256(b"hello").to_vec());
+    }
+}
@@ -0,0 +1,87 @@
+//! Allocations and time for encoding branch nodes, counted with a wrapping global allocator.
+//!
+//! `legacy_items` encodes each of the 17 items into its own `Vec` before `encode_list`, as
+//! `BranchNode::to_rlp` used to; the counts are printed before the timings.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --bench branch_encoding
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mpt_lib::{encode_bytes, encode_list, keccak256, BranchNode, MPTBuilder};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn legacy_items(branch: &BranchNode) -> Vec<u8> {
+    let mut items = Vec::with_capacity(17);
+    for child in &branch.children {
+        items.push(encode_bytes(child.as_ref().map_or(&[][..], |hash| &hash[..])));
+    }
+    items.push(encode_bytes(branch.value.as_deref().unwrap_or(&[])));
+    encode_list(&items)
+}
+
+/// 10000 full branches without values, each with its own child hashes
+fn branches() -> Vec<BranchNode> {
+    (0..10_000u32)
+        .map(|i| BranchNode {
+            children: std::array::from_fn(|slot| Some(keccak256(&[i.to_be_bytes(), (slot as u32).to_be_bytes()].concat()))),
+            value: None,
+        })
+        .collect()
+}
+
+fn bench_branch_encoding(c: &mut Criterion) {
+    let branches = branches();
+    for branch in &branches[..10] {
+        assert_eq!(branch.to_rlp(), legacy_items(branch));
+    }
+
+    let legacy = allocations(|| branches.iter().for_each(|branch| drop(black_box(legacy_items(branch)))));
+    let to_rlp = allocations(|| branches.iter().for_each(|branch| drop(black_box(branch.to_rlp()))));
+    let keys: Vec<_> = (0..10_000u32).map(|i| keccak256(&i.to_be_bytes())).collect();
+    let inserts = allocations(|| {
+        let mut builder = MPTBuilder::new();
+        for key in &keys {
+            builder.insert(key, b"value");
+        }
+        black_box(builder.root());
+    });
+    println!("allocations for 10000 branches: legacy_items {legacy}, to_rlp {to_rlp}");
+    println!("allocations for 10000 inserts: {inserts}");
+
+    let mut group = c.benchmark_group("encode_10000_branches");
+    group.bench_function("legacy_items", |b| {
+        b.iter(|| branches.iter().map(|branch| legacy_items(branch).len()).sum::<usize>())
+    });
+    group.bench_function("to_rlp", |b| b.iter(|| branches.iter().map(|branch| branch.to_rlp().len()).sum::<usize>()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_branch_encoding);
+criterion_main!(benches);
@@ -0,0 +1,41 @@
+//! Compare the scalar and SIMD `common_prefix_len` on 64-nibble paths, and building a
+//! trie of 32-byte keys with whichever one the `simd` feature selects.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --bench common_prefix
+//! cargo bench -p mpt-lib --features simd --bench common_prefix
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mpt_lib::{common_prefix_len, keccak256, to_nibbles, MPTBuilder};
+
+fn bench_common_prefix(c: &mut Criterion) {
+    let a = to_nibbles(&keccak256(b"key"));
+    let mut b = a.clone();
+    b[63] ^= 1;
+    let mut group = c.benchmark_group("common_prefix_len_64");
+
+    group.bench_function("scalar", |bench| bench.iter(|| common_prefix_len(black_box(&a), black_box(&b))));
+    #[cfg(feature = "simd")]
+    group.bench_function("simd", |bench| {
+        bench.iter(|| mpt_lib::common_prefix_len_simd(black_box(&a), black_box(&b)))
+    });
+
+    group.finish();
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let keys: Vec<_> = (0..10_000u32).map(|i| keccak256(&i.to_be_bytes())).collect();
+    c.bench_function("insert_10000", |bench| {
+        bench.iter(|| {
+            let mut builder = MPTBuilder::new();
+            for key in &keys {
+                builder.insert(key, b"value");
+            }
+            black_box(builder.root())
+        })
+    });
+}
+
+criterion_group!(benches, bench_common_prefix, bench_insert);
+criterion_main!(benches);
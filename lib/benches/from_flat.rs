@@ -0,0 +1,38 @@
+//! Compare building a trie with `MPTBuilder::from_flat` against sequential `insert` calls.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --bench from_flat
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mpt_lib::{keccak256, MPTBuilder};
+
+fn pairs(n: u32) -> Vec<(Vec<u8>, Vec<u8>)> {
+    (0..n)
+        .map(|i| (keccak256(&i.to_be_bytes()).to_vec(), i.to_be_bytes().repeat(10)))
+        .collect()
+}
+
+fn bench_build(c: &mut Criterion) {
+    let pairs = pairs(1000);
+    let mut group = c.benchmark_group("build_1000");
+
+    group.bench_function("insert", |b| {
+        b.iter(|| {
+            let mut builder = MPTBuilder::new();
+            for (key, value) in &pairs {
+                builder.insert(key, value);
+            }
+            black_box(builder.root())
+        })
+    });
+
+    group.bench_function("from_flat", |b| {
+        b.iter(|| black_box(MPTBuilder::from_flat(pairs.clone()).root()))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_build);
+criterion_main!(benches);
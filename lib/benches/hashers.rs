@@ -0,0 +1,44 @@
+//! Compare building, proving and verifying a trie hashed with Keccak-256 against
+//! Poseidon.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --features poseidon --bench hashers
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mpt_lib::{keccak256, verify_proof_with_hasher, Keccak256Hasher, MPTBuilder, NodeHasher, PoseidonHasher};
+
+fn populated<H: NodeHasher>(hasher: H, n: u32) -> MPTBuilder<H> {
+    let mut builder = MPTBuilder::with_hasher(hasher);
+    for i in 0..n {
+        builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+    }
+    builder
+}
+
+fn bench_hasher<H: NodeHasher + Copy>(c: &mut Criterion, name: &str, hasher: H) {
+    let mut group = c.benchmark_group(name);
+
+    group.bench_function("build_1000", |b| b.iter(|| black_box(populated(hasher, 1_000).root())));
+
+    let builder = populated(hasher, 1_000);
+    let root = builder.root().unwrap();
+    let key = keccak256(&500u32.to_be_bytes());
+    let value = 500u32.to_be_bytes().repeat(10);
+    group.bench_function("get_proof", |b| b.iter(|| black_box(builder.get_proof(&key))));
+
+    let proof = builder.get_proof(&key).unwrap();
+    group.bench_function("verify_proof", |b| {
+        b.iter(|| verify_proof_with_hasher(&hasher, &root, black_box(&key), &value, &proof))
+    });
+
+    group.finish();
+}
+
+fn bench_hashers(c: &mut Criterion) {
+    bench_hasher(c, "keccak256", Keccak256Hasher);
+    bench_hasher(c, "poseidon", PoseidonHasher);
+}
+
+criterion_group!(benches, bench_hashers);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+//! Compare applying a batch of updates with `insert_incremental` + `commit` against
+//! sequential `insert` calls on a populated trie.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --bench incremental
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use mpt_lib::{keccak256, MPTBuilder};
+
+fn populated(n: u32) -> MPTBuilder {
+    MPTBuilder::from_flat(
+        (0..n)
+            .map(|i| (keccak256(&i.to_be_bytes()).to_vec(), i.to_be_bytes().repeat(10)))
+            .collect(),
+    )
+}
+
+fn bench_updates(c: &mut Criterion) {
+    let base = populated(10_000);
+    let updates: Vec<_> = (0..100u32).map(|i| keccak256(&(i * 97).to_be_bytes())).collect();
+    let mut group = c.benchmark_group("update_100_of_10000");
+
+    group.bench_function("insert", |b| {
+        b.iter_batched(
+            || base.clone(),
+            |mut builder| {
+                for key in &updates {
+                    builder.insert(key, b"updated value");
+                }
+                black_box(builder.root())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("insert_incremental", |b| {
+        b.iter_batched(
+            || base.clone(),
+            |mut builder| {
+                for key in &updates {
+                    builder.insert_incremental(key, b"updated value");
+                }
+                black_box(builder.commit())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_updates);
+criterion_main!(benches);
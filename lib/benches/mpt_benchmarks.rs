@@ -0,0 +1,129 @@
+//! Baselines for the hot paths: building, lookups, proofs, verification, hashing and RLP.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --bench mpt_benchmarks
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use mpt_lib::{
+    decode_list, encode_bytes, encode_list, keccak256, verify_proof, BranchNode, CachedNode, MPTBuilder, Node,
+};
+
+/// `n` pseudo-random 32-byte keys with 40-byte values
+fn entries(n: u32) -> Vec<([u8; 32], Vec<u8>)> {
+    (0..n).map(|i| (keccak256(&i.to_be_bytes()), i.to_be_bytes().repeat(10))).collect()
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert");
+    for n in [1u32, 10, 100, 1_000, 10_000] {
+        let entries = entries(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &entries, |b, entries| {
+            b.iter(|| {
+                let mut builder = MPTBuilder::new();
+                for (key, value) in entries {
+                    builder.insert(key, value);
+                }
+                black_box(builder.root())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_lookup(c: &mut Criterion) {
+    let entries = entries(1_000);
+    let mut builder = MPTBuilder::new();
+    for (key, value) in &entries {
+        builder.insert(key, value);
+    }
+    let key = entries[500].0;
+
+    c.bench_function("get_1000", |b| b.iter(|| black_box(builder.get(black_box(&key)))));
+    c.bench_function("get_proof_1000", |b| b.iter(|| black_box(builder.get_proof(black_box(&key)))));
+}
+
+fn bench_verify(c: &mut Criterion) {
+    let value = [0x42u8; 40];
+
+    // A single leaf at the root
+    let mut single = MPTBuilder::new();
+    let single_root = single.insert(&[0u8; 32], &value);
+    let single_proof = single.get_proof(&[0u8; 32]).unwrap();
+    assert_eq!(single_proof.len(), 1);
+
+    // Keys splitting off the all-zero key one nibble at a time: 7 branches and a leaf
+    let mut deep = MPTBuilder::new();
+    deep.insert(&[0u8; 32], &value);
+    for nibble in 0..7 {
+        let mut key = [0u8; 32];
+        key[nibble / 2] = if nibble % 2 == 0 { 0x10 } else { 0x01 };
+        deep.insert(&key, &value);
+    }
+    let deep_root = deep.root().unwrap();
+    let deep_proof = deep.get_proof(&[0u8; 32]).unwrap();
+    assert_eq!(deep_proof.len(), 8);
+
+    let mut group = c.benchmark_group("verify_proof");
+    group.bench_function("1_node", |b| {
+        b.iter(|| verify_proof(black_box(&single_root), &[0u8; 32], &value, black_box(&single_proof)))
+    });
+    group.bench_function("8_nodes", |b| {
+        b.iter(|| verify_proof(black_box(&deep_root), &[0u8; 32], &value, black_box(&deep_proof)))
+    });
+    group.finish();
+}
+
+fn bench_keccak(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keccak256");
+    for len in [32usize, 200] {
+        let data = vec![0xab; len];
+        group.bench_with_input(BenchmarkId::from_parameter(len), &data, |b, data| {
+            b.iter(|| keccak256(black_box(data)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_rlp(c: &mut Criterion) {
+    // A full branch: 16 child hashes and an empty value
+    let items: Vec<Vec<u8>> = (0..16u8)
+        .map(|i| encode_bytes(&keccak256(&[i])))
+        .chain(std::iter::once(encode_bytes(&[])))
+        .collect();
+    let branch = encode_list(&items);
+
+    c.bench_function("encode_list_branch", |b| b.iter(|| encode_list(black_box(&items))));
+    c.bench_function("decode_list_branch", |b| b.iter(|| decode_list(black_box(&branch))));
+}
+
+fn bench_cached_node(c: &mut Criterion) {
+    // A branch filled one child at a time, as during a run of inserts, hashed at the end
+    let mut group = c.benchmark_group("branch_16_updates");
+    group.bench_function("eager_hash", |b| {
+        b.iter(|| {
+            let mut branch = BranchNode { children: Default::default(), value: None };
+            let mut hash = [0u8; 32];
+            for i in 0..16u8 {
+                branch.children[i as usize] = Some(keccak256(&[i]));
+                hash = Node::Branch(branch.clone()).hash();
+            }
+            black_box(hash)
+        })
+    });
+    group.bench_function("cached_node", |b| {
+        b.iter(|| {
+            let mut cached = CachedNode::new(Node::Branch(BranchNode { children: Default::default(), value: None }));
+            for i in 0..16u8 {
+                if let Node::Branch(branch) = cached.node_mut() {
+                    branch.children[i as usize] = Some(keccak256(&[i]));
+                }
+            }
+            black_box(cached.hash())
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert, bench_lookup, bench_verify, bench_keccak, bench_rlp, bench_cached_node);
+criterion_main!(benches);
@@ -0,0 +1,66 @@
+//! Compare walking a key's nibbles in place with `NibbleSlice` against unpacking them
+//! with `to_nibbles`, and time verifying 10000 proofs, which walks keys this way.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --bench nibble_slice
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mpt_lib::{keccak256, to_nibbles, verify_proof, MPTBuilder, NibbleSlice};
+
+/// A 64-nibble key walked as a proof walks it: an extension of 5 nibbles, then branches
+fn bench_nibble_path(c: &mut Criterion) {
+    let key = keccak256(b"key");
+    let extension = to_nibbles(&key[..3])[..5].to_vec();
+    let mut group = c.benchmark_group("walk_key_64_nibbles");
+    
+    group.bench_function("to_nibbles", |bench| {
+        bench.iter(|| {
+            let nibbles = to_nibbles(black_box(&key));
+            let mut rest = nibbles.strip_prefix(&extension[..]).unwrap();
+            let mut sum = 0u32;
+            while let Some((&nibble, tail)) = rest.split_first() {
+                sum += nibble as u32;
+                rest = tail;
+            }
+            black_box(sum)
+        })
+    });
+    group.bench_function("nibble_slice", |bench| {
+        bench.iter(|| {
+            let nibbles = NibbleSlice::new(black_box(&key));
+            assert!(nibbles.starts_with_nibbles(&extension));
+            let mut rest = nibbles.advance(extension.len());
+            let mut sum = 0u32;
+            while !rest.is_empty() {
+                sum += rest.get(0) as u32;
+                rest = rest.advance(1);
+            }
+            black_box(sum)
+        })
+    });
+    
+    group.finish();
+}
+
+fn bench_verify_10000(c: &mut Criterion) {
+    let mut builder = MPTBuilder::new();
+    let entries: Vec<_> = (0..10_000u32).map(|i| (keccak256(&i.to_be_bytes()), i.to_be_bytes().repeat(10))).collect();
+    for (key, value) in &entries {
+        builder.insert(key, value);
+    }
+    let root = builder.root().unwrap();
+    let proofs: Vec<_> = entries.iter().map(|(key, _)| builder.get_proof(key).unwrap()).collect();
+    
+    c.bench_function("verify_10000_proofs", |bench| {
+        bench.iter(|| {
+            entries
+                .iter()
+                .zip(&proofs)
+                .all(|((key, value), proof)| verify_proof(black_box(&root), key, value, proof))
+        })
+    });
+}
+
+criterion_group!(benches, bench_nibble_path, bench_verify_10000);
+criterion_main!(benches);
@@ -0,0 +1,72 @@
+//! Compare inserting 5000 entries with plain Keccak-256 against the caching
+//! `TrieHasher`, and rewriting them with unchanged values, where nodes repeat.
+//!
+//! ```shell
+//! cargo bench -p mpt-lib --bench trie_hasher
+//! ```
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use mpt_lib::{keccak256, Keccak256Hasher, MPTBuilder, NodeHasher, TrieHasher};
+
+fn insert_all<H: NodeHasher>(builder: &mut MPTBuilder<H>, n: u32) {
+    for i in 0..n {
+        builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+    }
+}
+
+fn bench_trie_hasher(c: &mut Criterion) {
+    let mut cached = MPTBuilder::with_hasher(TrieHasher::new(Keccak256Hasher));
+    insert_all(&mut cached, 5_000);
+    let hasher = cached.hasher();
+    println!("insert 5000: {} cache hits, {} misses", hasher.hits(), hasher.misses());
+    insert_all(&mut cached, 5_000);
+    let hasher = cached.hasher();
+    println!("rewrite 5000 unchanged: {} cache hits, {} misses", hasher.hits(), hasher.misses());
+
+    let mut group = c.benchmark_group("insert_5000");
+    group.sample_size(10);
+    group.bench_function("keccak256", |b| {
+        b.iter(|| {
+            let mut builder = MPTBuilder::with_hasher(Keccak256Hasher);
+            insert_all(&mut builder, 5_000);
+            black_box(builder.root())
+        })
+    });
+    group.bench_function("trie_hasher", |b| {
+        b.iter(|| {
+            let mut builder = MPTBuilder::with_hasher(TrieHasher::new(Keccak256Hasher));
+            insert_all(&mut builder, 5_000);
+            black_box(builder.root())
+        })
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("rewrite_5000_unchanged");
+    group.sample_size(10);
+    let mut plain = MPTBuilder::new();
+    insert_all(&mut plain, 5_000);
+    group.bench_function("keccak256", |b| {
+        b.iter_batched(
+            || plain.clone(),
+            |mut builder| {
+                insert_all(&mut builder, 5_000);
+                black_box(builder.root())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("trie_hasher", |b| {
+        b.iter_batched(
+            || cached.clone(),
+            |mut builder| {
+                insert_all(&mut builder, 5_000);
+                black_box(builder.root())
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_trie_hasher);
+criterion_main!(benches);
@@ -0,0 +1,149 @@
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use crate::mpt::{verified_storage_word, verify_account_proof};
+use crate::types::{EthAccount, VerificationError, H256};
+
+/// A storage slot proven against a state root: state root -> account -> storage slot
+///
+/// `account_proof` must show `account` at `address`, and `storage_proof` the slot under
+/// the account's `storage_root`. The slot value is read from the storage proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccountProofPipeline {
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub state_root: H256,
+    pub address: [u8; 20],
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub slot: H256,
+    pub account: EthAccount,
+    pub account_proof: Vec<Vec<u8>>,
+    pub storage_proof: Vec<Vec<u8>>,
+}
+
+/// Result of verifying an `AccountProofPipeline`
+///
+/// `value` is the slot value as a big-endian u256, zero unless `verified`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountStorageOutput {
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub state_root: H256,
+    pub address: [u8; 20],
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub slot: H256,
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub value: H256,
+    pub verified: bool,
+}
+
+/// Verify the account, then the storage slot, and return the slot value as a big-endian u256
+///
+/// A storage proof that the slot is absent, such as an empty proof against the empty
+/// storage root, proves it zero.
+pub fn verify_account_storage_pipeline(pipeline: &AccountProofPipeline) -> Result<H256, VerificationError> {
+    let AccountProofPipeline { state_root, address, slot, account, account_proof, storage_proof } = pipeline;
+    verify_account_proof(state_root, address, account, account_proof)
+        .map_err(|_| VerificationError::InvalidAccountProof)?;
+
+    verified_storage_word(&account.storage_root, slot, storage_proof)
+}
+
+impl AccountProofPipeline {
+    /// Verify the pipeline into the committed output form
+    pub fn verify(&self) -> AccountStorageOutput {
+        let value = verify_account_storage_pipeline(self);
+        AccountStorageOutput {
+            state_root: self.state_root,
+            address: self.address,
+            slot: self.slot,
+            value: value.unwrap_or_default(),
+            verified: value.is_ok(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::builder::MPTBuilder;
+    use crate::mpt::EMPTY_TRIE_ROOT;
+    use crate::rlp_encoding::{encode_bytes, encode_u256, keccak256};
+    use crate::state_trie::WorldStateTrieBuilder;
+    use crate::test_utils::word;
+
+    const ADDRESS: [u8; 20] = [0x42; 20];
+
+    /// A pipeline for `slot` in a storage trie holding `slots` (as raw RLP values), with an
+    /// exclusion proof if `slot` is unset
+    fn pipeline(slots: &[(H256, Vec<u8>)], slot: H256) -> AccountProofPipeline {
+        let mut storage = MPTBuilder::new();
+        for (key, value) in slots {
+            storage.insert(&keccak256(key), value);
+        }
+        let account = EthAccount {
+            nonce: 3,
+            balance: word(1_000),
+            storage_root: storage.root_or_empty(),
+            code_hash: keccak256(b"code"),
+        };
+
+        let mut state = WorldStateTrieBuilder::new();
+        state.insert_account(&[0x01; 20], &EthAccount { nonce: 9, ..account.clone() });
+        let state_root = state.insert_account(&ADDRESS, &account);
+
+        AccountProofPipeline {
+            state_root,
+            address: ADDRESS,
+            slot,
+            account_proof: state.get_account_proof(&ADDRESS).unwrap(),
+            storage_proof: storage
+                .get_exclusion_proof(&keccak256(&slot))
+                .or_else(|| storage.get_proof(&keccak256(&slot)))
+                .unwrap(),
+            account,
+        }
+    }
+
+    #[test]
+    fn test_pipeline_returns_slot_value() {
+        let slots = [(word(0), encode_u256(&word(1_000_250))), (word(7), encode_u256(&word(99)))];
+        let input = pipeline(&slots, word(0));
+        assert_eq!(verify_account_storage_pipeline(&input), Ok(word(1_000_250)));
+
+        let output = input.verify();
+        assert!(output.verified);
+        assert_eq!(output.value, word(1_000_250));
+        assert_eq!(output.state_root, input.state_root);
+
+        // An account without storage proves every slot zero
+        let input = pipeline(&[], word(5));
+        assert_eq!(input.account.storage_root, EMPTY_TRIE_ROOT);
+        assert_eq!(verify_account_storage_pipeline(&input), Ok([0u8; 32]));
+
+        // So does a proof that an unset slot is absent from a non-empty storage trie
+        let input = pipeline(&slots, word(5));
+        assert!(!input.storage_proof.is_empty());
+        assert_eq!(verify_account_storage_pipeline(&input), Ok([0u8; 32]));
+    }
+
+    #[test]
+    fn test_pipeline_rejects_broken_links() {
+        let slots = [(word(0), encode_u256(&word(5))), (word(7), encode_u256(&word(99)))];
+
+        let mut input = pipeline(&slots, word(0));
+        input.account.balance = word(2_000);
+        assert_eq!(verify_account_storage_pipeline(&input), Err(VerificationError::InvalidAccountProof));
+
+        let mut input = pipeline(&slots, word(0));
+        input.address = [0x01; 20];
+        assert_eq!(verify_account_storage_pipeline(&input), Err(VerificationError::InvalidAccountProof));
+
+        let mut input = pipeline(&slots, word(0));
+        input.slot = word(7);
+        assert_eq!(verify_account_storage_pipeline(&input), Err(VerificationError::InvalidStorageProof));
+        let output = input.verify();
+        assert!(!output.verified);
+        assert_eq!(output.value, [0u8; 32]);
+
+        let input = pipeline(&[(word(0), encode_bytes(&[0xff; 33]))], word(0));
+        assert_eq!(verify_account_storage_pipeline(&input), Err(VerificationError::InvalidStorageValue));
+    }
+}
@@ -0,0 +1,364 @@
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use crate::mpt::{verified_storage_word, verify_account_proof_rlp};
+use crate::rlp_encoding::{decode_bytes, decode_list, decode_storage_word, encode_bytes, encode_list, encode_u256, encode_u64, keccak256};
+use crate::types::{check_proof_version, EthAccount, MPTProofInput, VerificationError, H256};
+
+/// Index of `stateRoot` in the RLP field list of an Ethereum block header
+const STATE_ROOT_INDEX: usize = 3;
+
+/// A storage slot proven from a block hash: header -> state root -> account -> storage slot
+///
+/// `account_proof.value` is the RLP-encoded account and `storage_proof.value` the RLP-encoded
+/// slot value, as stored in the tries (empty for a zero slot). The proofs' `key` and `root` fields are not trusted:
+/// keys are derived from `address` and `slot`, and roots from the header and the account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlockHeaderProofInput {
+    pub rlp_header: Vec<u8>,
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub block_hash: H256,
+    pub address: [u8; 20],
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub slot: H256,
+    pub account_proof: MPTProofInput,
+    pub storage_proof: MPTProofInput,
+}
+
+/// Result of verifying a `BlockHeaderProofInput`
+///
+/// `value` is the slot value as a big-endian u256, zero unless `verified`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeaderProofOutput {
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub block_hash: H256,
+    pub address: [u8; 20],
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub slot: H256,
+    #[cfg_attr(feature = "serde-hex", serde(with = "crate::types::h256_hex"))]
+    pub value: H256,
+    pub verified: bool,
+}
+
+/// The fields of an Ethereum block header, in spec order
+///
+/// Fields added by later forks are `None` for blocks before the fork. They are encoded
+/// in order up to the first `None`, so `hash` is the block hash for any fork up to Prague.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EthBlockHeader {
+    pub parent_hash: H256,
+    pub ommers_hash: H256,
+    pub beneficiary: [u8; 20],
+    pub state_root: H256,
+    pub transactions_root: H256,
+    pub receipts_root: H256,
+    /// 256-byte bloom filter
+    pub logs_bloom: Vec<u8>,
+    /// Big-endian u256, zero since the merge
+    pub difficulty: H256,
+    pub block_number: u64,
+    pub gas_limit: u64,
+    pub gas_used: u64,
+    pub timestamp: u64,
+    pub extra_data: Vec<u8>,
+    pub mix_hash: H256,
+    pub nonce: [u8; 8],
+    /// London
+    pub base_fee_per_gas: Option<u64>,
+    /// Shanghai
+    pub withdrawals_root: Option<H256>,
+    /// Cancun
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    pub parent_beacon_block_root: Option<H256>,
+    /// Prague
+    pub requests_hash: Option<H256>,
+}
+
+impl EthBlockHeader {
+    /// Canonical RLP encoding, as hashed into the block hash
+    pub fn to_rlp(&self) -> Vec<u8> {
+        let mut fields = Vec::from([
+            encode_bytes(&self.parent_hash),
+            encode_bytes(&self.ommers_hash),
+            encode_bytes(&self.beneficiary),
+            encode_bytes(&self.state_root),
+            encode_bytes(&self.transactions_root),
+            encode_bytes(&self.receipts_root),
+            encode_bytes(&self.logs_bloom),
+            encode_u256(&self.difficulty),
+            encode_u64(self.block_number),
+            encode_u64(self.gas_limit),
+            encode_u64(self.gas_used),
+            encode_u64(self.timestamp),
+            encode_bytes(&self.extra_data),
+            encode_bytes(&self.mix_hash),
+            encode_bytes(&self.nonce),
+        ]);
+        let fork_fields = [
+            self.base_fee_per_gas.map(encode_u64),
+            self.withdrawals_root.map(|root| encode_bytes(&root)),
+            self.blob_gas_used.map(encode_u64),
+            self.excess_blob_gas.map(encode_u64),
+            self.parent_beacon_block_root.map(|root| encode_bytes(&root)),
+            self.requests_hash.map(|hash| encode_bytes(&hash)),
+        ];
+        fields.extend(fork_fields.into_iter().map_while(|field| field));
+        encode_list(&fields)
+    }
+
+    /// The block hash: keccak256 of `to_rlp`
+    pub fn hash(&self) -> H256 {
+        keccak256(&self.to_rlp())
+    }
+}
+
+/// Verify an RLP-encoded account against the state root of `header`
+///
+/// The root comes from the header itself, so a caller committing `header.hash()` proves
+/// the account in that block without trusting a separate state root.
+pub fn verify_state_proof_in_block(
+    header: &EthBlockHeader,
+    account_address: &[u8; 20],
+    account_rlp: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<(), VerificationError> {
+    verify_account_proof_rlp(&header.state_root, account_address, account_rlp, proof)
+}
+
+/// Extract `stateRoot` from an RLP-encoded block header
+pub fn header_state_root(rlp_header: &[u8]) -> Result<H256, &'static str> {
+    let fields = decode_list(rlp_header)?;
+    let state_root = fields.get(STATE_ROOT_INDEX).ok_or("Invalid header: missing stateRoot")?;
+    decode_bytes(state_root)?
+        .try_into()
+        .map_err(|_| "Invalid header: stateRoot must be 32 bytes")
+}
+
+impl BlockHeaderProofInput {
+    /// Verify the whole chain from `block_hash` down to the storage slot
+    ///
+    /// A zero slot is not stored, so its storage proof is an exclusion proof with an empty
+    /// value, and verifies with a zero `value`.
+    pub fn verify(&self) -> BlockHeaderProofOutput {
+        let value = self.verified_value();
+        BlockHeaderProofOutput {
+            block_hash: self.block_hash,
+            address: self.address,
+            slot: self.slot,
+            value: value.unwrap_or_default(),
+            verified: value.is_some(),
+        }
+    }
+
+    /// The proven slot value, or `None` if any link in the chain fails
+    fn verified_value(&self) -> Option<H256> {
+        check_proof_version(self.account_proof.version).ok()?;
+        check_proof_version(self.storage_proof.version).ok()?;
+        if keccak256(&self.rlp_header) != self.block_hash {
+            return None;
+        }
+        let state_root = header_state_root(&self.rlp_header).ok()?;
+
+        let account_rlp = &self.account_proof.value;
+        verify_account_proof_rlp(&state_root, &self.address, account_rlp, &self.account_proof.proof).ok()?;
+        let account = EthAccount::from_rlp(account_rlp).ok()?;
+
+        // An empty claimed value, like a proof of absence, reads as zero
+        let value = verified_storage_word(&account.storage_root, &self.slot, &self.storage_proof.proof).ok()?;
+        (decode_storage_word(&self.storage_proof.value)? == value).then_some(value)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::builder::MPTBuilder;
+    use crate::path::account_address_to_state_key;
+    use crate::rlp_encoding::{encode_bytes, encode_list, encode_u256};
+    use crate::state_trie::WorldStateTrieBuilder;
+    use crate::test_utils::word;
+    use crate::types::{ProofType, PROOF_FORMAT_VERSION};
+
+    const ADDRESS: [u8; 20] = [0x42; 20];
+
+    /// A pre-London header with the given state root
+    fn header(state_root: &H256) -> Vec<u8> {
+        let fields: Vec<Vec<u8>> = vec![
+            encode_bytes(&[0x11; 32]), // parentHash
+            encode_bytes(&[0x1d; 32]), // ommersHash
+            encode_bytes(&[0xbe; 20]), // beneficiary
+            encode_bytes(state_root),
+            encode_bytes(&[0x22; 32]), // transactionsRoot
+            encode_bytes(&[0x33; 32]), // receiptsRoot
+            encode_bytes(&[0u8; 256]), // logsBloom
+            encode_bytes(&[0x02]),     // difficulty
+            encode_bytes(&[0x01, 0x00]), // number
+            encode_bytes(&[0x01, 0xc9, 0xc3, 0x80]), // gasLimit
+            encode_bytes(&[]),         // gasUsed
+            encode_bytes(&[0x5f, 0x00, 0x00, 0x00]), // timestamp
+            encode_bytes(&[]),         // extraData
+            encode_bytes(&[0x44; 32]), // mixHash
+            encode_bytes(&[0u8; 8]),   // nonce
+        ];
+        encode_list(&fields)
+    }
+
+    fn eth_header(state_root: &H256) -> EthBlockHeader {
+        EthBlockHeader {
+            parent_hash: [0x11; 32],
+            ommers_hash: [0x1d; 32],
+            beneficiary: [0xbe; 20],
+            state_root: *state_root,
+            transactions_root: [0x22; 32],
+            receipts_root: [0x33; 32],
+            logs_bloom: vec![0; 256],
+            difficulty: word(2),
+            block_number: 256,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: 0x5f00_0000,
+            extra_data: vec![],
+            mix_hash: [0x44; 32],
+            nonce: [0; 8],
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            requests_hash: None,
+        }
+    }
+
+    fn chain_input(slot: H256, value: H256) -> BlockHeaderProofInput {
+        let mut storage = MPTBuilder::new();
+        storage.insert(&keccak256(&word(7)), &encode_u256(&word(99)));
+        let storage_root = storage.insert(&keccak256(&slot), &encode_u256(&value));
+
+        let account = EthAccount {
+            nonce: 1,
+            balance: word(1_000),
+            storage_root,
+            code_hash: keccak256(b"code"),
+        };
+        let mut state = WorldStateTrieBuilder::new();
+        state.insert_account(&[0x01; 20], &EthAccount { storage_root: [0x56; 32], ..account.clone() });
+        let state_root = state.insert_account(&ADDRESS, &account);
+
+        let rlp_header = header(&state_root);
+        BlockHeaderProofInput {
+            block_hash: keccak256(&rlp_header),
+            rlp_header,
+            address: ADDRESS,
+            slot,
+            account_proof: MPTProofInput {
+                version: PROOF_FORMAT_VERSION,
+                key: account_address_to_state_key(&ADDRESS).to_vec(),
+                value: account.to_rlp(),
+                proof: state.get_account_proof(&ADDRESS).unwrap(),
+                root: state_root,
+                proof_type: ProofType::Inclusion,
+            },
+            storage_proof: MPTProofInput {
+                version: PROOF_FORMAT_VERSION,
+                key: keccak256(&slot).to_vec(),
+                value: encode_u256(&value),
+                proof: storage.get_proof(&keccak256(&slot)).unwrap(),
+                root: storage_root,
+                proof_type: ProofType::Inclusion,
+            },
+        }
+    }
+
+    #[test]
+    fn test_block_header_proof_verifies() {
+        let input = chain_input(word(0), word(1_000_250));
+        let output = input.verify();
+        assert!(output.verified);
+        assert_eq!(output.value, word(1_000_250));
+        assert_eq!(output.block_hash, keccak256(&input.rlp_header));
+        assert_eq!(output.address, ADDRESS);
+        assert_eq!(output.slot, word(0));
+    }
+
+    #[test]
+    fn test_block_header_proof_zero_slot() {
+        let mut storage = MPTBuilder::new();
+        storage.insert(&keccak256(&word(7)), &encode_u256(&word(99)));
+        storage.insert(&keccak256(&word(0)), &encode_u256(&word(5)));
+
+        // Slot 4 is unset, so its storage proof shows it absent
+        let mut input = chain_input(word(0), word(5));
+        input.slot = word(4);
+        input.storage_proof.value = Vec::new();
+        input.storage_proof.proof = storage.get_exclusion_proof(&keccak256(&word(4))).unwrap();
+        input.storage_proof.proof_type = ProofType::Exclusion;
+        let output = input.verify();
+        assert!(output.verified);
+        assert_eq!(output.value, [0u8; 32]);
+
+        // The proof shows no value, so claiming one fails
+        input.storage_proof.value = encode_u256(&word(1));
+        assert!(!input.verify().verified);
+    }
+
+    #[test]
+    fn test_eth_block_header_rlp() {
+        let header_fields = eth_header(&[0x55; 32]);
+        assert_eq!(header_fields.to_rlp(), header(&[0x55; 32]));
+        assert_eq!(header_fields.hash(), keccak256(&header(&[0x55; 32])));
+        assert_eq!(header_state_root(&header_fields.to_rlp()), Ok([0x55; 32]));
+
+        // Fork fields are appended in order, stopping at the first missing one
+        let london = EthBlockHeader { base_fee_per_gas: Some(7), ..header_fields.clone() };
+        let fields = decode_list(&london.to_rlp()).unwrap();
+        assert_eq!(fields.len(), 16);
+        assert_eq!(fields[15], encode_u64(7));
+        let gap = EthBlockHeader { withdrawals_root: Some([0x66; 32]), ..header_fields };
+        assert_eq!(decode_list(&gap.to_rlp()).unwrap().len(), 15);
+    }
+
+    #[test]
+    fn test_verify_state_proof_in_block() {
+        let input = chain_input(word(0), word(5));
+        let header = eth_header(&input.account_proof.root);
+        let account_rlp = &input.account_proof.value;
+        let proof = &input.account_proof.proof;
+
+        assert_eq!(verify_state_proof_in_block(&header, &ADDRESS, account_rlp, proof), Ok(()));
+        assert!(verify_state_proof_in_block(&header, &[0x01; 20], account_rlp, proof).is_err());
+        assert_eq!(
+            verify_state_proof_in_block(&eth_header(&[0xaa; 32]), &ADDRESS, account_rlp, proof),
+            Err(VerificationError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_block_header_proof_rejects_broken_links() {
+        // Block hash does not commit to the header
+        let mut input = chain_input(word(0), word(5));
+        input.block_hash[0] ^= 1;
+        assert!(!input.verify().verified);
+
+        // Header committing to a different state root, with a matching block hash
+        let mut input = chain_input(word(0), word(5));
+        input.rlp_header = header(&[0xaa; 32]);
+        input.block_hash = keccak256(&input.rlp_header);
+        assert!(!input.verify().verified);
+
+        // Proofs for a different address or slot than claimed
+        let mut input = chain_input(word(0), word(5));
+        input.address = [0x01; 20];
+        assert!(!input.verify().verified);
+        let mut input = chain_input(word(0), word(5));
+        input.slot = word(7);
+        let output = input.verify();
+        assert!(!output.verified);
+        assert_eq!(output.value, [0u8; 32]);
+    }
+
+    #[test]
+    fn test_header_state_root() {
+        assert_eq!(header_state_root(&header(&[0x5a; 32])), Ok([0x5a; 32]));
+        assert!(header_state_root(&encode_list(&[encode_bytes(b"short")])).is_err());
+    }
+}
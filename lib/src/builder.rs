@@ -1,45 +1,779 @@
-use crate::types::H256;
-use crate::rlp_encoding::{encode_bytes, encode_list, keccak256, decode_list, decode_bytes};
+use crate::types::{
+    AbbrevHash, BranchNode, ExtensionNode, LeafNode, MPTBatchProofInput, MPTProofInput, Node, ProofType, SiblingProof,
+    H256, PROOF_FORMAT_VERSION,
+};
+use crate::hasher::{Keccak256Hasher, NodeHasher};
+use crate::rlp_encoding::{
+    encode_bytes, encode_hash_item, encode_list, encode_list_into, decode_list, decode_bytes, hex_string, keccak256,
+    rlp_pretty_print, RLP_EMPTY,
+};
 use crate::path::{to_nibbles, encode_path, decode_path};
-use std::collections::HashMap;
-
-#[derive(Clone, Debug)]
-enum TrieNode {
-    Empty,
-    Leaf(Vec<u8>, Vec<u8>),      // (path, value)
-    Extension(Vec<u8>, H256),     // (path, child_hash)
-    Branch([Option<H256>; 16], Option<Vec<u8>>), // (children, value)
+#[cfg(not(feature = "simd"))]
+use crate::path::common_prefix_len;
+#[cfg(feature = "simd")]
+use crate::path::common_prefix_len_simd as common_prefix_len;
+use crate::compact_proof::CompactProof;
+use crate::read_only::ReadOnlyMPT;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Deref;
+use std::sync::Arc;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// A dangling node reference found by `MPTBuilder::verify_internal_consistency`
+/// 
+/// For a missing root or an orphaned cache entry, `referenced_by` is the missing hash itself.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ConsistencyError {
+    pub missing_node: H256,
+    pub referenced_by: H256,
+}
+
+impl fmt::Debug for ConsistencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsistencyError")
+            .field("missing_node", &AbbrevHash(self.missing_node))
+            .field("referenced_by", &AbbrevHash(self.referenced_by))
+            .finish()
+    }
+}
+
+/// Error returned by `MPTBuilder::update` when the key is not in the trie
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpdateError {
+    KeyNotFound,
+}
+
+/// Error returned when inserting into an `MPTBuilder`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertError {
+    /// `insert_new` found the key already in the trie
+    KeyAlreadyExists,
+    /// A nibble path contained a value above 15
+    InvalidNibble(u8),
+}
+
+/// Error returned by `MPTBuilder::merge` for a key holding different values in the two tries
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MergeError {
+    ConflictingKey(Vec<u8>),
+}
+
+/// Error returned by `MPTBuilder::apply_diff` when the diff does not fit the trie
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ApplyDiffError {
+    /// A deleted key is not in the trie
+    MissingDeleteTarget(Vec<u8>),
+    /// An updated key is not in the trie, or is deleted by the same diff
+    MissingUpdateTarget(Vec<u8>),
+    /// An inserted key is already in the trie
+    ConflictingInsert(Vec<u8>),
+}
+
+/// Error returned by `MPTBuilder::repair_from_partial_proof`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepairError {
+    /// The first node does not hash to the root, a later node is not referenced by an
+    /// earlier one, or a node is not a valid trie node
+    InvalidProof,
+    /// The builder already holds different content under this hash
+    ConflictingNode(H256),
+}
+
+/// Error returned by `MPTBuilder::compact_decode`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompactDecodeError {
+    /// The input ends in the middle of a node
+    UnexpectedEnd,
+    /// A node starts with an unknown type tag
+    InvalidTag(u8),
+    /// A length does not fit in a usize
+    InvalidVarint,
+    /// A child does not hash to the reference its parent records
+    ChildHashMismatch,
+    /// Bytes remain after the root's subtrie
+    TrailingBytes,
 }
 
+/// The changes turning one trie into another, from `MPTBuilder::trie_diff`
+/// 
+/// Each list is sorted by key; `updates` hold the new value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieDiff {
+    pub deletions: Vec<Vec<u8>>,
+    pub insertions: Vec<(Vec<u8>, Vec<u8>)>,
+    pub updates: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Writes buffered by `MPTBuilder::insert_incremental` until the next `commit`
+/// 
+/// Writes are keyed by nibble path, so a key written twice keeps only its last value.
+#[derive(Clone, Debug, Default)]
+pub struct IncrementalHasher {
+    pending: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl IncrementalHasher {
+    /// Number of distinct keys waiting to be committed
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+    
+    /// Whether there is nothing to commit
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// Node storage keyed by hash; sharded and lockable per entry under the `concurrent` feature
+#[cfg(not(feature = "concurrent"))]
+type NodeMap<V> = std::collections::HashMap<H256, V>;
+#[cfg(feature = "concurrent")]
+type NodeMap<V> = dashmap::DashMap<H256, V>;
+
+/// Trie nodes as `(hash, rlp)` pairs sorted by hash, for deterministic serialization
+pub type SortedNodeList = Vec<(H256, Vec<u8>)>;
+
 /// An in-memory Merkle Patricia Trie builder with full insertion logic
-pub struct MPTBuilder {
+/// 
+/// Nodes are referenced by `H` hashes; the default `Keccak256Hasher` builds Ethereum tries.
+/// 
+/// The builder is `Send + Sync` and lookups such as `get` and `get_proof` take `&self`, so
+/// a built trie can serve proofs from many threads at once. Writes such as `insert` and
+/// `insert_at` take `&mut self` and must be synchronized externally (e.g. behind an
+/// `RwLock`); `into_read_only` gives a cheaply cloneable handle once the trie is final.
+/// With the `concurrent` feature the node maps are `DashMap`s, so `get_proof_concurrent`
+/// can also fill the decoded node cache from shared references.
+#[derive(Clone)]
+pub struct MPTBuilder<H: NodeHasher = Keccak256Hasher> {
     // Store nodes by their hash
-    nodes: HashMap<H256, Vec<u8>>,
+    nodes: NodeMap<Vec<u8>>,
     // In-memory representation of nodes for easier manipulation
-    node_cache: HashMap<H256, TrieNode>,
+    node_cache: NodeMap<Node>,
     root: Option<H256>,
+    hasher: H,
+    // Uncommitted writes from `insert_incremental`
+    incremental: IncrementalHasher,
 }
 
 impl MPTBuilder {
     pub fn new() -> Self {
+        Self::with_hasher(Keccak256Hasher)
+    }
+    
+    /// Rebuild a trie from a node list produced by `to_sorted_node_list`
+    pub fn from_sorted_node_list(nodes: SortedNodeList, root: Option<H256>) -> Self {
+        Self {
+            nodes: nodes.into_iter().collect(),
+            node_cache: NodeMap::new(),
+            root,
+            hasher: Keccak256Hasher,
+            incremental: IncrementalHasher::default(),
+        }
+    }
+    
+    /// Read a trie written by `serialize_to_writer`
+    /// 
+    /// Each node is checked against its hash, so corrupted input fails with
+    /// `ErrorKind::InvalidData` instead of producing a broken trie.
+    pub fn deserialize_from_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut root = [0u8; 32];
+        reader.read_exact(&mut root)?;
+        let mut nodes = SortedNodeList::new();
+        
+        loop {
+            let mut hash = [0u8; 32];
+            // End of input is only valid between records
+            match reader.read(&mut hash[..1])? {
+                0 => break,
+                _ => reader.read_exact(&mut hash[1..])?,
+            }
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            let mut rlp = vec![0u8; u32::from_be_bytes(len) as usize];
+            reader.read_exact(&mut rlp)?;
+            if keccak256(&rlp) != hash {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "node does not match its hash"));
+            }
+            nodes.push((hash, rlp));
+        }
+        
+        let root = (root != crate::mpt::EMPTY_TRIE_ROOT).then_some(root);
+        Ok(Self::from_sorted_node_list(nodes, root))
+    }
+    
+    /// Read a trie written by `compact_encode`
+    /// 
+    /// Every child is rebuilt from the records that follow its parent and must hash to the
+    /// reference the parent records, so corrupted input is rejected.
+    pub fn compact_decode(data: &[u8]) -> Result<Self, CompactDecodeError> {
+        let mut builder = Self::new();
+        let mut input = data;
+        if !input.is_empty() {
+            builder.root = Some(builder.decode_compact_node(&mut input)?);
+        }
+        if !input.is_empty() {
+            return Err(CompactDecodeError::TrailingBytes);
+        }
+        Ok(builder)
+    }
+    
+    /// Decode one `compact_encode` record and the subtrie after it, returning its hash
+    fn decode_compact_node(&mut self, input: &mut &[u8]) -> Result<H256, CompactDecodeError> {
+        let node = match take_bytes(input, 1)?[0] {
+            COMPACT_LEAF => {
+                let path = read_compact_path(input)?;
+                let len = read_varint(input)?;
+                Node::Leaf(LeafNode { path, value: take_bytes(input, len)?.to_vec() })
+            }
+            COMPACT_EXTENSION => {
+                let path = read_compact_path(input)?;
+                let child = read_hash(input)?;
+                if self.decode_compact_node(input)? != child {
+                    return Err(CompactDecodeError::ChildHashMismatch);
+                }
+                Node::Extension(ExtensionNode { path, child })
+            }
+            COMPACT_BRANCH => {
+                let bitmap = u16::from_be_bytes(take_bytes(input, 2)?.try_into().unwrap());
+                let mut children: [Option<H256>; 16] = Default::default();
+                for (i, child) in children.iter_mut().enumerate() {
+                    if bitmap & (1 << i) != 0 {
+                        *child = Some(read_hash(input)?);
+                    }
+                }
+                let len = read_varint(input)?;
+                let value = take_bytes(input, len)?.to_vec();
+                for child in children.iter().flatten() {
+                    if self.decode_compact_node(input)? != *child {
+                        return Err(CompactDecodeError::ChildHashMismatch);
+                    }
+                }
+                Node::Branch(BranchNode { children, value: (!value.is_empty()).then_some(value) })
+            }
+            tag => return Err(CompactDecodeError::InvalidTag(tag)),
+        };
+        Ok(self.store_node(node))
+    }
+    
+    /// Build a trie from key-value pairs in one pass
+    /// 
+    /// The pairs are sorted and the trie is constructed bottom-up by partitioning on the
+    /// next nibble, so no node is created and then restructured as with repeated `insert`
+    /// calls. The root matches inserting the pairs in order; for duplicate keys the last
+    /// value wins.
+    pub fn from_flat(pairs: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        let mut entries: Vec<(Vec<u8>, Vec<u8>)> = pairs
+            .into_iter()
+            .map(|(key, value)| (to_nibbles(&key), value))
+            .collect();
+        // Stable sort keeps insertion order among duplicates; keep the last of each run
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.reverse();
+        entries.dedup_by(|later, earlier| later.0 == earlier.0);
+        entries.reverse();
+        
+        let mut builder = Self::new();
+        builder.root = builder.build_sorted(&entries, 0);
+        builder
+    }
+}
+
+#[cfg(feature = "poseidon")]
+impl MPTBuilder<crate::poseidon::PoseidonHasher> {
+    /// Create an empty trie whose nodes are hashed with Poseidon instead of Keccak-256
+    /// 
+    /// Proofs from it verify with `verify_proof_with_hasher(&PoseidonHasher, ...)`.
+    pub fn new_poseidon() -> Self {
+        Self::with_hasher(crate::poseidon::PoseidonHasher)
+    }
+}
+
+impl<H: NodeHasher> MPTBuilder<H> {
+    /// Create an empty trie whose nodes are hashed with `hasher`
+    pub fn with_hasher(hasher: H) -> Self {
         Self {
-            nodes: HashMap::new(),
-            node_cache: HashMap::new(),
+            nodes: NodeMap::new(),
+            node_cache: NodeMap::new(),
             root: None,
+            hasher,
+            incremental: IncrementalHasher::default(),
+        }
+    }
+    
+    /// Empty the trie, keeping its hasher and the capacity of its node maps for reuse
+    /// 
+    /// Pending `insert_incremental` writes are discarded.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.node_cache.clear();
+        self.root = None;
+        self.incremental = IncrementalHasher::default();
+    }
+    
+    /// Replace the trie with a node list from `to_sorted_node_list`, reusing its allocations
+    /// 
+    /// Same as `from_sorted_node_list`, in place; the nodes are not checked against `root`.
+    pub fn reset_to(&mut self, nodes: SortedNodeList, root: Option<H256>) {
+        self.clear();
+        self.nodes.extend(nodes);
+        self.root = root;
+    }
+    
+    /// Prune every node more than `max_depth` proof entries below the root, returning the root
+    /// 
+    /// Non-standard extension for "shallow proof" protocols. A node's depth is its index in
+    /// `get_proof`, so embedded nodes share their parent's depth and are kept with it. The
+    /// kept nodes still reference pruned children by hash, which serve as sentinels: the
+    /// root is unchanged, and `get_truncated_proof` works up to `max_depth`, but reads and
+    /// writes below it do not. Nodes unreachable from the root are dropped too, and pending
+    /// `insert_incremental` writes are discarded.
+    pub fn truncate_to_depth(&mut self, max_depth: usize) -> H256 {
+        let mut keep = HashSet::new();
+        let mut stack: Vec<(H256, usize)> = self.root.into_iter().map(|root| (root, 0)).collect();
+        while let Some((hash, depth)) = stack.pop() {
+            if depth >= max_depth || !keep.insert(hash) {
+                continue;
+            }
+            let children = match self.peek_node(&hash) {
+                Some(Node::Extension(extension)) => vec![extension.child],
+                Some(Node::Branch(branch)) => branch.children.iter().flatten().copied().collect(),
+                _ => Vec::new(),
+            };
+            for child in children {
+                let embedded = self.nodes.get(&child).is_some_and(|rlp| rlp.len() < 32);
+                stack.push((child, if embedded { depth } else { depth + 1 }));
+            }
         }
+        
+        self.nodes.retain(|hash, _| keep.contains(hash));
+        self.node_cache.retain(|hash, _| keep.contains(hash));
+        self.incremental = IncrementalHasher::default();
+        self.root_or_empty()
     }
     
     /// Insert a key-value pair and return the new root
+    /// 
+    /// Any writes pending from `insert_incremental` are committed first, so they cannot
+    /// later overwrite this one.
     pub fn insert(&mut self, key: &[u8], value: &[u8]) -> H256 {
+        if !self.incremental.is_empty() {
+            self.commit();
+        }
         let nibbles = to_nibbles(key);
-        let new_root = self.insert_at(self.root, &nibbles, value.to_vec());
+        let new_root = self
+            .insert_at(self.root, &nibbles, value.to_vec())
+            .expect("to_nibbles only yields nibbles below 16");
         self.root = Some(new_root);
         new_root
     }
     
+    /// Overwrite the value of an existing key and return the new root
+    /// 
+    /// Unlike `insert`, this never creates an entry: an absent key is an error and the
+    /// trie is left unchanged.
+    pub fn update(&mut self, key: &[u8], new_value: &[u8]) -> Result<H256, UpdateError> {
+        if !self.contains_key(key) {
+            return Err(UpdateError::KeyNotFound);
+        }
+        Ok(self.insert(key, new_value))
+    }
+    
+    /// Insert a key that must not already exist and return the new root
+    /// 
+    /// An existing key is an error and its value is left unchanged.
+    pub fn insert_new(&mut self, key: &[u8], value: &[u8]) -> Result<H256, InsertError> {
+        if self.contains_key(key) {
+            return Err(InsertError::KeyAlreadyExists);
+        }
+        Ok(self.insert(key, value))
+    }
+    
+    /// Remove a key, returning whether it was present
+    /// 
+    /// Nodes left with a single child are collapsed, so the root is the same as if the key
+    /// had never been inserted. Pending `insert_incremental` writes are committed first.
+    pub fn delete(&mut self, key: &[u8]) -> bool {
+        if !self.incremental.is_empty() {
+            self.commit();
+        }
+        let root = match self.root {
+            Some(root) => root,
+            None => return false,
+        };
+        match self.delete_at(&root, &to_nibbles(key)) {
+            Some(new_root) => {
+                self.root = new_root;
+                true
+            }
+            None => false,
+        }
+    }
+    
+    /// Delete each key in turn, skipping absent keys, and return the final root
+    pub fn delete_many<K: AsRef<[u8]>>(&mut self, keys: impl IntoIterator<Item = K>) -> H256 {
+        for key in keys {
+            self.delete(key.as_ref());
+        }
+        self.root_or_empty()
+    }
+    
+    /// Delete all `keys` and return the final root, or the absent keys if there are any
+    /// 
+    /// On error nothing is deleted.
+    pub fn delete_many_checked<K: AsRef<[u8]>>(
+        &mut self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<H256, Vec<Vec<u8>>> {
+        let keys: Vec<K> = keys.into_iter().collect();
+        let missing: Vec<Vec<u8>> = keys
+            .iter()
+            .filter(|key| !self.contains_key(key.as_ref()))
+            .map(|key| key.as_ref().to_vec())
+            .collect();
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+        Ok(self.delete_many(keys))
+    }
+    
+    /// Insert every entry of `other` and return the new root
+    /// 
+    /// Keys present in both tries must hold the same value. On the first conflicting key
+    /// (in key order) nothing is inserted. Pending writes of `self` are committed first;
+    /// those of `other` are not seen.
+    pub fn merge<O: NodeHasher>(&mut self, other: &MPTBuilder<O>) -> Result<H256, MergeError> {
+        if !self.incremental.is_empty() {
+            self.commit();
+        }
+        let mut new_entries = Vec::new();
+        for (key, value) in other.flatten() {
+            match self.get(&key) {
+                Some(existing) if existing == value => {}
+                Some(_) => return Err(MergeError::ConflictingKey(key)),
+                None => new_entries.push((key, value)),
+            }
+        }
+        
+        for (key, value) in &new_entries {
+            self.insert(key, value);
+        }
+        Ok(self.root_or_empty())
+    }
+    
+    /// The deletions, insertions and updates that turn `self` into `other`
+    pub fn trie_diff<O: NodeHasher>(&self, other: &MPTBuilder<O>) -> TrieDiff {
+        let mut diff = TrieDiff::default();
+        let mut old = self.flatten().into_iter().peekable();
+        let mut new = other.flatten().into_iter().peekable();
+        loop {
+            let order = match (old.peek(), new.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((old_key, _)), Some((new_key, _))) => old_key.cmp(new_key),
+            };
+            match order {
+                Ordering::Less => diff.deletions.extend(old.next().map(|(key, _)| key)),
+                Ordering::Greater => diff.insertions.extend(new.next()),
+                Ordering::Equal => {
+                    let (_, old_value) = old.next().unwrap();
+                    let (key, new_value) = new.next().unwrap();
+                    if old_value != new_value {
+                        diff.updates.push((key, new_value));
+                    }
+                }
+            }
+        }
+        diff
+    }
+    
+    /// Apply a `TrieDiff`: deletions, then insertions, then updates, and return the new root
+    /// 
+    /// Deleted and updated keys must be present and inserted keys absent (or deleted by the
+    /// same diff). The whole diff is checked first, so on error the trie is unchanged.
+    pub fn apply_diff(&mut self, diff: &TrieDiff) -> Result<H256, ApplyDiffError> {
+        if !self.incremental.is_empty() {
+            self.commit();
+        }
+        let deleted: HashSet<&[u8]> = diff.deletions.iter().map(Vec::as_slice).collect();
+        if let Some(key) = diff.deletions.iter().find(|key| !self.contains_key(key)) {
+            return Err(ApplyDiffError::MissingDeleteTarget(key.clone()));
+        }
+        if let Some((key, _)) = diff
+            .insertions
+            .iter()
+            .find(|(key, _)| self.contains_key(key) && !deleted.contains(key.as_slice()))
+        {
+            return Err(ApplyDiffError::ConflictingInsert(key.clone()));
+        }
+        if let Some((key, _)) = diff
+            .updates
+            .iter()
+            .find(|(key, _)| !self.contains_key(key) || deleted.contains(key.as_slice()))
+        {
+            return Err(ApplyDiffError::MissingUpdateTarget(key.clone()));
+        }
+        
+        self.delete_many(&diff.deletions);
+        for (key, value) in diff.insertions.iter().chain(&diff.updates) {
+            self.insert(key, value);
+        }
+        Ok(self.root_or_empty())
+    }
+    
+    /// Move the trie to `root` by importing the nodes of a proof against it
+    /// 
+    /// The proof must hash-link to `root`: its first node hashes to `root`, and every later
+    /// node is referenced by an earlier one. Subtries off the proof's path are expected to
+    /// be unchanged from the current trie, as when `root` is a later state of it. An empty
+    /// proof is accepted only for the empty trie root. Everything is checked first, so on
+    /// error the trie is unchanged. Pending `insert_incremental` writes stay pending.
+    pub fn repair_from_partial_proof(&mut self, proof: &[Vec<u8>], root: H256) -> Result<(), RepairError> {
+        if proof.is_empty() {
+            if root != self.hasher.hash(RLP_EMPTY) {
+                return Err(RepairError::InvalidProof);
+            }
+            self.root = None;
+            return Ok(());
+        }
+        
+        let mut referenced = HashSet::from([root]);
+        let mut imported = Vec::new();
+        for rlp in proof {
+            let hash = self.hasher.hash(rlp);
+            if !referenced.contains(&hash) {
+                return Err(RepairError::InvalidProof);
+            }
+            let node = decode_trie_node(&self.hasher, rlp);
+            if matches!(node, Node::Empty) {
+                return Err(RepairError::InvalidProof);
+            }
+            referenced.extend(node.child_hashes());
+            
+            // Embedded children are stored under their own hash, as `store_node` does
+            let mut pending = vec![rlp.clone()];
+            while let Some(rlp) = pending.pop() {
+                let items = decode_list(&rlp).map_err(|_| RepairError::InvalidProof)?;
+                pending.extend(items.into_iter().filter(|item| item.first().is_some_and(|&prefix| prefix >= 0xc0)));
+                let hash = self.hasher.hash(&rlp);
+                match self.nodes.get(&hash) {
+                    Some(existing) if *existing != rlp => return Err(RepairError::ConflictingNode(hash)),
+                    _ => imported.push((hash, rlp)),
+                }
+            }
+        }
+        
+        self.nodes.extend(imported);
+        self.root = Some(root);
+        Ok(())
+    }
+    
+    /// A new trie with the entries of `self` whose keys are also in `other`
+    pub fn intersect<O: NodeHasher>(&self, other: &MPTBuilder<O>) -> Self
+    where
+        H: Clone,
+    {
+        let mut result = Self::with_hasher(self.hasher.clone());
+        for (key, value) in self.flatten() {
+            if other.contains_key(&key) {
+                result.insert(&key, &value);
+            }
+        }
+        result
+    }
+    
+    /// A new trie with the entries whose keys are in exactly one of `self` and `other`
+    pub fn symmetric_difference<O: NodeHasher>(&self, other: &MPTBuilder<O>) -> Self
+    where
+        H: Clone,
+    {
+        let mut result = Self::with_hasher(self.hasher.clone());
+        for (key, value) in self.flatten() {
+            if !other.contains_key(&key) {
+                result.insert(&key, &value);
+            }
+        }
+        for (key, value) in other.flatten() {
+            if !self.contains_key(&key) {
+                result.insert(&key, &value);
+            }
+        }
+        result
+    }
+    
+    /// Buffer a write without touching the trie; `commit` applies all buffered writes
+    /// 
+    /// Each node on the written paths is rebuilt and hashed once per `commit`, however many
+    /// buffered writes pass through it, instead of once per `insert`. Until then `root`,
+    /// `get` and `get_proof` only see committed data.
+    pub fn insert_incremental(&mut self, key: &[u8], value: &[u8]) {
+        self.incremental.pending.insert(to_nibbles(key), value.to_vec());
+    }
+    
+    /// Apply the writes buffered by `insert_incremental` bottom-up and return the new root
+    /// 
+    /// With nothing buffered this returns the current root, or the empty trie root.
+    pub fn commit(&mut self) -> H256 {
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = std::mem::take(&mut self.incremental.pending).into_iter().collect();
+        if !entries.is_empty() {
+            self.root = Some(self.commit_at(self.root, &entries, 0));
+        }
+        self.root_or_empty()
+    }
+    
+    /// Pending writes waiting for `commit`
+    pub fn pending(&self) -> &IncrementalHasher {
+        &self.incremental
+    }
+    
+    /// Apply sorted, non-empty `entries` (sharing their first `depth` nibbles) to the subtrie at `node`
+    fn commit_at(&mut self, node: Option<H256>, entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> H256 {
+        let hash = match node {
+            Some(hash) => hash,
+            None => return self.build_sorted(entries, depth).expect("entries are non-empty"),
+        };
+        
+        match self.get_node(&hash) {
+            Node::Empty => self.build_sorted(entries, depth).expect("entries are non-empty"),
+            Node::Leaf(leaf) => {
+                // Merge the existing leaf in, unless a buffered write replaces it
+                let mut leaf_key = entries[0].0[..depth].to_vec();
+                leaf_key.extend_from_slice(&leaf.path);
+                let mut merged = entries.to_vec();
+                if let Err(pos) = merged.binary_search_by(|(path, _)| path.cmp(&leaf_key)) {
+                    merged.insert(pos, (leaf_key, leaf.value));
+                }
+                self.build_sorted(&merged, depth).expect("entries are non-empty")
+            }
+            Node::Extension(extension) => {
+                let ext_path = extension.path;
+                let common_len = entries
+                    .iter()
+                    .map(|(path, _)| common_prefix_len(&ext_path, &path[depth..]))
+                    .min()
+                    .unwrap_or(0);
+                
+                if common_len == ext_path.len() {
+                    let child = self.commit_at(Some(extension.child), entries, depth + common_len);
+                    return self.create_extension(&ext_path, child);
+                }
+                
+                // Split: the old extension's remainder hangs off a new branch
+                let mut children: [Option<H256>; 16] = Default::default();
+                children[ext_path[common_len] as usize] = Some(if common_len + 1 == ext_path.len() {
+                    extension.child
+                } else {
+                    self.create_extension(&ext_path[common_len + 1..], extension.child)
+                });
+                let branch = self.commit_branch(children, None, entries, depth + common_len);
+                
+                if common_len == 0 {
+                    branch
+                } else {
+                    self.create_extension(&ext_path[..common_len], branch)
+                }
+            }
+            Node::Branch(branch) => self.commit_branch(branch.children, branch.value, entries, depth),
+        }
+    }
+    
+    /// Apply `entries` to a branch at `depth` and store the branch once
+    fn commit_branch(
+        &mut self,
+        mut children: [Option<H256>; 16],
+        mut value: Option<Vec<u8>>,
+        entries: &[(Vec<u8>, Vec<u8>)],
+        depth: usize,
+    ) -> H256 {
+        let mut rest = entries;
+        
+        // A key ending here sorts first and replaces the branch value
+        if let Some(((path, v), tail)) = rest.split_first() {
+            if path.len() == depth {
+                value = Some(v.clone());
+                rest = tail;
+            }
+        }
+        
+        while let Some((path, _)) = rest.first() {
+            let nibble = path[depth] as usize;
+            let end = rest.iter().position(|(p, _)| p[depth] as usize != nibble).unwrap_or(rest.len());
+            children[nibble] = Some(self.commit_at(children[nibble], &rest[..end], depth + 1));
+            rest = &rest[end..];
+        }
+        
+        self.create_branch_node(children, value)
+    }
+    
+    /// Build the subtrie for sorted, deduplicated `entries` whose first `depth` nibbles are shared
+    fn build_sorted(&mut self, entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> Option<H256> {
+        match entries {
+            [] => None,
+            [(path, value)] => Some(self.create_leaf(&path[depth..], value.clone())),
+            [(first, _), .., (last, _)] => {
+                // Sorted, so the first and last entries bound the shared prefix
+                let common_len = common_prefix_len(&first[depth..], &last[depth..]);
+                if common_len > 0 {
+                    let child = self.build_branch(entries, depth + common_len);
+                    return Some(self.create_extension(&first[depth..depth + common_len], child));
+                }
+                Some(self.build_branch(entries, depth))
+            }
+        }
+    }
+    
+    /// Build a branch at `depth`, partitioning `entries` by their next nibble
+    fn build_branch(&mut self, entries: &[(Vec<u8>, Vec<u8>)], depth: usize) -> H256 {
+        let mut children: [Option<H256>; 16] = Default::default();
+        let mut rest = entries;
+        
+        // A key ending here sorts first and becomes the branch value
+        let mut value = None;
+        if let Some(((path, v), tail)) = rest.split_first() {
+            if path.len() == depth {
+                value = Some(v.clone());
+                rest = tail;
+            }
+        }
+        
+        while let Some((path, _)) = rest.first() {
+            let nibble = path[depth];
+            let end = rest.iter().position(|(p, _)| p[depth] != nibble).unwrap_or(rest.len());
+            children[nibble as usize] = self.build_sorted(&rest[..end], depth + 1);
+            rest = &rest[end..];
+        }
+        
+        self.create_branch_node(children, value)
+    }
+    
+    /// Compute the root the trie would have after inserting `ops`, without mutating `self`
+    /// 
+    /// Insertions are applied in order to a clone of the builder. An empty result yields
+    /// the empty trie root.
+    pub fn root_preview(&self, ops: &[(&[u8], &[u8])]) -> H256
+    where
+        H: Clone,
+    {
+        let mut preview = self.clone();
+        for (key, value) in ops {
+            preview.insert(key, value);
+        }
+        preview.root_or_empty()
+    }
+    
     /// Recursively insert into the trie
-    fn insert_at(&mut self, node_hash: Option<H256>, path: &[u8], value: Vec<u8>) -> H256 {
-        match node_hash {
+    /// 
+    /// `path` must be nibbles; a value above 15 would index past a branch's children, so it
+    /// is rejected before anything is stored.
+    fn insert_at(&mut self, node_hash: Option<H256>, path: &[u8], value: Vec<u8>) -> Result<H256, InsertError> {
+        if let Some(&nibble) = path.iter().find(|&&nibble| nibble > 15) {
+            return Err(InsertError::InvalidNibble(nibble));
+        }
+        
+        Ok(match node_hash {
             None => {
                 // Create a new leaf node
                 self.create_leaf(path, value)
@@ -49,11 +783,11 @@ impl MPTBuilder {
                 let node = self.get_node(&hash);
                 
                 match node {
-                    TrieNode::Empty => {
+                    Node::Empty => {
                         // Replace empty with leaf
                         self.create_leaf(path, value)
                     }
-                    TrieNode::Leaf(leaf_path, leaf_value) => {
+                    Node::Leaf(LeafNode { path: leaf_path, value: leaf_value }) => {
                         // Find common prefix
                         let common_len = common_prefix_len(&leaf_path, path);
                         
@@ -96,12 +830,12 @@ impl MPTBuilder {
                             }
                         }
                     }
-                    TrieNode::Extension(ext_path, child_hash) => {
+                    Node::Extension(ExtensionNode { path: ext_path, child: child_hash }) => {
                         let common_len = common_prefix_len(&ext_path, path);
                         
                         if common_len == ext_path.len() {
                             // Continue down the extension
-                            let new_child = self.insert_at(Some(child_hash), &path[common_len..], value);
+                            let new_child = self.insert_at(Some(child_hash), &path[common_len..], value)?;
                             self.create_extension(&ext_path, new_child)
                         } else {
                             // Extension needs to be split
@@ -117,21 +851,17 @@ impl MPTBuilder {
                             }
                             
                             // Add new value
-                            let new_idx = path[common_len] as usize;
-                            if common_len + 1 == path.len() {
-                                // Value goes in branch
-                                let branch = self.create_branch_node(branch_children, Some(value));
-                                return if common_len == 0 {
-                                    branch
-                                } else {
-                                    self.create_extension(&path[..common_len], branch)
-                                };
+                            let mut branch_value = None;
+                            if common_len == path.len() {
+                                // New path ends at the branch, value goes in branch
+                                branch_value = Some(value);
                             } else {
+                                let new_idx = path[common_len] as usize;
                                 let new_child = self.create_leaf(&path[common_len + 1..], value);
                                 branch_children[new_idx] = Some(new_child);
                             }
                             
-                            let branch = self.create_branch_node(branch_children, None);
+                            let branch = self.create_branch_node(branch_children, branch_value);
                             
                             if common_len == 0 {
                                 branch
@@ -140,134 +870,176 @@ impl MPTBuilder {
                             }
                         }
                     }
-                    TrieNode::Branch(mut children, branch_value) => {
+                    Node::Branch(BranchNode { mut children, value: branch_value }) => {
                         if path.is_empty() {
                             // Update branch value
                             self.create_branch_node(children, Some(value))
                         } else {
                             // Insert into appropriate child
                             let idx = path[0] as usize;
-                            let new_child = self.insert_at(children[idx], &path[1..], value);
+                            let new_child = self.insert_at(children[idx], &path[1..], value)?;
                             children[idx] = Some(new_child);
                             self.create_branch_node(children, branch_value)
                         }
                     }
                 }
             }
+        })
+    }
+    
+    /// Recursively delete from the trie
+    /// 
+    /// Returns `None` if the key is absent, otherwise the new subtrie (`Some(None)` if it
+    /// became empty).
+    fn delete_at(&mut self, node_hash: &H256, path: &[u8]) -> Option<Option<H256>> {
+        match self.get_node(node_hash) {
+            Node::Empty => None,
+            Node::Leaf(leaf) => (leaf.path == path).then_some(None),
+            Node::Extension(ExtensionNode { path: ext_path, child }) => {
+                let rest = path.strip_prefix(&ext_path[..])?;
+                match self.delete_at(&child, rest)? {
+                    Some(new_child) => Some(Some(self.prepend_path(&ext_path, new_child))),
+                    None => Some(None),
+                }
+            }
+            Node::Branch(BranchNode { mut children, mut value }) => {
+                match path.split_first() {
+                    None => {
+                        value.take()?;
+                    }
+                    Some((&nibble, rest)) => {
+                        let child = children[nibble as usize]?;
+                        children[nibble as usize] = self.delete_at(&child, rest)?;
+                    }
+                }
+                Some(self.collapse_branch(children, value))
+            }
+        }
+    }
+    
+    /// Store a branch, or the simpler node it reduces to once it has fewer than two entries
+    fn collapse_branch(&mut self, children: [Option<H256>; 16], value: Option<Vec<u8>>) -> Option<H256> {
+        let mut occupied = children.iter().enumerate().filter_map(|(i, child)| Some((i, (*child)?)));
+        match (occupied.next(), occupied.next(), value) {
+            (None, _, None) => None,
+            (None, _, Some(value)) => Some(self.create_leaf(&[], value)),
+            (Some((nibble, child)), None, None) => Some(self.prepend_path(&[nibble as u8], child)),
+            (_, _, value) => Some(self.create_branch_node(children, value)),
+        }
+    }
+    
+    /// Store the subtrie at `child` with `prefix` nibbles prepended to its path
+    /// 
+    /// A leaf or extension absorbs the prefix; a branch gets an extension above it.
+    fn prepend_path(&mut self, prefix: &[u8], child: H256) -> H256 {
+        match self.get_node(&child) {
+            Node::Leaf(leaf) => self.create_leaf(&[prefix, &leaf.path].concat(), leaf.value),
+            Node::Extension(extension) => {
+                self.create_extension(&[prefix, &extension.path].concat(), extension.child)
+            }
+            _ => self.create_extension(prefix, child),
         }
     }
     
     /// Create a leaf node
     fn create_leaf(&mut self, path: &[u8], value: Vec<u8>) -> H256 {
-        let encoded_path = encode_path(path, true);
-        let leaf_items = vec![
-            encode_bytes(&encoded_path),
-            encode_bytes(&value),
-        ];
-        let leaf_rlp = encode_list(&leaf_items);
-        let hash = keccak256(&leaf_rlp);
-        
-        self.nodes.insert(hash, leaf_rlp);
-        self.node_cache.insert(hash, TrieNode::Leaf(path.to_vec(), value));
-        hash
+        self.store_node(Node::Leaf(LeafNode { path: path.to_vec(), value }))
     }
     
     /// Create an extension node
     fn create_extension(&mut self, path: &[u8], child_hash: H256) -> H256 {
-        let encoded_path = encode_path(path, false);
-        let ext_items = vec![
-            encode_bytes(&encoded_path),
-            encode_bytes(&child_hash),
-        ];
-        let ext_rlp = encode_list(&ext_items);
-        let hash = keccak256(&ext_rlp);
-        
-        self.nodes.insert(hash, ext_rlp);
-        self.node_cache.insert(hash, TrieNode::Extension(path.to_vec(), child_hash));
-        hash
+        self.store_node(Node::Extension(ExtensionNode { path: path.to_vec(), child: child_hash }))
     }
     
     /// Create a branch node
     fn create_branch_node(&mut self, children: [Option<H256>; 16], value: Option<Vec<u8>>) -> H256 {
-        let mut items = Vec::with_capacity(17);
-        
-        for child in &children {
-            if let Some(hash) = child {
-                items.push(encode_bytes(hash));
-            } else {
-                items.push(encode_bytes(&[]));
+        self.store_node(Node::Branch(BranchNode { children, value }))
+    }
+    
+    /// Hash a node and store both its RLP and decoded form
+    fn store_node(&mut self, node: Node) -> H256 {
+        let rlp = self.encode_node(&node);
+        let hash = self.hasher.hash(&rlp);
+        self.nodes.insert(hash, rlp);
+        self.node_cache.insert(hash, node);
+        hash
+    }
+    
+    /// RLP-encode a node, embedding children whose encoding is under 32 bytes
+    /// 
+    /// Embedded children are still stored under their own hash, so they can be looked up
+    /// like any other node.
+    fn encode_node(&self, node: &Node) -> Vec<u8> {
+        match node {
+            Node::Empty | Node::Leaf(_) => node.to_rlp(),
+            Node::Extension(extension) => encode_list(&[
+                encode_bytes(&encode_path(&extension.path, false)),
+                self.child_ref(&extension.child),
+            ]),
+            Node::Branch(branch) => {
+                // Children are borrowed or encoded on the stack, so only the value and
+                // output allocate
+                let hashes = branch.children.map(|child| encode_hash_item(&child.unwrap_or_default()));
+                let embedded = branch.children.map(|child| child.and_then(|hash| self.embedded_child(&hash)));
+                let value = branch.value.as_deref().map(encode_bytes);
+                let items: [&[u8]; 17] = std::array::from_fn(|i| match branch.children.get(i) {
+                    Some(Some(_)) => embedded[i].as_deref().map_or(&hashes[i][..], Vec::as_slice),
+                    Some(None) => RLP_EMPTY,
+                    None => value.as_deref().unwrap_or(RLP_EMPTY),
+                });
+                let mut encoded = Vec::new();
+                encode_list_into(&items, &mut encoded);
+                encoded
             }
         }
-        
-        if let Some(v) = &value {
-            items.push(encode_bytes(v));
-        } else {
-            items.push(encode_bytes(&[]));
-        }
-        
-        let branch_rlp = encode_list(&items);
-        let hash = keccak256(&branch_rlp);
-        
-        self.nodes.insert(hash, branch_rlp);
-        self.node_cache.insert(hash, TrieNode::Branch(children, value));
-        hash
     }
     
-    /// Helper to create a branch with a single leaf child
+    /// The RLP item referencing a child: the child itself if short, otherwise its hash
+    fn child_ref(&self, hash: &H256) -> Vec<u8> {
+        self.embedded_child(hash).map_or_else(|| encode_bytes(hash), |rlp| rlp.to_vec())
+    }
+    
+    /// The RLP of a child short enough to embed in its parent
+    fn embedded_child(&self, hash: &H256) -> Option<impl Deref<Target = Vec<u8>> + '_> {
+        self.nodes.get(hash).filter(|rlp| rlp.len() < 32)
+    }
+    
     /// Get a node from cache or decode it
-    fn get_node(&mut self, hash: &H256) -> TrieNode {
+    fn get_node(&mut self, hash: &H256) -> Node {
         if let Some(node) = self.node_cache.get(hash) {
             return node.clone();
         }
         
         // Decode from RLP
-        if let Some(rlp) = self.nodes.get(hash) {
-            if let Ok(items) = decode_list(rlp) {
-                if items.len() == 2 {
-                    // Leaf or Extension
-                    if let Ok(path_bytes) = decode_bytes(&items[0]) {
-                        let (path, is_leaf) = decode_path(&path_bytes);
-                        
-                        if is_leaf {
-                            if let Ok(value) = decode_bytes(&items[1]) {
-                                let node = TrieNode::Leaf(path, value);
-                                self.node_cache.insert(*hash, node.clone());
-                                return node;
-                            }
-                        } else {
-                            if let Ok(child_bytes) = decode_bytes(&items[1]) {
-                                if child_bytes.len() == 32 {
-                                    let mut child_hash = [0u8; 32];
-                                    child_hash.copy_from_slice(&child_bytes);
-                                    let node = TrieNode::Extension(path, child_hash);
-                                    self.node_cache.insert(*hash, node.clone());
-                                    return node;
-                                }
-                            }
-                        }
-                    }
-                } else if items.len() == 17 {
-                    // Branch
-                    let mut children: [Option<H256>; 16] = Default::default();
-                    for i in 0..16 {
-                        if let Ok(child_bytes) = decode_bytes(&items[i]) {
-                            if child_bytes.len() == 32 {
-                                let mut child_hash = [0u8; 32];
-                                child_hash.copy_from_slice(&child_bytes);
-                                children[i] = Some(child_hash);
-                            }
-                        }
-                    }
-                    
-                    let value = decode_bytes(&items[16]).ok().filter(|v| !v.is_empty());
-                    let node = TrieNode::Branch(children, value);
-                    self.node_cache.insert(*hash, node.clone());
-                    return node;
-                }
-            }
+        let node = match self.nodes.get(hash) {
+            Some(rlp) => decode_trie_node(&self.hasher, rlp.as_slice()),
+            None => Node::Empty,
+        };
+        if !matches!(node, Node::Empty) {
+            self.node_cache.insert(*hash, node.clone());
         }
-        
-        TrieNode::Empty
+        node
+    }
+    
+    /// Look up a node without populating the cache
+    fn peek_node(&self, hash: &H256) -> Option<Node> {
+        match self.node_cache.get(hash) {
+            Some(node) => Some(node.clone()),
+            None => self.nodes.get(hash).map(|rlp| decode_trie_node(&self.hasher, rlp.as_slice())),
+        }
+    }
+    
+    /// Look up a node, caching it through `&self` on a miss
+    #[cfg(feature = "concurrent")]
+    fn peek_node_cached(&self, hash: &H256) -> Option<Node> {
+        if let Some(node) = self.node_cache.get(hash) {
+            return Some(node.clone());
+        }
+        let node = decode_trie_node(&self.hasher, self.nodes.get(hash)?.as_slice());
+        if !matches!(node, Node::Empty) {
+            self.node_cache.insert(*hash, node.clone());
+        }
+        Some(node)
     }
     
     /// Get the current root hash
@@ -275,62 +1047,171 @@ impl MPTBuilder {
         self.root
     }
     
+    /// The hasher used for this trie's nodes
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+    
+    /// Get the root hash, or the empty trie root (`EMPTY_TRIE_ROOT` with keccak256) before any insertion
+    pub fn root_or_empty(&self) -> H256 {
+        self.root.unwrap_or_else(|| self.hasher.hash(RLP_EMPTY))
+    }
+    
     /// Generate a proof for a key (collect all nodes along the path)
+    /// 
+    /// Nodes under 32 bytes are embedded in their parent, so apart from the root they do
+    /// not appear as separate proof entries.
     pub fn get_proof(&self, key: &[u8]) -> Option<Vec<Vec<u8>>> {
+        self.path_nodes(key).map(Self::proof_from_path)
+    }
+    
+    /// `get_proof` for many threads sharing one builder, caching the nodes it decodes
+    /// 
+    /// Lookups and cache inserts lock single `DashMap` shards, so proofs for different keys
+    /// proceed in parallel and later proofs reuse the decoded nodes. Writes such as
+    /// `insert_at` still need `&mut self`, so they must be synchronized externally.
+    #[cfg(feature = "concurrent")]
+    pub fn get_proof_concurrent(&self, key: &[u8]) -> Option<Vec<Vec<u8>>> {
+        self.path_nodes_with(key, |hash| self.peek_node_cached(hash)).map(Self::proof_from_path)
+    }
+    
+    /// The proof entries of a `path_nodes` walk: the root and every hash-referenced node
+    fn proof_from_path(path: Vec<(H256, Vec<u8>, Node)>) -> Vec<Vec<u8>> {
+        path.into_iter()
+            .enumerate()
+            .filter(|(i, (_, rlp, _))| *i == 0 || rlp.len() >= 32)
+            .map(|(_, (_, rlp, _))| rlp)
+            .collect()
+    }
+    
+    /// The first `max_depth` nodes of the proof for `key`, for `verify_truncated_proof`
+    /// 
+    /// Non-standard extension; a proof shorter than `max_depth` is returned whole. Works on
+    /// a trie pruned by `truncate_to_depth` to at least `max_depth`. Returns `None` where
+    /// `get_proof` would, or if a node within `max_depth` is missing.
+    pub fn get_truncated_proof(&self, key: &[u8], max_depth: usize) -> Option<Vec<Vec<u8>>> {
         let nibbles = to_nibbles(key);
         let mut proof = Vec::new();
         let mut current_hash = self.root?;
         let mut remaining_path = &nibbles[..];
         
         loop {
-            let node_rlp = self.nodes.get(&current_hash)?;
-            proof.push(node_rlp.clone());
-            
-            // Decode and determine next step
-            let items = decode_list(node_rlp).ok()?;
-            
-            if items.len() == 2 {
-                // Leaf or Extension
-                let path_bytes = decode_bytes(&items[0]).ok()?;
-                let (path, is_leaf) = decode_path(&path_bytes);
-                
-                if is_leaf {
-                    // Reached a leaf
-                    return Some(proof);
-                } else {
-                    // Extension - continue to child
-                    if !remaining_path.starts_with(&path) {
-                        return None;
-                    }
-                    remaining_path = &remaining_path[path.len()..];
-                    let child_bytes = decode_bytes(&items[1]).ok()?;
-                    if child_bytes.len() != 32 {
-                        return None;
-                    }
-                    current_hash.copy_from_slice(&child_bytes);
-                }
-            } else if items.len() == 17 {
-                // Branch
-                if remaining_path.is_empty() {
-                    // Value is in the branch itself
+            // Only the root and hash-referenced nodes are proof entries
+            let embedded = !proof.is_empty() && self.nodes.get(&current_hash).is_some_and(|rlp| rlp.len() < 32);
+            if !embedded {
+                if proof.len() == max_depth {
                     return Some(proof);
                 }
-                
-                let idx = remaining_path[0] as usize;
-                let child_bytes = decode_bytes(&items[idx]).ok()?;
-                
-                if child_bytes.is_empty() {
-                    return None;
+                proof.push(self.nodes.get(&current_hash)?.clone());
+            }
+            
+            let next = match self.peek_node(&current_hash)? {
+                Node::Leaf(_) => None,
+                Node::Extension(extension) => {
+                    remaining_path = remaining_path.strip_prefix(&extension.path[..])?;
+                    Some(extension.child)
                 }
-                
-                if child_bytes.len() != 32 {
-                    return None;
+                Node::Branch(branch) => match remaining_path.split_first() {
+                    None => None,
+                    Some((&nibble, rest)) => {
+                        remaining_path = rest;
+                        Some(branch.children[nibble as usize]?)
+                    }
+                },
+                Node::Empty => return None,
+            };
+            match next {
+                Some(child) => current_hash = child,
+                None => return Some(proof),
+            }
+        }
+    }
+    
+    /// Proof that `key` is absent: the nodes on its path down to where it leaves the trie
+    /// 
+    /// For `verify_non_inclusion_proof` and `ProofType::Exclusion` inputs. Unlike
+    /// `get_proof`, this covers keys that diverge from an extension or reach an empty
+    /// branch slot. Returns `None` if the key is present; the empty trie needs no nodes.
+    pub fn get_exclusion_proof(&self, key: &[u8]) -> Option<Vec<Vec<u8>>> {
+        if self.contains_key(key) {
+            return None;
+        }
+        let nibbles = to_nibbles(key);
+        let mut proof = Vec::new();
+        let Some(mut current_hash) = self.root else {
+            return Some(proof);
+        };
+        let mut remaining_path = &nibbles[..];
+        
+        loop {
+            let node_rlp = self.nodes.get(&current_hash)?;
+            if proof.is_empty() || node_rlp.len() >= 32 {
+                proof.push(node_rlp.clone());
+            }
+            
+            let next = match self.peek_node(&current_hash)? {
+                Node::Extension(extension) => remaining_path.strip_prefix(&extension.path[..]).map(|rest| {
+                    remaining_path = rest;
+                    extension.child
+                }),
+                Node::Branch(branch) => remaining_path.split_first().and_then(|(&nibble, rest)| {
+                    remaining_path = rest;
+                    branch.children[nibble as usize]
+                }),
+                Node::Leaf(_) | Node::Empty => None,
+            };
+            match next {
+                Some(child) => current_hash = child,
+                None => return Some(proof),
+            }
+        }
+    }
+    
+    /// Every node on the path to `key` as `(hash, rlp, node)`, including embedded ones
+    /// 
+    /// The walk stops at a leaf (matching or not) or at a branch where the key ends, and
+    /// returns `None` if the key diverges from an extension or hits an empty branch slot.
+    pub(crate) fn path_nodes(&self, key: &[u8]) -> Option<Vec<(H256, Vec<u8>, Node)>> {
+        self.path_nodes_with(key, |hash| self.peek_node(hash))
+    }
+    
+    /// `path_nodes`, decoding each node with `fetch`
+    fn path_nodes_with<F>(&self, key: &[u8], fetch: F) -> Option<Vec<(H256, Vec<u8>, Node)>>
+    where
+        F: Fn(&H256) -> Option<Node>,
+    {
+        let nibbles = to_nibbles(key);
+        let mut path = Vec::new();
+        let mut current_hash = self.root?;
+        let mut remaining_path = &nibbles[..];
+        
+        loop {
+            let node_rlp = self.nodes.get(&current_hash)?.clone();
+            let node = fetch(&current_hash)?;
+            
+            let next = match &node {
+                // Reached a leaf
+                Node::Leaf(_) => None,
+                Node::Extension(extension) => {
+                    // Continue to child
+                    remaining_path = remaining_path.strip_prefix(&extension.path[..])?;
+                    Some(extension.child)
                 }
-                
-                current_hash.copy_from_slice(&child_bytes);
-                remaining_path = &remaining_path[1..];
-            } else {
-                return None;
+                Node::Branch(branch) => match remaining_path.split_first() {
+                    // Value is in the branch itself
+                    None => None,
+                    Some((&nibble, rest)) => {
+                        remaining_path = rest;
+                        Some(branch.children[nibble as usize]?)
+                    }
+                },
+                Node::Empty => return None,
+            };
+            
+            path.push((current_hash, node_rlp, node));
+            match next {
+                Some(child) => current_hash = child,
+                None => return Some(path),
             }
         }
     }
@@ -342,58 +1223,310 @@ impl MPTBuilder {
         let mut remaining_path = &nibbles[..];
         
         loop {
-            let node_rlp = self.nodes.get(&current_hash)?;
-            let items = decode_list(node_rlp).ok()?;
-            
-            if items.len() == 2 {
-                // Leaf or Extension
-                let path_bytes = decode_bytes(&items[0]).ok()?;
-                let (path, is_leaf) = decode_path(&path_bytes);
-                
-                if is_leaf {
-                    // Check if path matches
-                    if path == remaining_path {
-                        return decode_bytes(&items[1]).ok();
-                    } else {
-                        return None;
+            match self.peek_node(&current_hash)? {
+                Node::Leaf(leaf) => return (leaf.path == remaining_path).then_some(leaf.value),
+                Node::Extension(extension) => {
+                    remaining_path = remaining_path.strip_prefix(&extension.path[..])?;
+                    current_hash = extension.child;
+                }
+                Node::Branch(branch) => match remaining_path.split_first() {
+                    None => return branch.value,
+                    Some((&nibble, rest)) => {
+                        current_hash = branch.children[nibble as usize]?;
+                        remaining_path = rest;
                     }
-                } else {
-                    // Extension
-                    if !remaining_path.starts_with(&path) {
-                        return None;
+                },
+                Node::Empty => return None,
+            }
+        }
+    }
+    
+    /// The node holding the value of `key`: its `Leaf`, or the `Branch` whose value slot it ends at
+    pub fn node_at_key_path(&self, key: &[u8]) -> Option<Node> {
+        let nibbles = to_nibbles(key);
+        let mut current_hash = self.root?;
+        let mut remaining_path = &nibbles[..];
+        
+        loop {
+            let node = self.peek_node(&current_hash)?;
+            match &node {
+                Node::Leaf(leaf) => return (leaf.path == remaining_path).then_some(node),
+                Node::Extension(extension) => {
+                    remaining_path = remaining_path.strip_prefix(&extension.path[..])?;
+                    current_hash = extension.child;
+                }
+                Node::Branch(branch) => match remaining_path.split_first() {
+                    None => return branch.value.is_some().then_some(node),
+                    Some((&nibble, rest)) => {
+                        current_hash = branch.children[nibble as usize]?;
+                        remaining_path = rest;
                     }
-                    remaining_path = &remaining_path[path.len()..];
-                    let child_bytes = decode_bytes(&items[1]).ok()?;
-                    if child_bytes.len() != 32 {
-                        return None;
+                },
+                Node::Empty => return None,
+            }
+        }
+    }
+    
+    /// Decode the stored node with hash `hash`, if any
+    /// 
+    /// Embedded children are resolved like the rest of the builder does, so unlike
+    /// `Node::from_rlp` this accepts every node the trie stores.
+    pub fn node_at_hash(&self, hash: &H256) -> Option<Node> {
+        self.peek_node(hash).filter(|node| !matches!(node, Node::Empty))
+    }
+    
+    /// Check whether `key` has a value in the trie
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+    
+    /// The value of `key`, or `default` if it is absent; the trie is not modified
+    pub fn get_or_default(&self, key: &[u8], default: Vec<u8>) -> Vec<u8> {
+        self.get(key).unwrap_or(default)
+    }
+    
+    /// Return the root and the value of `key`, inserting `default` first if it is absent
+    /// 
+    /// Pending `insert_incremental` writes are committed first, so they are seen.
+    pub fn get_or_insert(&mut self, key: &[u8], default: &[u8]) -> (H256, Vec<u8>) {
+        if !self.incremental.is_empty() {
+            self.commit();
+        }
+        match self.get(key) {
+            Some(value) => (self.root_or_empty(), value),
+            None => (self.insert(key, default), default.to_vec()),
+        }
+    }
+    
+    /// Check whether any key starting with `prefix` has a value
+    /// 
+    /// Stops as soon as the prefix is used up, without walking down to a leaf.
+    pub fn contains_prefix(&self, prefix: &[u8]) -> bool {
+        self.prefix_node(prefix).is_some()
+    }
+    
+    /// Number of keys starting with `prefix`
+    pub fn count_with_prefix(&self, prefix: &[u8]) -> usize {
+        let mut count = 0;
+        let mut stack: Vec<H256> = self.prefix_node(prefix).into_iter().collect();
+        while let Some(hash) = stack.pop() {
+            match self.peek_node(&hash) {
+                Some(Node::Leaf(_)) => count += 1,
+                Some(Node::Extension(extension)) => stack.push(extension.child),
+                Some(Node::Branch(branch)) => {
+                    count += usize::from(branch.value.is_some());
+                    stack.extend(branch.children.iter().flatten());
+                }
+                Some(Node::Empty) | None => {}
+            }
+        }
+        count
+    }
+    
+    /// The highest node whose subtrie holds exactly the keys starting with `prefix`
+    /// 
+    /// The prefix may end partway along a leaf or extension path, in which case that node
+    /// is returned.
+    fn prefix_node(&self, prefix: &[u8]) -> Option<H256> {
+        let nibbles = to_nibbles(prefix);
+        let mut current_hash = self.root?;
+        let mut remaining = &nibbles[..];
+        
+        while !remaining.is_empty() {
+            match self.peek_node(&current_hash)? {
+                Node::Leaf(leaf) => return leaf.path.starts_with(remaining).then_some(current_hash),
+                Node::Extension(extension) => {
+                    if extension.path.starts_with(remaining) {
+                        break;
                     }
-                    current_hash.copy_from_slice(&child_bytes);
+                    remaining = remaining.strip_prefix(&extension.path[..])?;
+                    current_hash = extension.child;
                 }
-            } else if items.len() == 17 {
-                // Branch
-                if remaining_path.is_empty() {
-                    // Value is in the branch
-                    let value = decode_bytes(&items[16]).ok()?;
-                    return if value.is_empty() { None } else { Some(value) };
+                Node::Branch(branch) => {
+                    current_hash = branch.children[remaining[0] as usize]?;
+                    remaining = &remaining[1..];
                 }
-                
-                let idx = remaining_path[0] as usize;
-                let child_bytes = decode_bytes(&items[idx]).ok()?;
-                
-                if child_bytes.is_empty() {
-                    return None;
+                Node::Empty => return None,
+            }
+        }
+        Some(current_hash)
+    }
+    
+    /// Count the `(leaf, extension, branch)` nodes reachable from the root
+    /// 
+    /// Nodes superseded by later inserts stay in the node maps but are not counted.
+    pub fn node_count_by_type(&self) -> (usize, usize, usize) {
+        let (mut leaves, mut extensions, mut branches) = (0, 0, 0);
+        let mut stack: Vec<H256> = self.root.into_iter().collect();
+        while let Some(hash) = stack.pop() {
+            match self.peek_node(&hash) {
+                Some(Node::Leaf(_)) => leaves += 1,
+                Some(Node::Extension(extension)) => {
+                    extensions += 1;
+                    stack.push(extension.child);
                 }
-                
-                if child_bytes.len() != 32 {
-                    return None;
+                Some(Node::Branch(branch)) => {
+                    branches += 1;
+                    stack.extend(branch.children.iter().flatten());
                 }
-                
-                current_hash.copy_from_slice(&child_bytes);
-                remaining_path = &remaining_path[1..];
-            } else {
-                return None;
+                Some(Node::Empty) | None => {}
+            }
+        }
+        (leaves, extensions, branches)
+    }
+    
+    /// Total number of nodes reachable from the root
+    pub fn total_node_count(&self) -> usize {
+        let (leaves, extensions, branches) = self.node_count_by_type();
+        leaves + extensions + branches
+    }
+    
+    /// Nibble paths of every reachable extension node with their lengths, longest first
+    /// 
+    /// Each path is the extension's own segment, not the full key prefix above it. Long
+    /// segments show where many keys share a prefix.
+    pub fn get_extension_paths(&self) -> Vec<(Vec<u8>, usize)> {
+        let mut paths = Vec::new();
+        let mut stack: Vec<H256> = self.root.into_iter().collect();
+        while let Some(hash) = stack.pop() {
+            match self.peek_node(&hash) {
+                Some(Node::Extension(extension)) => {
+                    let len = extension.path.len();
+                    paths.push((extension.path, len));
+                    stack.push(extension.child);
+                }
+                Some(Node::Branch(branch)) => stack.extend(branch.children.iter().flatten()),
+                Some(Node::Leaf(_)) | Some(Node::Empty) | None => {}
+            }
+        }
+        paths.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        paths
+    }
+    
+    /// Nibble path of the longest extension node, if the trie has any
+    pub fn longest_extension_path(&self) -> Option<Vec<u8>> {
+        self.get_extension_paths().into_iter().next().map(|(path, _)| path)
+    }
+    
+    /// Number of proof nodes for a stored key, `None` if the key has no value
+    pub fn depth_of(&self, key: &[u8]) -> Option<usize> {
+        if !self.contains_key(key) {
+            return None;
+        }
+        self.get_proof(key).map(|proof| proof.len())
+    }
+    
+    /// Average `depth_of` over all stored keys, `0.0` for an empty trie
+    pub fn avg_depth(&self) -> f64 {
+        let depths = self.key_depths();
+        if depths.is_empty() {
+            return 0.0;
+        }
+        depths.iter().sum::<usize>() as f64 / depths.len() as f64
+    }
+    
+    /// Largest `depth_of` over all stored keys, `0` for an empty trie
+    /// 
+    /// `max_depth() / avg_depth()` measures balance; a balanced trie is close to 1.
+    pub fn max_depth(&self) -> usize {
+        self.key_depths().into_iter().max().unwrap_or(0)
+    }
+    
+    /// The proof length of every stored key, in one walk from the root
+    /// 
+    /// As in `get_proof`, embedded nodes other than the root are not counted.
+    fn key_depths(&self) -> Vec<usize> {
+        let mut depths = Vec::new();
+        let mut stack: Vec<(H256, usize)> = self.root.map(|root| (root, 0)).into_iter().collect();
+        while let Some((hash, parent_depth)) = stack.pop() {
+            let is_root = Some(hash) == self.root;
+            let embedded = self.nodes.get(&hash).is_some_and(|rlp| rlp.len() < 32);
+            let depth = parent_depth + usize::from(is_root || !embedded);
+            match self.peek_node(&hash) {
+                Some(Node::Leaf(_)) => depths.push(depth),
+                Some(Node::Extension(extension)) => stack.push((extension.child, depth)),
+                Some(Node::Branch(branch)) => {
+                    if branch.value.is_some() {
+                        depths.push(depth);
+                    }
+                    stack.extend(branch.children.iter().flatten().map(|child| (*child, depth)));
+                }
+                Some(Node::Empty) | None => {}
+            }
+        }
+        depths
+    }
+    
+    /// Check that the internal node maps are consistent (debug/testing utility)
+    /// 
+    /// Every cached node must have its RLP in `nodes`, the root must be stored, and every
+    /// child referenced from a node reachable from the root must be stored as well.
+    #[cfg(any(test, debug_assertions, feature = "verify"))]
+    pub fn verify_internal_consistency(&self) -> Result<(), ConsistencyError> {
+        #[cfg(not(feature = "concurrent"))]
+        let cached: Vec<H256> = self.node_cache.keys().copied().collect();
+        #[cfg(feature = "concurrent")]
+        let cached: Vec<H256> = self.node_cache.iter().map(|entry| *entry.key()).collect();
+        for hash in cached {
+            if !self.nodes.contains_key(&hash) {
+                return Err(ConsistencyError { missing_node: hash, referenced_by: hash });
+            }
+        }
+        
+        let root = match self.root {
+            Some(root) => root,
+            None => return Ok(()),
+        };
+        
+        let mut stack = vec![(root, root)];
+        while let Some((hash, parent)) = stack.pop() {
+            let node = match self.peek_node(&hash) {
+                Some(node) if self.nodes.contains_key(&hash) => node,
+                _ => return Err(ConsistencyError { missing_node: hash, referenced_by: parent }),
+            };
+            
+            match node {
+                Node::Extension(extension) => stack.push((extension.child, hash)),
+                Node::Branch(branch) => {
+                    stack.extend(branch.children.iter().flatten().map(|child| (*child, hash)));
+                }
+                Node::Leaf(_) | Node::Empty => {}
             }
         }
+        
+        Ok(())
+    }
+    
+    /// Re-encode and rehash every node reachable from the root, bottom-up
+    /// 
+    /// Nodes are read from `node_cache` first, so manual edits to cached nodes are
+    /// re-signed into `nodes` along with every parent above them. Children missing from
+    /// both maps keep their old hash. Sets and returns the new root; pending
+    /// `insert_incremental` writes are not applied.
+    pub fn recompute_all_hashes(&mut self) -> H256 {
+        if let Some(root) = self.root {
+            self.root = Some(self.rehash_subtrie(&root));
+        }
+        self.root_or_empty()
+    }
+    
+    /// Post-order rehash of the subtrie at `hash`, returning its new hash
+    fn rehash_subtrie(&mut self, hash: &H256) -> H256 {
+        let node = match self.peek_node(hash) {
+            Some(Node::Leaf(leaf)) => Node::Leaf(leaf),
+            Some(Node::Extension(mut extension)) => {
+                extension.child = self.rehash_subtrie(&extension.child);
+                Node::Extension(extension)
+            }
+            Some(Node::Branch(mut branch)) => {
+                for child in branch.children.iter_mut().flatten() {
+                    *child = self.rehash_subtrie(child);
+                }
+                Node::Branch(branch)
+            }
+            Some(Node::Empty) | None => return *hash,
+        };
+        self.store_node(node)
     }
     
     /// Get proofs for multiple keys at once
@@ -401,83 +1534,445 @@ impl MPTBuilder {
         keys.iter().map(|key| self.get_proof(key)).collect()
     }
     
-    /// Get all key-value pairs in the trie (for testing/debugging)
-    pub fn get_all_entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+    /// Get a proof for a key as a single-entry `CompactProof`
+    pub fn get_compact_proof(&self, key: &[u8]) -> Option<CompactProof> {
+        self.get_multi_compact_proof(&[key])
+    }
+    
+    /// Get proofs for several keys sharing one node dictionary, in the order of `keys`
+    /// 
+    /// Returns `None` if any key has no proof.
+    pub fn get_multi_compact_proof(&self, keys: &[&[u8]]) -> Option<CompactProof> {
+        let mut compact = CompactProof::default();
+        for key in keys {
+            compact.push(self.get_proof(key)?, |rlp| self.hasher.hash(rlp));
+        }
+        Some(compact)
+    }
+    
+    /// Get a proof for every key `k` with `start <= k <= end` (bytewise), in key order
+    /// 
+    /// Subtrees whose keys all fall outside the range are skipped, so only the part of
+    /// the trie overlapping the range is visited.
+    pub fn get_proof_for_range(&self, start: &[u8], end: &[u8]) -> Vec<MPTProofInput> {
         let mut entries = Vec::new();
-        if let Some(root) = self.root {
-            self.collect_entries(&root, &mut Vec::new(), &mut entries);
+        let root = match self.root {
+            Some(root) => root,
+            None => return Vec::new(),
+        };
+        
+        let start_nibbles = to_nibbles(start);
+        let end_nibbles = to_nibbles(end);
+        
+        // Depth-first walk, pushing children in reverse so keys come out in order
+        let mut stack = vec![(root, Vec::new())];
+        while let Some((hash, prefix)) = stack.pop() {
+            // Keys below `prefix` are all < start or all > end
+            let below_start = !start_nibbles.starts_with(&prefix) && prefix < start_nibbles;
+            let above_end = !end_nibbles.starts_with(&prefix) && prefix > end_nibbles;
+            if below_start || above_end {
+                continue;
+            }
+            
+            match self.peek_node(&hash) {
+                Some(Node::Leaf(LeafNode { path, value })) => {
+                    let mut key = prefix;
+                    key.extend_from_slice(&path);
+                    entries.push((key, value));
+                }
+                Some(Node::Extension(ExtensionNode { path, child })) => {
+                    let mut child_prefix = prefix;
+                    child_prefix.extend_from_slice(&path);
+                    stack.push((child, child_prefix));
+                }
+                Some(Node::Branch(BranchNode { children, value })) => {
+                    for (i, child) in children.iter().enumerate().rev() {
+                        if let Some(child) = child {
+                            let mut child_prefix = prefix.clone();
+                            child_prefix.push(i as u8);
+                            stack.push((*child, child_prefix));
+                        }
+                    }
+                    if let Some(value) = value {
+                        entries.push((prefix, value));
+                    }
+                }
+                Some(Node::Empty) | None => {}
+            }
         }
+        
         entries
+            .into_iter()
+            .filter(|(key, _)| *key >= start_nibbles && *key <= end_nibbles)
+            .filter_map(|(key_nibbles, value)| {
+                let key = crate::path::from_nibbles(&key_nibbles);
+                let proof = self.get_proof(&key)?;
+                Some(MPTProofInput { version: PROOF_FORMAT_VERSION, key, value, proof, root, proof_type: ProofType::Inclusion })
+            })
+            .collect()
     }
     
-    /// Recursively collect all entries from the trie
-    fn collect_entries(&self, hash: &H256, prefix: &mut Vec<u8>, entries: &mut Vec<(Vec<u8>, Vec<u8>)>) {
-        if let Some(node_rlp) = self.nodes.get(hash) {
-            if let Ok(items) = decode_list(node_rlp) {
-                if items.len() == 2 {
-                    // Leaf or Extension
-                    if let Ok(path_bytes) = decode_bytes(&items[0]) {
-                        let (path, is_leaf) = decode_path(&path_bytes);
-                        
-                        if is_leaf {
-                            // Leaf - add entry
-                            let mut key_nibbles = prefix.clone();
-                            key_nibbles.extend_from_slice(&path);
-                            
-                            if let Ok(value) = decode_bytes(&items[1]) {
-                                // Convert nibbles back to bytes
-                                let key = crate::path::from_nibbles(&key_nibbles);
-                                entries.push((key, value));
-                            }
-                        } else {
-                            // Extension - follow child
-                            prefix.extend_from_slice(&path);
-                            if let Ok(child_bytes) = decode_bytes(&items[1]) {
-                                if child_bytes.len() == 32 {
-                                    let mut child_hash = [0u8; 32];
-                                    child_hash.copy_from_slice(&child_bytes);
-                                    self.collect_entries(&child_hash, prefix, entries);
-                                }
-                            }
-                            prefix.truncate(prefix.len() - path.len());
+    /// Get a proof for every key in the trie, in key order
+    /// 
+    /// Each entry can be verified on its own against the current root. Takes O(N × D) for
+    /// N entries at depth up to D; an empty trie gives no proofs.
+    pub fn get_all_proofs(&self) -> Vec<MPTProofInput> {
+        let Some(root) = self.root else {
+            return Vec::new();
+        };
+        self.flatten()
+            .into_iter()
+            .filter_map(|(key, value)| {
+                let proof = self.get_proof(&key)?;
+                Some(MPTProofInput { version: PROOF_FORMAT_VERSION, key, value, proof, root, proof_type: ProofType::Inclusion })
+            })
+            .collect()
+    }
+    
+    /// Proofs of `key` and of the keys just before and after it, or `None` if `key` is absent
+    /// 
+    /// Together they show that no key lies between `key` and each neighbour; see
+    /// `verify_sibling_proof`.
+    pub fn get_sibling_proof(&self, key: &[u8]) -> Option<SiblingProof> {
+        let root = self.root?;
+        let entries = self.flatten();
+        let index = entries.binary_search_by(|(k, _)| k.as_slice().cmp(key)).ok()?;
+        let input = |(key, value): &(Vec<u8>, Vec<u8>)| {
+            let proof = self.get_proof(key)?;
+            Some(MPTProofInput { version: PROOF_FORMAT_VERSION, key: key.clone(), value: value.clone(), proof, root, proof_type: ProofType::Inclusion })
+        };
+        Some(SiblingProof {
+            target: input(&entries[index])?,
+            left_neighbor: match index.checked_sub(1) {
+                Some(left) => Some(input(&entries[left])?),
+                None => None,
+            },
+            right_neighbor: match entries.get(index + 1) {
+                Some(right) => Some(input(right)?),
+                None => None,
+            },
+        })
+    }
+    
+    /// `get_all_proofs` as one batch against the trie's root (the empty trie root if empty)
+    pub fn get_all_proofs_as_batch(&self) -> MPTBatchProofInput {
+        MPTBatchProofInput {
+            version: PROOF_FORMAT_VERSION,
+            proofs: self.get_all_proofs(),
+            root: self.root_or_empty(),
+        }
+    }
+    
+    /// Materialize every key-value pair, sorted by key
+    pub fn flatten(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        Self::flatten_from(self.root, |hash| self.peek_node(hash))
+    }
+    
+    /// Consuming variant of `flatten` that moves decoded values out instead of cloning
+    pub fn into_flat(self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        let root = self.root;
+        #[cfg(not(feature = "concurrent"))]
+        let mut node_cache = self.node_cache;
+        #[cfg(feature = "concurrent")]
+        let mut node_cache: std::collections::HashMap<_, _> = self.node_cache.into_iter().collect();
+        Self::flatten_from(root, |hash| {
+            node_cache
+                .remove(hash)
+                .or_else(|| self.nodes.get(hash).map(|rlp| decode_trie_node(&self.hasher, rlp.as_slice())))
+        })
+    }
+    
+    /// Freeze the trie into a lock-free, shareable `ReadOnlyMPT`
+    /// 
+    /// Pending incremental writes are committed first. The decoded node cache is dropped;
+    /// an empty trie gets the empty trie root.
+    pub fn into_read_only(mut self) -> ReadOnlyMPT {
+        if !self.incremental.is_empty() {
+            self.commit();
+        }
+        let root = self.root_or_empty();
+        #[cfg(not(feature = "concurrent"))]
+        let nodes = self.nodes;
+        #[cfg(feature = "concurrent")]
+        let nodes = self.nodes.into_iter().collect();
+        ReadOnlyMPT::new(Arc::new(nodes), root)
+    }
+    
+    /// Depth-first walk from the root, fetching each node with `fetch`
+    fn flatten_from<F>(root: Option<H256>, mut fetch: F) -> Vec<(Vec<u8>, Vec<u8>)>
+    where
+        F: FnMut(&H256) -> Option<Node>,
+    {
+        let mut entries = Vec::new();
+        let mut stack: Vec<(H256, Vec<u8>)> = root.map(|root| (root, Vec::new())).into_iter().collect();
+        
+        while let Some((hash, mut prefix)) = stack.pop() {
+            match fetch(&hash) {
+                Some(Node::Leaf(LeafNode { path, value })) => {
+                    prefix.extend_from_slice(&path);
+                    entries.push((crate::path::from_nibbles(&prefix), value));
+                }
+                Some(Node::Extension(ExtensionNode { path, child })) => {
+                    prefix.extend_from_slice(&path);
+                    stack.push((child, prefix));
+                }
+                Some(Node::Branch(BranchNode { children, value })) => {
+                    for (i, child) in children.iter().enumerate() {
+                        if let Some(child) = child {
+                            let mut child_prefix = prefix.clone();
+                            child_prefix.push(i as u8);
+                            stack.push((*child, child_prefix));
                         }
                     }
-                } else if items.len() == 17 {
-                    // Branch
-                    // Check if branch has a value
-                    if let Ok(value) = decode_bytes(&items[16]) {
-                        if !value.is_empty() {
-                            let key = crate::path::from_nibbles(prefix);
-                            entries.push((key, value));
-                        }
+                    if let Some(value) = value {
+                        entries.push((crate::path::from_nibbles(&prefix), value));
                     }
-                    
-                    // Check all 16 children
-                    for i in 0..16 {
-                        if let Ok(child_bytes) = decode_bytes(&items[i]) {
-                            if child_bytes.len() == 32 {
-                                prefix.push(i as u8);
-                                let mut child_hash = [0u8; 32];
-                                child_hash.copy_from_slice(&child_bytes);
-                                self.collect_entries(&child_hash, prefix, entries);
-                                prefix.pop();
-                            }
-                        }
+                }
+                Some(Node::Empty) | None => {}
+            }
+        }
+        
+        entries.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+    
+    /// Get all key-value pairs in the trie (for testing/debugging)
+    pub fn get_all_entries(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.flatten()
+    }
+    
+    /// Stream the trie to `writer` in a simple binary format
+    /// 
+    /// The root hash (the empty trie root for an empty trie) is followed by one
+    /// `hash || rlp_len (u32, big-endian) || rlp` record per node reachable from the root,
+    /// in depth-first order. Nodes are written as they are visited, without collecting them
+    /// first. Pending `insert_incremental` writes are not included.
+    pub fn serialize_to_writer<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.root_or_empty())?;
+        let mut stack: Vec<H256> = self.root.into_iter().collect();
+        while let Some(hash) = stack.pop() {
+            let rlp = self
+                .nodes
+                .get(&hash)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "trie references a missing node"))?;
+            let len = u32::try_from(rlp.len())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "node longer than u32::MAX bytes"))?;
+            writer.write_all(&hash)?;
+            writer.write_all(&len.to_be_bytes())?;
+            writer.write_all(rlp.as_slice())?;
+            
+            if let Some(node) = self.peek_node(&hash) {
+                stack.extend(node.child_hashes().into_iter().rev());
+            }
+        }
+        Ok(())
+    }
+    
+    /// Encode the trie in a compact binary form, readable with `compact_decode`
+    /// 
+    /// Nodes are written depth-first, each followed by its children, as a type tag and:
+    /// - leaf (0): varint nibble count, path packed two nibbles per byte, varint value
+    ///   length, value
+    /// - extension (1): varint nibble count, packed path, 32-byte child hash
+    /// - branch (2): big-endian 16-bit bitmap of present children, the hash of each,
+    ///   varint value length (0 if none), value
+    /// 
+    /// Without RLP framing or a hash per node this is smaller than `serialize_to_writer`:
+    /// a 100-entry trie with 32-byte keys and 40-byte values takes about 68% of the
+    /// bytes. The empty trie encodes to nothing. Nodes missing from the builder are
+    /// skipped, so a trie with dangling references will not decode.
+    pub fn compact_encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut stack: Vec<H256> = self.root.into_iter().collect();
+        while let Some(hash) = stack.pop() {
+            match self.peek_node(&hash) {
+                Some(Node::Leaf(leaf)) => {
+                    out.push(COMPACT_LEAF);
+                    write_compact_path(&mut out, &leaf.path);
+                    write_varint(&mut out, leaf.value.len());
+                    out.extend_from_slice(&leaf.value);
+                }
+                Some(Node::Extension(extension)) => {
+                    out.push(COMPACT_EXTENSION);
+                    write_compact_path(&mut out, &extension.path);
+                    out.extend_from_slice(&extension.child);
+                    stack.push(extension.child);
+                }
+                Some(Node::Branch(branch)) => {
+                    let bitmap = (0..16)
+                        .filter(|&i| branch.children[i].is_some())
+                        .fold(0u16, |bits, i| bits | 1 << i);
+                    out.push(COMPACT_BRANCH);
+                    out.extend_from_slice(&bitmap.to_be_bytes());
+                    for child in branch.children.iter().flatten() {
+                        out.extend_from_slice(child);
                     }
+                    let value = branch.value.as_deref().unwrap_or(&[]);
+                    write_varint(&mut out, value.len());
+                    out.extend_from_slice(value);
+                    stack.extend(branch.children.iter().rev().flatten());
                 }
+                _ => {}
+            }
+        }
+        out
+    }
+    
+    /// Export every stored node as a list sorted by hash
+    /// 
+    /// Unlike the internal hash map, the order is deterministic across runs and
+    /// architectures, so the serialized list suits content-addressed storage.
+    pub fn to_sorted_node_list(&self) -> SortedNodeList {
+        #[cfg(not(feature = "concurrent"))]
+        let mut nodes: SortedNodeList = self.nodes.iter().map(|(hash, rlp)| (*hash, rlp.clone())).collect();
+        #[cfg(feature = "concurrent")]
+        let mut nodes: SortedNodeList =
+            self.nodes.iter().map(|entry| (*entry.key(), entry.value().clone())).collect();
+        nodes.sort_unstable_by_key(|(hash, _)| *hash);
+        nodes
+    }
+}
+
+/// Decode a node from its RLP encoding (`Empty` if it is not a valid node)
+/// 
+/// An embedded child is referenced by the hash of its RLP, which is where `store_node`
+/// keeps it.
+fn decode_trie_node<H: NodeHasher>(hasher: &H, rlp: &[u8]) -> Node {
+    let items = match decode_list(rlp) {
+        Ok(items) => items,
+        Err(_) => return Node::Empty,
+    };
+    
+    // `Some(None)` for an empty reference, `None` if the reference is invalid
+    let child_ref = |item: &Vec<u8>| -> Option<Option<H256>> {
+        if item.first().is_some_and(|&prefix| prefix >= 0xc0) {
+            return Some(Some(hasher.hash(item)));
+        }
+        let bytes = decode_bytes(item).ok()?;
+        if bytes.is_empty() {
+            return Some(None);
+        }
+        bytes.try_into().ok().map(Some)
+    };
+    
+    let node = match items.len() {
+        2 => decode_bytes(&items[0]).ok().and_then(|path_bytes| {
+            let (path, is_leaf) = decode_path(&path_bytes);
+            if is_leaf {
+                let value = decode_bytes(&items[1]).ok()?;
+                Some(Node::Leaf(LeafNode { path, value }))
+            } else {
+                let child = child_ref(&items[1])??;
+                Some(Node::Extension(ExtensionNode { path, child }))
+            }
+        }),
+        17 => (|| {
+            let mut children: [Option<H256>; 16] = Default::default();
+            for (child, item) in children.iter_mut().zip(&items) {
+                *child = child_ref(item)?;
             }
+            let value = decode_bytes(&items[16]).ok().filter(|v| !v.is_empty());
+            Some(Node::Branch(BranchNode { children, value }))
+        })(),
+        _ => None,
+    };
+    node.unwrap_or(Node::Empty)
+}
+
+/// Node type tags of the `compact_encode` format
+const COMPACT_LEAF: u8 = 0;
+const COMPACT_EXTENSION: u8 = 1;
+const COMPACT_BRANCH: u8 = 2;
+
+/// Append `value` as an unsigned LEB128 varint
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<usize, CompactDecodeError> {
+    let mut value = 0usize;
+    for shift in (0..usize::BITS).step_by(7) {
+        let byte = take_bytes(input, 1)?[0];
+        let bits = (byte & 0x7f) as usize;
+        if bits << shift >> shift != bits {
+            return Err(CompactDecodeError::InvalidVarint);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
         }
     }
+    Err(CompactDecodeError::InvalidVarint)
+}
+
+/// Append a nibble count and the nibbles packed two per byte, high nibble first
+fn write_compact_path(out: &mut Vec<u8>, nibbles: &[u8]) {
+    write_varint(out, nibbles.len());
+    out.extend(nibbles.chunks(2).map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0)));
+}
+
+fn read_compact_path(input: &mut &[u8]) -> Result<Vec<u8>, CompactDecodeError> {
+    let len = read_varint(input)?;
+    let packed = take_bytes(input, len.div_ceil(2))?;
+    Ok(packed.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).take(len).collect())
+}
+
+fn read_hash(input: &mut &[u8]) -> Result<H256, CompactDecodeError> {
+    Ok(take_bytes(input, 32)?.try_into().unwrap())
 }
 
-/// Helper function to find common prefix length
-fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
-    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+/// Split `len` bytes off the front of `input`
+fn take_bytes<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], CompactDecodeError> {
+    if input.len() < len {
+        return Err(CompactDecodeError::UnexpectedEnd);
+    }
+    let (head, rest) = input.split_at(len);
+    *input = rest;
+    Ok(head)
 }
 
-impl Default for MPTBuilder {
+impl<H: NodeHasher + Default> Default for MPTBuilder<H> {
     fn default() -> Self {
-        Self::new()
+        Self::with_hasher(H::default())
+    }
+}
+
+impl<H: NodeHasher> fmt::Debug for MPTBuilder<H> {
+    /// Prints every node reachable from the root as pretty-printed RLP
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let root = match self.root {
+            Some(root) => root,
+            None => return write!(f, "MPTBuilder {{ root: None }}"),
+        };
+        
+        writeln!(f, "MPTBuilder {{ root: 0x{} }}", hex_string(&root))?;
+        
+        // Depth-first walk from the root
+        let mut stack = vec![root];
+        while let Some(hash) = stack.pop() {
+            let rlp = match self.nodes.get(&hash) {
+                Some(rlp) => rlp,
+                None => {
+                    writeln!(f, "node 0x{}: <missing>", hex_string(&hash))?;
+                    continue;
+                }
+            };
+            writeln!(f, "node 0x{}:", hex_string(&hash))?;
+            writeln!(f, "{}", rlp_pretty_print(rlp.as_slice(), 1))?;
+            
+            match self.peek_node(&hash) {
+                Some(Node::Extension(extension)) => stack.push(extension.child),
+                Some(Node::Branch(branch)) => {
+                    stack.extend(branch.children.iter().rev().flatten());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
     }
 }
 
@@ -496,6 +1991,8 @@ mod tests {
         
         let retrieved = builder.get(key).unwrap();
         assert_eq!(retrieved, value);
+        
+        builder.verify_internal_consistency().unwrap();
     }
 
     #[test]
@@ -509,6 +2006,36 @@ mod tests {
         
         let proof = builder.get_proof(key).unwrap();
         assert!(!proof.is_empty());
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+    
+    #[test]
+    fn test_builder_proof_for_key_ending_at_branch() {
+        let mut builder = MPTBuilder::new();
+        
+        // A lone key is a single leaf
+        builder.insert(b"ab", b"v");
+        let proof = builder.get_proof(b"ab").unwrap();
+        assert!(crate::mpt::verify_proof(&builder.root().unwrap(), b"ab", b"v", &proof));
+        
+        // "ab" ends at the branch below the shared prefix, whose value slot is empty
+        assert!(builder.delete(b"ab"));
+        builder.insert(b"ab\x01", b"one");
+        builder.insert(b"ab\x11", b"two");
+        let root = builder.root().unwrap();
+        let proof = builder.get_proof(b"ab").unwrap();
+        assert!(crate::mpt::verify_proof(&root, b"ab", b"", &proof));
+        assert!(!crate::mpt::verify_proof(&root, b"ab", b"v", &proof));
+        
+        // With a value, the same branch holds it
+        builder.insert(b"ab", b"v");
+        let root = builder.root().unwrap();
+        let proof = builder.get_proof(b"ab").unwrap();
+        assert!(crate::mpt::verify_proof(&root, b"ab", b"v", &proof));
+        assert!(!crate::mpt::verify_proof(&root, b"ab", b"", &proof));
+        
+        builder.verify_internal_consistency().unwrap();
     }
     
     #[test]
@@ -526,6 +2053,8 @@ mod tests {
         assert_eq!(builder.get(b"dog").unwrap(), b"puppy");
         assert_eq!(builder.get(b"doge").unwrap(), b"coin");
         assert_eq!(builder.get(b"horse").unwrap(), b"stallion");
+        
+        builder.verify_internal_consistency().unwrap();
     }
     
     #[test]
@@ -537,6 +2066,8 @@ mod tests {
         
         builder.insert(b"key", b"value2");
         assert_eq!(builder.get(b"key").unwrap(), b"value2");
+        
+        builder.verify_internal_consistency().unwrap();
     }
     
     #[test]
@@ -549,6 +2080,8 @@ mod tests {
         
         assert_eq!(builder.get(b"a").unwrap(), b"value_a");
         assert_eq!(builder.get(b"b").unwrap(), b"value_b");
+        
+        builder.verify_internal_consistency().unwrap();
     }
     
     #[test]
@@ -561,6 +2094,8 @@ mod tests {
         
         assert_eq!(builder.get(b"dog").unwrap(), b"puppy");
         assert_eq!(builder.get(b"dodge").unwrap(), b"car");
+        
+        builder.verify_internal_consistency().unwrap();
     }
     
     #[test]
@@ -570,18 +2105,47 @@ mod tests {
         builder.insert(b"key", b"value");
         
         assert!(builder.get(b"nonexistent").is_none());
+        
+        builder.verify_internal_consistency().unwrap();
     }
     
     #[test]
-    fn test_builder_complex_proof() {
+    fn test_builder_node_at_key_path() {
         let mut builder = MPTBuilder::new();
         
-        // Build a complex trie
         builder.insert(b"do", b"verb");
         builder.insert(b"dog", b"puppy");
-        builder.insert(b"doge", b"coin");
+        builder.insert(b"horse", b"stallion");
         
-        let root = builder.root().unwrap();
+        match builder.node_at_key_path(b"dog") {
+            Some(Node::Leaf(leaf)) => assert_eq!(leaf.value, b"puppy"),
+            other => panic!("expected a leaf, got {:?}", other),
+        }
+        match builder.node_at_key_path(b"do") {
+            Some(Node::Branch(branch)) => assert_eq!(branch.value.as_deref(), Some(&b"verb"[..])),
+            other => panic!("expected a branch, got {:?}", other),
+        }
+        assert!(builder.node_at_key_path(b"d").is_none());
+        assert!(builder.node_at_key_path(b"doge").is_none());
+        assert!(builder.node_at_key_path(b"cat").is_none());
+        assert!(MPTBuilder::new().node_at_key_path(b"dog").is_none());
+        
+        for (hash, _, node) in builder.path_nodes(b"dog").unwrap() {
+            assert_eq!(builder.node_at_hash(&hash), Some(node));
+        }
+        assert!(builder.node_at_hash(&[0u8; 32]).is_none());
+    }
+    
+    #[test]
+    fn test_builder_complex_proof() {
+        let mut builder = MPTBuilder::new();
+        
+        // Build a complex trie
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        
+        let root = builder.root().unwrap();
         
         // Get proof for each key and verify
         let proof1 = builder.get_proof(b"do").unwrap();
@@ -602,6 +2166,8 @@ mod tests {
         // Proofs for longer keys should have more nodes
         assert!(proof2.len() >= proof1.len());
         assert!(proof3.len() >= proof2.len());
+        
+        builder.verify_internal_consistency().unwrap();
     }
     
     #[test]
@@ -620,8 +2186,639 @@ mod tests {
         // All proofs should exist
         assert_eq!(proofs.len(), 3);
         assert!(proofs.iter().all(|p| p.is_some()));
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+    
+    #[test]
+    fn test_builder_debug_output() {
+        let mut builder = MPTBuilder::new();
+        assert_eq!(format!("{:?}", builder), "MPTBuilder { root: None }");
+        
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        
+        let output = format!("{:?}", builder);
+        assert!(output.contains("extension path=646f"));
+        assert!(output.contains("branch"));
+        assert!(output.contains("leaf path=7 value=0x7075707079"));
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+    
+    #[test]
+    fn test_builder_detects_dangling_child() {
+        let mut builder = MPTBuilder::new();
+        // 'a' (0x61) and 'q' (0x71) differ in the first nibble, so the root is a branch
+        builder.insert(b"a", b"value_a");
+        let root = builder.insert(b"q", b"value_q");
+        builder.verify_internal_consistency().unwrap();
+        
+        // Point one of the root branch's children at a node that does not exist
+        let dangling = [0xee; 32];
+        if let Some(Node::Branch(mut branch)) = builder.peek_node(&root) {
+            branch.children[0] = Some(dangling);
+            builder.node_cache.insert(root, Node::Branch(branch));
+        } else {
+            panic!("root should be a branch");
+        }
+        
+        assert_eq!(
+            builder.verify_internal_consistency(),
+            Err(ConsistencyError { missing_node: dangling, referenced_by: root })
+        );
+    }
+    
+    #[test]
+    fn test_builder_recompute_all_hashes() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb-value-long-enough-to-be-hashed");
+        builder.insert(b"dog", b"puppy-value-long-enough-to-be-hashed");
+        let old_root = builder.insert(b"horse", b"stallion-value-long-enough-to-be-hashed");
+        
+        // Unchanged nodes rehash to the same root
+        assert_eq!(builder.recompute_all_hashes(), old_root);
+        
+        // Swap the leaf value in the cache only, leaving a stale `nodes` entry behind
+        let (leaf_hash, _, _) = builder.path_nodes(b"dog").unwrap().pop().unwrap();
+        match builder.peek_node(&leaf_hash) {
+            Some(Node::Leaf(mut leaf)) => {
+                leaf.value = b"tampered-value-long-enough-to-be-hashed".to_vec();
+                builder.node_cache.insert(leaf_hash, Node::Leaf(leaf));
+            }
+            _ => panic!("dog should end in a leaf"),
+        }
+        
+        let new_root = builder.recompute_all_hashes();
+        assert_ne!(new_root, old_root);
+        assert_eq!(builder.root(), Some(new_root));
+        builder.verify_internal_consistency().unwrap();
+        
+        let value = b"tampered-value-long-enough-to-be-hashed";
+        let proof = builder.get_proof(b"dog").unwrap();
+        assert!(crate::mpt::verify_proof(&new_root, b"dog", value, &proof));
+        assert!(!crate::mpt::verify_proof(&old_root, b"dog", value, &proof));
+        
+        let proof = builder.get_proof(b"horse").unwrap();
+        assert!(crate::mpt::verify_proof(&new_root, b"horse", b"stallion-value-long-enough-to-be-hashed", &proof));
+        
+        assert_eq!(MPTBuilder::new().recompute_all_hashes(), crate::mpt::EMPTY_TRIE_ROOT);
+    }
+    
+    #[test]
+    fn test_builder_sorted_node_list_roundtrip() {
+        let mut builder = MPTBuilder::new();
+        let entries: Vec<(Vec<u8>, Vec<u8>)> = (0..50u32)
+            .map(|i| (i.to_be_bytes().to_vec(), format!("value number {:04} in the trie", i).into_bytes()))
+            .collect();
+        for (key, value) in &entries {
+            builder.insert(key, value);
+        }
+        
+        let list = builder.to_sorted_node_list();
+        assert!(list.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(list, builder.to_sorted_node_list());
+        
+        let bytes = postcard::to_allocvec(&list).unwrap();
+        let decoded: SortedNodeList = postcard::from_bytes(&bytes).unwrap();
+        let restored = MPTBuilder::from_sorted_node_list(decoded, builder.root());
+        
+        assert_eq!(restored.root(), builder.root());
+        for (key, value) in &entries {
+            assert_eq!(restored.get(key).unwrap(), *value);
+            assert_eq!(restored.get_proof(key), builder.get_proof(key));
+        }
+        
+        restored.verify_internal_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_builder_clear_and_reset_to() {
+        let mut builder = MPTBuilder::new();
+        for i in 0..100u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes());
+        }
+        let root = builder.root();
+        let nodes = builder.to_sorted_node_list();
+        #[cfg(not(feature = "concurrent"))]
+        let capacity = builder.nodes.capacity();
+        
+        builder.insert_incremental(b"pending", b"write");
+        builder.clear();
+        assert_eq!(builder.root(), None);
+        assert_eq!(builder.root_or_empty(), crate::mpt::EMPTY_TRIE_ROOT);
+        assert!(builder.nodes.is_empty() && builder.node_cache.is_empty());
+        // DashMap clears by erasing entries, which keeps the shard allocations but
+        // lowers the reported capacity
+        #[cfg(not(feature = "concurrent"))]
+        assert_eq!(builder.nodes.capacity(), capacity);
+        
+        for i in 0..100u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes());
+        }
+        assert_eq!(builder.root(), root);
+        assert_eq!(builder.get(b"pending"), None);
+        
+        let mut other = MPTBuilder::new();
+        other.insert(b"other", b"trie");
+        other.reset_to(nodes, root);
+        assert_eq!(other.root(), root);
+        assert_eq!(other.get(&keccak256(&7u32.to_be_bytes())), Some(7u32.to_be_bytes().to_vec()));
+        assert_eq!(other.get(b"other"), None);
+        other.verify_internal_consistency().unwrap();
+    }
+    
+    #[test]
+    fn test_builder_truncate_to_depth() {
+        use crate::mpt::verify_truncated_proof;
+        use crate::types::{ProofResult, VerificationError};
+        
+        let mut builder = MPTBuilder::new();
+        for i in 0..200u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes());
+        }
+        let root = builder.root().unwrap();
+        let key = keccak256(&7u32.to_be_bytes());
+        let full = builder.get_proof(&key).unwrap();
+        assert_eq!(full.len(), 3);
+        
+        // Cut at each depth: the last kept node references the next by hash
+        for depth in 0..full.len() {
+            let proof = builder.get_truncated_proof(&key, depth).unwrap();
+            assert_eq!(proof, full[..depth]);
+            let next = keccak256(&full[depth]);
+            assert_eq!(verify_truncated_proof(&root, &key, &proof, depth), Ok(ProofResult::Partial(next)));
+            assert_eq!(verify_truncated_proof(&root, &key, &proof, depth + 1), Err(VerificationError::InvalidProof));
+        }
+        for depth in [3, 10] {
+            assert_eq!(builder.get_truncated_proof(&key, depth).unwrap(), full);
+            let result = verify_truncated_proof(&root, &key, &full, depth);
+            assert_eq!(result, Ok(ProofResult::Complete(7u32.to_be_bytes().to_vec())));
+        }
+        assert_eq!(verify_truncated_proof(&root, &key, &full, 2), Err(VerificationError::ProofTooDeep));
+        
+        let node_count = builder.nodes.len();
+        assert_eq!(builder.truncate_to_depth(2), root);
+        assert!(builder.nodes.len() < node_count);
+        assert_eq!(builder.get_truncated_proof(&key, 2).unwrap(), full[..2]);
+        assert_eq!(builder.get_truncated_proof(&key, 3), None);
+        assert_eq!(builder.get(&key), None);
+        
+        assert_eq!(builder.truncate_to_depth(0), root);
+        assert!(builder.nodes.is_empty());
+        assert_eq!(builder.get_truncated_proof(&key, 0), Some(vec![]));
+    }
+    
+    #[test]
+    fn test_builder_sha256_hasher() {
+        use crate::hasher::Sha256Hasher;
+        use crate::mpt::{verify_proof, verify_proof_with_hasher};
+        
+        let entries: [(&[u8], &[u8]); 3] = [
+            (b"alpha", b"the first value stored in the sha256 trie"),
+            (b"alpine", b"the second value stored in the sha256 trie"),
+            (b"beta", b"the third value stored in the sha256 trie"),
+        ];
+        
+        let mut sha_builder = MPTBuilder::with_hasher(Sha256Hasher);
+        let mut keccak_builder = MPTBuilder::new();
+        for (key, value) in &entries {
+            sha_builder.insert(key, value);
+            keccak_builder.insert(key, value);
+        }
+        let root = sha_builder.root().unwrap();
+        assert_ne!(root, keccak_builder.root().unwrap());
+        
+        for (key, value) in &entries {
+            let proof = sha_builder.get_proof(key).unwrap();
+            assert!(proof.len() > 1);
+            assert!(verify_proof_with_hasher(&Sha256Hasher, &root, key, value, &proof));
+            // Child references are SHA-256 hashes, so keccak verification fails
+            assert!(!verify_proof(&root, key, value, &proof));
+        }
+        
+        sha_builder.verify_internal_consistency().unwrap();
+    }
+
+    #[cfg(feature = "poseidon")]
+    #[test]
+    fn test_builder_new_poseidon() {
+        use crate::mpt::{proof_value_with_hasher, verify_proof, verify_proof_with_hasher};
+        use crate::poseidon::PoseidonHasher;
+        
+        let mut builder = MPTBuilder::new_poseidon();
+        assert_eq!(builder.root_or_empty(), PoseidonHasher.hash(RLP_EMPTY));
+        for i in 0..50u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        let root = builder.root().unwrap();
+        
+        let key = keccak256(&7u32.to_be_bytes());
+        let proof = builder.get_proof(&key).unwrap();
+        assert!(verify_proof_with_hasher(&PoseidonHasher, &root, &key, &7u32.to_be_bytes().repeat(10), &proof));
+        assert!(!verify_proof(&root, &key, &7u32.to_be_bytes().repeat(10), &proof));
+        assert_eq!(proof_value_with_hasher(&PoseidonHasher, &root, b"absent", &proof), None);
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_builder_concurrent_get_proof() {
+        use crate::mpt::verify_proof;
+        
+        let mut builder = MPTBuilder::new();
+        for i in 0..1_000u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        let root = builder.root().unwrap();
+        let builder = &builder;
+        
+        // Eight threads generating proofs from the same shared reference; a failed
+        // assertion in any of them panics out of the scope
+        rayon::scope(|scope| {
+            for thread in 0..8u32 {
+                scope.spawn(move |_| {
+                    for i in (thread..1_000).step_by(8) {
+                        let key = keccak256(&i.to_be_bytes());
+                        let proof = builder.get_proof(&key).unwrap();
+                        assert!(verify_proof(&root, &key, &i.to_be_bytes().repeat(10), &proof));
+                    }
+                });
+            }
+        });
+    }
+
+    #[cfg(feature = "concurrent")]
+    #[test]
+    fn test_builder_get_proof_concurrent() {
+        use crate::mpt::verify_proof;
+        
+        let mut built = MPTBuilder::new();
+        for i in 0..1_000u32 {
+            built.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        let root = built.root().unwrap();
+        // Restored from a node list, so the decode cache starts out empty
+        let builder = MPTBuilder::from_sorted_node_list(built.to_sorted_node_list(), Some(root));
+        assert!(builder.node_cache.is_empty());
+        let builder = &builder;
+        
+        rayon::scope(|scope| {
+            for thread in 0..8u32 {
+                scope.spawn(move |_| {
+                    for i in (thread..1_000).step_by(8) {
+                        let key = keccak256(&i.to_be_bytes());
+                        let proof = builder.get_proof_concurrent(&key).unwrap();
+                        assert_eq!(Some(&proof), builder.get_proof(&key).as_ref());
+                        assert!(verify_proof(&root, &key, &i.to_be_bytes().repeat(10), &proof));
+                    }
+                });
+            }
+        });
+        
+        assert!(!builder.node_cache.is_empty());
+        builder.verify_internal_consistency().unwrap();
+        assert_eq!(builder.get_proof_concurrent(b"absent"), builder.get_proof(b"absent"));
+    }
+
+    #[test]
+    fn test_builder_get_proof_for_range() {
+        use crate::mpt::verify_proof;
+        
+        let mut builder = MPTBuilder::new();
+        for i in 0..1000u32 {
+            builder.insert(&i.to_be_bytes(), format!("value number {:04} in the trie", i).as_bytes());
+        }
+        let root = builder.root().unwrap();
+        
+        let inputs = builder.get_proof_for_range(&100u32.to_be_bytes(), &199u32.to_be_bytes());
+        assert_eq!(inputs.len(), 100);
+        
+        for (i, input) in (100..200u32).zip(&inputs) {
+            assert_eq!(input.key, i.to_be_bytes());
+            assert_eq!(input.root, root);
+            assert!(verify_proof(&input.root, &input.key, &input.value, &input.proof));
+        }
+        
+        // Bounds are inclusive, and an inverted range is empty
+        assert_eq!(builder.get_proof_for_range(&5u32.to_be_bytes(), &5u32.to_be_bytes()).len(), 1);
+        assert!(builder.get_proof_for_range(&9u32.to_be_bytes(), &5u32.to_be_bytes()).is_empty());
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_builder_nodes_match_node_to_rlp() {
+        let mut builder = MPTBuilder::new();
+        for i in 0..50u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        builder.insert(&[0x01, 0x02], b"a value long enough to be hashed by its parent");
+        builder.insert(&[0x01, 0x02, 0x03], b"another value long enough to be hashed");
+        
+        // Every stored node round-trips through `Node`; all children here are hashed
+        let mut kinds = HashSet::new();
+        for (hash, rlp) in builder.to_sorted_node_list() {
+            let node = Node::from_rlp(&rlp).unwrap();
+            assert_eq!(node.to_rlp(), rlp);
+            assert_eq!(node.hash(), hash);
+            kinds.insert(std::mem::discriminant(&node));
+        }
+        assert_eq!(kinds.len(), 3);
+    }
+
+    #[test]
+    fn test_builder_get_all_proofs() {
+        use crate::mpt::verify_proof;
+        
+        let mut builder = MPTBuilder::new();
+        assert!(builder.get_all_proofs().is_empty());
+        assert!(builder.get_all_proofs_as_batch().verify_all().all_verified);
+        
+        for i in 0..20u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), format!("value number {}", i).as_bytes());
+        }
+        let root = builder.root().unwrap();
+        
+        let proofs = builder.get_all_proofs();
+        assert_eq!(proofs.len(), 20);
+        assert_eq!(proofs.iter().map(|p| (p.key.clone(), p.value.clone())).collect::<Vec<_>>(), builder.flatten());
+        for input in &proofs {
+            assert_eq!(input.root, root);
+            assert!(verify_proof(&input.root, &input.key, &input.value, &input.proof));
+        }
+        
+        let batch = builder.get_all_proofs_as_batch();
+        assert_eq!(batch.root, root);
+        let result = batch.verify_all();
+        assert!(result.all_verified);
+        assert_eq!(result.count, 20);
+    }
+
+    #[test]
+    fn test_builder_get_sibling_proof() {
+        use crate::mpt::verify_sibling_proof;
+        
+        let mut builder = MPTBuilder::new();
+        for key in [&b"do"[..], b"dog", b"doge", b"horse", b"dodge", b"dot"] {
+            builder.insert(key, &[key, b"-value"].concat());
+        }
+        let root = builder.root().unwrap();
+        let keys: Vec<Vec<u8>> = builder.flatten().into_iter().map(|(key, _)| key).collect();
+        
+        for (i, key) in keys.iter().enumerate() {
+            let proof = builder.get_sibling_proof(key).unwrap();
+            assert_eq!(proof.target.key, *key);
+            assert_eq!(proof.left_neighbor.as_ref().map(|p| &p.key), i.checked_sub(1).map(|j| &keys[j]));
+            assert_eq!(proof.right_neighbor.as_ref().map(|p| &p.key), keys.get(i + 1));
+            assert!(verify_sibling_proof(&root, &proof), "{:?}", key);
+            assert!(!verify_sibling_proof(&[0u8; 32], &proof));
+        }
+        assert!(builder.get_sibling_proof(b"cat").is_none());
+        assert!(MPTBuilder::new().get_sibling_proof(b"dog").is_none());
+        
+        // Skipping a key leaves a non-empty gap, which the branch slots reveal
+        let dog = builder.get_sibling_proof(b"dog").unwrap();
+        let horse = builder.get_sibling_proof(b"horse").unwrap();
+        let mut skipping = dog.clone();
+        skipping.right_neighbor = Some(horse.target.clone());
+        assert!(!verify_sibling_proof(&root, &skipping));
+        let mut skipping = horse.clone();
+        skipping.left_neighbor = Some(dog.target.clone());
+        assert!(!verify_sibling_proof(&root, &skipping));
+        
+        // Dropping a neighbour claims the target is first or last
+        let mut no_left = dog.clone();
+        no_left.left_neighbor = None;
+        assert!(!verify_sibling_proof(&root, &no_left));
+        let mut no_right = dog;
+        no_right.right_neighbor = None;
+        assert!(!verify_sibling_proof(&root, &no_right));
+        
+        // Neighbours in the wrong order
+        let mut swapped = horse;
+        swapped.right_neighbor = swapped.left_neighbor.clone();
+        assert!(!verify_sibling_proof(&root, &swapped));
+        
+        // Hashed keys, with every node referenced by hash
+        let mut hashed = MPTBuilder::new();
+        for i in 0..50u32 {
+            hashed.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes());
+        }
+        let root = hashed.root().unwrap();
+        let keys: Vec<Vec<u8>> = hashed.flatten().into_iter().map(|(key, _)| key).collect();
+        for i in 1..keys.len() - 1 {
+            let mut proof = hashed.get_sibling_proof(&keys[i]).unwrap();
+            assert!(verify_sibling_proof(&root, &proof));
+            proof.right_neighbor = hashed.get_sibling_proof(&keys[i + 1]).unwrap().right_neighbor;
+            assert!(!verify_sibling_proof(&root, &proof));
+        }
+    }
+    
+    #[test]
+    fn test_builder_root_preview() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"alpha", b"the first value stored in the trie");
+        builder.insert(b"beta", b"the second value stored in the trie");
+        let root = builder.root().unwrap();
+        
+        let ops: [(&[u8], &[u8]); 2] = [
+            (b"gamma", b"the third value stored in the trie"),
+            (b"alpha", b"an updated first value for the trie"),
+        ];
+        let preview = builder.root_preview(&ops);
+        
+        let mut applied = builder.clone();
+        for (key, value) in &ops {
+            applied.insert(key, value);
+        }
+        assert_eq!(preview, applied.root().unwrap());
+        
+        // The builder itself is untouched
+        assert_eq!(builder.root(), Some(root));
+        assert_eq!(builder.get(b"gamma"), None);
+        assert_eq!(builder.root_preview(&[]), root);
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_builder_split_extension_at_key_end() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"apple", b"the value stored under the key apple");
+        builder.insert(b"apply", b"the value stored under the key apply");
+        // Diverges from the shared "appl" extension on its last nibble
+        builder.insert(b"apt", b"the value stored under the key apt");
+        // Ends inside the extension
+        builder.insert(b"ap", b"the value stored under the key ap");
+        
+        for key in [&b"apple"[..], b"apply", b"apt", b"ap"] {
+            let value = builder.get(key).unwrap();
+            let proof = builder.get_proof(key).unwrap();
+            assert!(crate::mpt::verify_proof(&builder.root().unwrap(), key, &value, &proof));
+        }
+        assert_eq!(builder.get(b"app"), None);
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_builder_stream_roundtrip() {
+        let mut builder = MPTBuilder::new();
+        for i in 0..1000u32 {
+            builder.insert(&crate::rlp_encoding::keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        // Short entries give embedded nodes, which are stored under their own hash
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        builder.serialize_to_writer(&mut cursor).unwrap();
+        cursor.set_position(0);
+        let restored = MPTBuilder::deserialize_from_reader(&mut cursor).unwrap();
+        assert_eq!(restored.root(), builder.root());
+        assert_eq!(restored.flatten(), builder.flatten());
+        restored.verify_internal_consistency().unwrap();
+        
+        // Through a file
+        let path = std::env::temp_dir().join(format!("mpt-stream-{}.bin", std::process::id()));
+        builder.serialize_to_writer(&mut std::fs::File::create(&path).unwrap()).unwrap();
+        let from_file = MPTBuilder::deserialize_from_reader(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(from_file.root(), builder.root());
+        assert_eq!(from_file.get(b"dog"), Some(b"puppy".to_vec()));
+        
+        // Corrupt and truncated input
+        let bytes = cursor.into_inner();
+        let mut corrupt = bytes.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 1;
+        let err = MPTBuilder::deserialize_from_reader(&mut corrupt.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let err = MPTBuilder::deserialize_from_reader(&mut &bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        
+        let mut empty = Vec::new();
+        MPTBuilder::new().serialize_to_writer(&mut empty).unwrap();
+        assert_eq!(empty, crate::mpt::EMPTY_TRIE_ROOT.to_vec());
+        assert_eq!(MPTBuilder::deserialize_from_reader(&mut empty.as_slice()).unwrap().root(), None);
     }
     
+    #[test]
+    fn test_builder_compact_encode_roundtrip() {
+        let mut builder = MPTBuilder::new();
+        for i in 0..100u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        
+        let compact = builder.compact_encode();
+        let mut stream = Vec::new();
+        builder.serialize_to_writer(&mut stream).unwrap();
+        // About 68% of the streamed node set, as documented on `compact_encode`
+        assert!(compact.len() * 10 < stream.len() * 7);
+        
+        let restored = MPTBuilder::compact_decode(&compact).unwrap();
+        assert_eq!(restored.root(), builder.root());
+        assert_eq!(restored.flatten(), builder.flatten());
+        restored.verify_internal_consistency().unwrap();
+        
+        // Embedded nodes, odd-length paths and a value in a branch
+        let mut small = MPTBuilder::new();
+        small.insert(b"do", b"verb");
+        small.insert(b"dog", b"puppy");
+        small.insert(b"doge", b"coin");
+        small.insert(b"horse", b"stallion");
+        let restored = MPTBuilder::compact_decode(&small.compact_encode()).unwrap();
+        assert_eq!(restored.root(), small.root());
+        assert_eq!(restored.get(b"do"), Some(b"verb".to_vec()));
+        
+        assert!(MPTBuilder::new().compact_encode().is_empty());
+        assert_eq!(MPTBuilder::compact_decode(&[]).unwrap().root(), None);
+    }
+    
+    #[test]
+    fn test_builder_compact_decode_errors() {
+        let mut builder = MPTBuilder::new();
+        for i in 0..20u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        let compact = builder.compact_encode();
+        
+        let mut corrupt = compact.clone();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 1;
+        assert_eq!(MPTBuilder::compact_decode(&corrupt).unwrap_err(), CompactDecodeError::ChildHashMismatch);
+        assert_eq!(
+            MPTBuilder::compact_decode(&compact[..compact.len() - 1]).unwrap_err(),
+            CompactDecodeError::UnexpectedEnd
+        );
+        assert_eq!(
+            MPTBuilder::compact_decode(&[compact.as_slice(), &[0]].concat()).unwrap_err(),
+            CompactDecodeError::TrailingBytes
+        );
+        assert_eq!(MPTBuilder::compact_decode(&[7]).unwrap_err(), CompactDecodeError::InvalidTag(7));
+        let overlong_varint = [0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f];
+        assert_eq!(MPTBuilder::compact_decode(&overlong_varint).unwrap_err(), CompactDecodeError::InvalidVarint);
+    }
+    
+    #[test]
+    fn test_builder_from_flat() {
+        let pairs: Vec<(Vec<u8>, Vec<u8>)> = ["apt", "apple", "ap", "apply", "application", "b"]
+            .iter()
+            .map(|key| (key.as_bytes().to_vec(), format!("value for {}", key).into_bytes()))
+            .collect();
+        
+        let mut inserted = MPTBuilder::new();
+        for (key, value) in &pairs {
+            inserted.insert(key, value);
+        }
+        
+        let built = MPTBuilder::from_flat(pairs.clone());
+        assert_eq!(built.root(), inserted.root());
+        assert_eq!(built.flatten(), inserted.flatten());
+        built.verify_internal_consistency().unwrap();
+        
+        assert_eq!(MPTBuilder::from_flat(Vec::new()).root(), None);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_from_flat_matches_insert(
+            pairs in proptest::collection::vec(
+                (
+                    proptest::collection::vec(proptest::sample::select(vec![0x00u8, 0x01, 0x10, 0x11, 0xf0, 0xff]), 0..5),
+                    proptest::collection::vec(proptest::num::u8::ANY, 1..40),
+                ),
+                0..40,
+            )
+        ) {
+            let mut inserted = MPTBuilder::new();
+            for (key, value) in &pairs {
+                inserted.insert(key, value);
+            }
+            let built = MPTBuilder::from_flat(pairs);
+            proptest::prop_assert_eq!(built.root(), inserted.root());
+        }
+    }
+
+    #[test]
+    fn test_builder_flatten() {
+        let mut builder = MPTBuilder::new();
+        for key in ["apple", "apply", "application", "apt"] {
+            builder.insert(key.as_bytes(), key.to_uppercase().as_bytes());
+        }
+        
+        let expected: Vec<(Vec<u8>, Vec<u8>)> = ["apple", "application", "apply", "apt"]
+            .iter()
+            .map(|key| (key.as_bytes().to_vec(), key.to_uppercase().into_bytes()))
+            .collect();
+        
+        builder.verify_internal_consistency().unwrap();
+        assert_eq!(builder.flatten(), expected);
+        assert_eq!(builder.into_flat(), expected);
+        assert!(MPTBuilder::new().flatten().is_empty());
+    }
+
     #[test]
     fn test_builder_get_all_entries() {
         let mut builder = MPTBuilder::new();
@@ -645,5 +2842,570 @@ mod tests {
         expected.sort();
         
         assert_eq!(retrieved, expected);
+        
+        builder.verify_internal_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_builder_update() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"dog", b"puppy-value-long-enough-to-hash-1");
+        builder.insert(b"doge", b"coin-value-long-enough-to-hash-22");
+        let root = builder.root();
+        
+        // "do" is a prefix of both keys but has no value of its own
+        assert_eq!(builder.update(b"do", b"verb"), Err(UpdateError::KeyNotFound));
+        assert_eq!(builder.update(b"doges", b"plural"), Err(UpdateError::KeyNotFound));
+        assert_eq!(builder.update(b"cat", b"meow"), Err(UpdateError::KeyNotFound));
+        assert_eq!(builder.root(), root);
+        assert_eq!(builder.get(b"do"), None);
+        
+        let new_root = builder.update(b"dog", b"hound-value-long-enough-to-hash-3").unwrap();
+        assert_eq!(builder.root(), Some(new_root));
+        assert_ne!(builder.root(), root);
+        assert_eq!(builder.get(b"dog"), Some(b"hound-value-long-enough-to-hash-3".to_vec()));
+        assert_eq!(builder.get(b"doge"), Some(b"coin-value-long-enough-to-hash-22".to_vec()));
+        
+        assert_eq!(MPTBuilder::new().update(b"dog", b"x"), Err(UpdateError::KeyNotFound));
+        builder.verify_internal_consistency().unwrap();
+    }
+    
+    #[test]
+    fn test_builder_repair_from_partial_proof() {
+        let mut old = MPTBuilder::new();
+        for key in [&b"do"[..], b"dog", b"doge", b"horse"] {
+            old.insert(key, key);
+        }
+        for i in 0..20u32 {
+            old.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        let mut new = old.clone();
+        let new_root = new.insert(b"dog", b"puppy");
+        let proof = new.get_proof(b"dog").unwrap();
+        
+        let mut light = MPTBuilder::from_sorted_node_list(old.to_sorted_node_list(), old.root());
+        assert_eq!(light.repair_from_partial_proof(&proof, new_root), Ok(()));
+        assert_eq!(light.root(), Some(new_root));
+        assert_eq!(light.get(b"dog"), Some(b"puppy".to_vec()));
+        assert_eq!(light.flatten(), new.flatten());
+        light.verify_internal_consistency().unwrap();
+        
+        // The proof must link to the claimed root, node by node
+        let mut light = MPTBuilder::from_sorted_node_list(old.to_sorted_node_list(), old.root());
+        assert_eq!(light.repair_from_partial_proof(&proof, old.root().unwrap()), Err(RepairError::InvalidProof));
+        let mut reordered = proof.clone();
+        reordered.swap(0, 1);
+        assert_eq!(light.repair_from_partial_proof(&reordered, new_root), Err(RepairError::InvalidProof));
+        assert_eq!(light.repair_from_partial_proof(&[], new_root), Err(RepairError::InvalidProof));
+        assert_eq!(light.root(), old.root());
+        
+        // A stored node whose content does not match its hash
+        let last = proof.last().unwrap();
+        let hash = keccak256(last);
+        light.nodes.insert(hash, b"corrupt".to_vec());
+        assert_eq!(light.repair_from_partial_proof(&proof, new_root), Err(RepairError::ConflictingNode(hash)));
+        assert_eq!(light.root(), old.root());
+        
+        assert_eq!(light.repair_from_partial_proof(&[], crate::mpt::EMPTY_TRIE_ROOT), Ok(()));
+        assert_eq!(light.root(), None);
+    }
+    
+    #[test]
+    fn test_builder_merge() {
+        let mut left = MPTBuilder::new();
+        left.insert(b"do", b"verb");
+        left.insert(b"dog", b"puppy");
+        let mut right = MPTBuilder::new();
+        right.insert(b"doge", b"coin");
+        right.insert(b"horse", b"stallion");
+        
+        let mut expected = MPTBuilder::new();
+        for (key, value) in left.flatten().into_iter().chain(right.flatten()) {
+            expected.insert(&key, &value);
+        }
+        
+        // Disjoint keys
+        let mut merged = left.clone();
+        assert_eq!(merged.merge(&right), Ok(expected.root().unwrap()));
+        assert_eq!(merged.flatten(), expected.flatten());
+        
+        // Identical overlapping entries are idempotent
+        assert_eq!(merged.merge(&left), Ok(expected.root().unwrap()));
+        assert_eq!(merged.clone().merge(&merged), Ok(expected.root().unwrap()));
+        
+        // A conflicting key leaves the trie untouched, even with other keys to add
+        let mut conflicting = MPTBuilder::new();
+        conflicting.insert(b"cat", b"kitten");
+        conflicting.insert(b"dog", b"hound");
+        let root = left.root();
+        assert_eq!(left.merge(&conflicting), Err(MergeError::ConflictingKey(b"dog".to_vec())));
+        assert_eq!(left.root(), root);
+        assert_eq!(left.get(b"cat"), None);
+        
+        let mut empty = MPTBuilder::new();
+        assert_eq!(empty.merge(&MPTBuilder::new()), Ok(crate::mpt::EMPTY_TRIE_ROOT));
+    }
+    
+    #[test]
+    fn test_builder_trie_diff_round_trip() {
+        let keccak_key = |i: u32| crate::rlp_encoding::keccak256(&i.to_be_bytes());
+        let mut old = MPTBuilder::new();
+        for i in 0..50u32 {
+            old.insert(&keccak_key(i), &i.to_be_bytes().repeat(10));
+        }
+        let mut new = old.clone();
+        for i in 0..10u32 {
+            new.delete(&keccak_key(i));
+        }
+        for i in 10..20u32 {
+            new.insert(&keccak_key(i), b"updated value long enough to be hashed by its parent");
+        }
+        for i in 50..60u32 {
+            new.insert(&keccak_key(i), &i.to_be_bytes());
+        }
+        
+        let diff = old.trie_diff(&new);
+        assert_eq!((diff.deletions.len(), diff.insertions.len(), diff.updates.len()), (10, 10, 10));
+        assert_eq!(old.trie_diff(&old), TrieDiff::default());
+        
+        let mut patched = old.clone();
+        assert_eq!(patched.apply_diff(&diff), Ok(new.root().unwrap()));
+        assert_eq!(patched.root(), new.root());
+        
+        // Reapplying fails up front and changes nothing
+        let root = patched.root();
+        assert_eq!(
+            patched.apply_diff(&diff),
+            Err(ApplyDiffError::MissingDeleteTarget(diff.deletions[0].clone()))
+        );
+        assert_eq!(patched.root(), root);
+        let inserts_only = TrieDiff { insertions: diff.insertions.clone(), ..TrieDiff::default() };
+        assert_eq!(
+            patched.apply_diff(&inserts_only),
+            Err(ApplyDiffError::ConflictingInsert(diff.insertions[0].0.clone()))
+        );
+        let stale_update = TrieDiff { updates: vec![(keccak_key(0).to_vec(), b"gone".to_vec())], ..TrieDiff::default() };
+        assert_eq!(
+            patched.apply_diff(&stale_update),
+            Err(ApplyDiffError::MissingUpdateTarget(keccak_key(0).to_vec()))
+        );
+        assert_eq!(patched.root(), root);
+        
+        // Diffing down to nothing empties the trie
+        let mut emptied = old.clone();
+        assert_eq!(emptied.apply_diff(&old.trie_diff(&MPTBuilder::new())), Ok(crate::mpt::EMPTY_TRIE_ROOT));
+    }
+    
+    #[test]
+    fn test_builder_intersect_and_symmetric_difference() {
+        let mut left = MPTBuilder::new();
+        left.insert(b"do", b"verb");
+        left.insert(b"dog", b"puppy");
+        left.insert(b"doge", b"coin");
+        let mut right = MPTBuilder::new();
+        right.insert(b"dog", b"hound");
+        right.insert(b"horse", b"stallion");
+        let mut disjoint = MPTBuilder::new();
+        disjoint.insert(b"cat", b"kitten");
+        
+        // Values come from `self`
+        assert_eq!(left.intersect(&right).flatten(), vec![(b"dog".to_vec(), b"puppy".to_vec())]);
+        assert_eq!(
+            left.symmetric_difference(&right).flatten(),
+            vec![
+                (b"do".to_vec(), b"verb".to_vec()),
+                (b"doge".to_vec(), b"coin".to_vec()),
+                (b"horse".to_vec(), b"stallion".to_vec()),
+            ]
+        );
+        
+        assert_eq!(left.intersect(&disjoint).root(), None);
+        assert_eq!(left.intersect(&left).root(), left.root());
+        assert_eq!(left.symmetric_difference(&left).root(), None);
+        assert_eq!(left.symmetric_difference(&MPTBuilder::new()).root(), left.root());
+    }
+    
+    #[test]
+    fn test_builder_delete() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        let root = builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        builder.insert(b"horse", b"stallion");
+        
+        assert!(builder.delete(b"horse"));
+        assert!(builder.delete(b"doge"));
+        assert!(!builder.delete(b"doge"));
+        assert!(!builder.delete(b"d"));
+        assert_eq!(builder.root(), Some(root));
+        
+        // A branch value, then the last keys
+        assert!(builder.delete(b"do"));
+        assert_eq!(builder.flatten(), vec![(b"dog".to_vec(), b"puppy".to_vec())]);
+        assert!(builder.delete(b"dog"));
+        assert_eq!(builder.root(), None);
+        assert!(!builder.delete(b"dog"));
+        builder.verify_internal_consistency().unwrap();
+    }
+    
+    #[test]
+    fn test_builder_delete_many() {
+        let keys: Vec<[u8; 32]> = (0..100u32).map(|i| crate::rlp_encoding::keccak256(&i.to_be_bytes())).collect();
+        let mut builder = MPTBuilder::new();
+        for key in &keys {
+            builder.insert(key, &key[..8]);
+        }
+        
+        // Pseudo-random half of the keys, by the top bit of a second hash
+        let (deleted, kept): (Vec<&[u8; 32]>, Vec<&[u8; 32]>) =
+            keys.iter().partition(|key| crate::rlp_encoding::keccak256(&key[..])[0] < 0x80);
+        assert!(!deleted.is_empty() && !kept.is_empty());
+        
+        let mut checked = builder.clone();
+        let root = builder.delete_many(&deleted);
+        for key in &deleted {
+            assert_eq!(builder.get(&key[..]), None);
+        }
+        for key in &kept {
+            assert_eq!(builder.get(&key[..]), Some(key[..8].to_vec()));
+        }
+        
+        let mut expected = MPTBuilder::new();
+        for key in &kept {
+            expected.insert(&key[..], &key[..8]);
+        }
+        assert_eq!(Some(root), expected.root());
+        
+        // Missing keys are reported and nothing is deleted
+        let before = checked.root();
+        assert_eq!(
+            checked.delete_many_checked([&deleted[0][..], b"missing"]),
+            Err(vec![b"missing".to_vec()])
+        );
+        assert_eq!(checked.root(), before);
+        assert_eq!(checked.delete_many_checked(&deleted), Ok(root));
+    }
+    
+    #[test]
+    fn test_builder_root_or_empty() {
+        let mut builder = MPTBuilder::new();
+        assert_eq!(builder.root(), None);
+        assert_eq!(builder.root_or_empty(), crate::mpt::EMPTY_TRIE_ROOT);
+        
+        let root = builder.insert(b"key", b"value");
+        assert_eq!(builder.root_or_empty(), root);
+    }
+    
+    #[test]
+    fn test_builder_insert_new() {
+        let mut builder = MPTBuilder::new();
+        builder.insert_new(b"doge", b"coin-value-long-enough-to-hash-22").unwrap();
+        
+        // Keys that are a prefix or an extension of an existing key are new
+        builder.insert_new(b"dog", b"puppy-value-long-enough-to-hash-1").unwrap();
+        let root = builder.insert_new(b"doges", b"plural-value-long-enough-to-hash").unwrap();
+        assert_eq!(builder.root(), Some(root));
+        
+        assert_eq!(builder.insert_new(b"dog", b"other"), Err(InsertError::KeyAlreadyExists));
+        assert_eq!(builder.insert_new(b"doge", b"other"), Err(InsertError::KeyAlreadyExists));
+        assert_eq!(builder.root(), Some(root));
+        assert_eq!(builder.get(b"dog"), Some(b"puppy-value-long-enough-to-hash-1".to_vec()));
+        
+        assert!(builder.contains_key(b"doges"));
+        assert!(!builder.contains_key(b"do"));
+        builder.verify_internal_consistency().unwrap();
+    }
+
+    #[test]
+    fn test_builder_node_count_by_type() {
+        assert_eq!(MPTBuilder::new().node_count_by_type(), (0, 0, 0));
+        
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        builder.insert(b"horse", b"stallion");
+        
+        // ext [6] -> branch { 4: ext [6,f] -> branch("verb") { 6: ext [7] -> branch("puppy")
+        // { 6: leaf [5] "coin" } }, 8: leaf [6,f,7,2,7,3,6,5] "stallion" }
+        assert_eq!(builder.node_count_by_type(), (2, 3, 3));
+        assert_eq!(builder.total_node_count(), 8);
+        
+        // Overwriting a value replaces nodes rather than adding to the count
+        builder.insert(b"doge", b"wow");
+        assert_eq!(builder.node_count_by_type(), (2, 3, 3));
+    }
+    
+    #[test]
+    fn test_builder_node_count_single_byte_keys() {
+        let mut builder = MPTBuilder::new();
+        for key in 0..=255u8 {
+            builder.insert(&[key], b"value");
+        }
+        
+        // Two nibbles per key: a full root branch over 16 full branches of empty-path leaves
+        assert_eq!(builder.node_count_by_type(), (256, 0, 17));
+        assert_eq!(builder.total_node_count(), 273);
+    }
+
+    #[test]
+    fn test_builder_insert_rejects_invalid_nibble() {
+        let mut builder = MPTBuilder::new();
+        let root = builder.insert(b"dog", b"puppy-value-long-enough-to-hash-1");
+        let nodes = builder.nodes.len();
+        
+        // Would index past the root's children instead of panicking
+        let result = builder.insert_at(builder.root, &[6, 4, 0x10], b"x".to_vec());
+        assert_eq!(result, Err(InsertError::InvalidNibble(0x10)));
+        assert_eq!(builder.insert_at(builder.root, &[0xff], b"x".to_vec()), Err(InsertError::InvalidNibble(0xff)));
+        assert_eq!(builder.insert_at(None, &[1, 2, 16], b"x".to_vec()), Err(InsertError::InvalidNibble(16)));
+        assert_eq!(builder.nodes.len(), nodes);
+        assert_eq!(builder.root(), Some(root));
+    }
+    
+    #[test]
+    fn test_builder_get_or_insert() {
+        let mut builder = MPTBuilder::new();
+        let root = builder.insert(b"count", &[7]);
+        
+        // Present: the value is returned and nothing is written
+        assert_eq!(builder.get_or_insert(b"count", &[0]), (root, vec![7]));
+        assert_eq!(builder.get_or_default(b"count", vec![0]), vec![7]);
+        assert_eq!(builder.root(), Some(root));
+        
+        // Absent: get_or_default leaves the trie alone, get_or_insert writes the default
+        assert_eq!(builder.get_or_default(b"other", vec![0]), vec![0]);
+        assert_eq!(builder.root(), Some(root));
+        let (new_root, value) = builder.get_or_insert(b"other", &[0]);
+        assert_eq!(value, vec![0]);
+        assert_ne!(new_root, root);
+        assert_eq!(builder.root(), Some(new_root));
+        assert_eq!(builder.get(b"other"), Some(vec![0]));
+        
+        // Pending writes are seen
+        builder.insert_incremental(b"pending", &[9]);
+        assert_eq!(builder.get_or_insert(b"pending", &[0]).1, vec![9]);
+    }
+    
+    #[test]
+    fn test_builder_contains_prefix() {
+        assert!(!MPTBuilder::new().contains_prefix(b""));
+        
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"foo/1", b"one");
+        builder.insert(b"foo/2", b"two");
+        builder.insert(b"bar/1", b"three");
+        
+        assert!(builder.contains_prefix(b"foo/"));
+        assert!(builder.contains_prefix(b"fo"));
+        assert!(builder.contains_prefix(b"bar/1"));
+        assert!(!builder.contains_prefix(b"baz/"));
+        assert!(!builder.contains_prefix(b"foo/3"));
+        assert!(!builder.contains_prefix(b"bar/1/x"));
+        
+        assert_eq!(builder.count_with_prefix(b"foo/"), 2);
+        assert_eq!(builder.count_with_prefix(b"b"), 1);
+        assert_eq!(builder.count_with_prefix(b""), 3);
+        assert_eq!(builder.count_with_prefix(b"baz/"), 0);
+        
+        // A key that is itself a prefix of others counts too
+        builder.insert(b"foo", b"zero");
+        assert_eq!(builder.count_with_prefix(b"foo"), 3);
+        assert_eq!(builder.count_with_prefix(b"foo/"), 2);
+    }
+    
+    #[test]
+    fn test_builder_extension_paths() {
+        assert!(MPTBuilder::new().get_extension_paths().is_empty());
+        assert_eq!(MPTBuilder::new().longest_extension_path(), None);
+        
+        let mut builder = MPTBuilder::new();
+        for key in ["storage/account/0x1234/slot/0", "storage/account/0x1234/slot/1", "storage/account/0x1234/slot/2"] {
+            builder.insert(key.as_bytes(), b"value");
+        }
+        
+        // The shared prefix plus the high nibble of '0'..'2' (0x3_)
+        let prefix = to_nibbles(b"storage/account/0x1234/slot/");
+        let longest = builder.longest_extension_path().unwrap();
+        assert_eq!(longest.len(), prefix.len() + 1);
+        assert!(longest.starts_with(&prefix));
+        assert_eq!(builder.get_extension_paths(), vec![(longest, prefix.len() + 1)]);
+        
+        // 's' (0x73) and 'z' (0x7a) share a nibble, so the root becomes an extension [7]
+        // over a branch, and "zz1"/"zz2" share "7a3" after the branch nibble
+        builder.insert(b"zz1", b"value");
+        builder.insert(b"zz2", b"value");
+        let lengths: Vec<usize> = builder.get_extension_paths().iter().map(|(_, len)| *len).collect();
+        assert_eq!(lengths, vec![prefix.len() - 1, 3, 1]);
+    }
+    
+    #[test]
+    fn test_builder_depth_shared_prefix() {
+        assert_eq!(MPTBuilder::new().avg_depth(), 0.0);
+        assert_eq!(MPTBuilder::new().max_depth(), 0);
+        
+        // 256 keys sharing 10 nibbles, then diverging over a full byte:
+        // extension -> branch -> branch -> leaf for every key
+        let mut builder = MPTBuilder::new();
+        let key = |i: u8| [&[0xab, 0xcd, 0xef, 0x01, 0x23, i][..], &[0u8; 26]].concat();
+        for i in 0..=255u8 {
+            builder.insert(&key(i), &[i; 40]);
+        }
+        
+        assert_eq!(builder.depth_of(&key(7)), Some(4));
+        assert_eq!(builder.depth_of(&key(7)), builder.get_proof(&key(7)).map(|p| p.len()));
+        assert_eq!(builder.depth_of(&[0xab; 32]), None);
+        assert_eq!(builder.avg_depth(), 4.0);
+        assert_eq!(builder.max_depth() as f64, builder.avg_depth());
+        
+        // Embedded leaves are not separate proof nodes
+        let mut small = MPTBuilder::new();
+        small.insert(b"do", b"verb");
+        small.insert(b"dog", b"puppy");
+        for key in [&b"do"[..], b"dog"] {
+            assert_eq!(small.depth_of(key), small.get_proof(key).map(|p| p.len()));
+        }
+        let expected = (small.depth_of(b"do").unwrap() + small.depth_of(b"dog").unwrap()) as f64 / 2.0;
+        assert_eq!(small.avg_depth(), expected);
+    }
+    
+    /// Keccak256 that counts how many nodes it hashed
+    #[derive(Clone, Default)]
+    struct CountingHasher(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+    
+    impl NodeHasher for CountingHasher {
+        fn hash(&self, data: &[u8]) -> H256 {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            crate::rlp_encoding::keccak256(data)
+        }
+    }
+    
+    #[test]
+    fn test_builder_commit_hash_count() {
+        let hasher = CountingHasher::default();
+        let count = hasher.0.clone();
+        let mut builder = MPTBuilder::with_hasher(hasher);
+        let keys: Vec<H256> = (0..10_000u32).map(|i| crate::rlp_encoding::keccak256(&i.to_be_bytes())).collect();
+        for key in &keys {
+            builder.insert_incremental(key, b"initial value");
+        }
+        builder.commit();
+        
+        // One update only rehashes the path from the root, about log16(10000) nodes
+        count.store(0, std::sync::atomic::Ordering::Relaxed);
+        builder.insert_incremental(&keys[1234], b"updated value");
+        builder.commit();
+        let single = count.load(std::sync::atomic::Ordering::Relaxed);
+        assert!(single <= 14, "{} hashes for one update", single);
+        
+        // Updates sharing upper nodes hash them once per commit instead of once per insert
+        let mut sequential = builder.clone();
+        count.store(0, std::sync::atomic::Ordering::Relaxed);
+        for key in &keys[..100] {
+            sequential.insert(key, b"batch value");
+        }
+        let inserted = count.swap(0, std::sync::atomic::Ordering::Relaxed);
+        for key in &keys[..100] {
+            builder.insert_incremental(key, b"batch value");
+        }
+        let root = builder.commit();
+        let committed = count.load(std::sync::atomic::Ordering::Relaxed);
+        assert_eq!(Some(root), sequential.root());
+        assert!(committed < inserted, "{} hashes committed vs {} inserted", committed, inserted);
+    }
+    
+    #[test]
+    fn test_builder_insert_incremental_pending() {
+        let mut builder = MPTBuilder::new();
+        let root = builder.insert(b"dog", b"puppy");
+        
+        builder.insert_incremental(b"dog", b"hound");
+        builder.insert_incremental(b"doge", b"coin");
+        builder.insert_incremental(b"doge", b"wow");
+        assert_eq!(builder.pending().len(), 2);
+        assert_eq!(builder.root(), Some(root));
+        assert_eq!(builder.get(b"doge"), None);
+        
+        // A direct insert commits the buffered writes first
+        builder.insert(b"dog", b"pup");
+        assert!(builder.pending().is_empty());
+        assert_eq!(builder.get(b"dog"), Some(b"pup".to_vec()));
+        assert_eq!(builder.get(b"doge"), Some(b"wow".to_vec()));
+        
+        assert_eq!(builder.commit(), builder.root().unwrap());
+        assert_eq!(MPTBuilder::new().commit(), crate::mpt::EMPTY_TRIE_ROOT);
+        builder.verify_internal_consistency().unwrap();
+    }
+    
+    proptest::proptest! {
+        #[test]
+        fn prop_commit_matches_insert(
+            initial in proptest::collection::vec(
+                (
+                    proptest::collection::vec(proptest::sample::select(vec![0x00u8, 0x01, 0x10, 0x11, 0xf0, 0xff]), 0..5),
+                    proptest::collection::vec(proptest::num::u8::ANY, 1..40),
+                ),
+                0..30,
+            ),
+            updates in proptest::collection::vec(
+                (
+                    proptest::collection::vec(proptest::sample::select(vec![0x00u8, 0x01, 0x10, 0x11, 0xf0, 0xff]), 0..5),
+                    proptest::collection::vec(proptest::num::u8::ANY, 1..40),
+                ),
+                1..30,
+            ),
+        ) {
+            let mut inserted = MPTBuilder::new();
+            let mut committed = MPTBuilder::new();
+            for (key, value) in &initial {
+                inserted.insert(key, value);
+                committed.insert(key, value);
+            }
+            for (key, value) in &updates {
+                inserted.insert(key, value);
+                committed.insert_incremental(key, value);
+            }
+            let root = committed.commit();
+            proptest::prop_assert_eq!(Some(root), inserted.root());
+            proptest::prop_assert!(committed.verify_internal_consistency().is_ok());
+        }
+        
+        #[test]
+        fn prop_delete_many_matches_rebuild(
+            pairs in proptest::collection::vec(
+                (
+                    proptest::collection::vec(proptest::sample::select(vec![0x00u8, 0x01, 0x10, 0x11, 0xf0, 0xff]), 0..5),
+                    proptest::collection::vec(proptest::num::u8::ANY, 1..40),
+                ),
+                0..30,
+            ),
+            deletions in proptest::collection::vec(
+                proptest::collection::vec(proptest::sample::select(vec![0x00u8, 0x01, 0x10, 0x11, 0xf0, 0xff]), 0..5),
+                0..30,
+            ),
+        ) {
+            let mut batched = MPTBuilder::new();
+            for (key, value) in &pairs {
+                batched.insert(key, value);
+            }
+            let mut sequential = batched.clone();
+            
+            let root = batched.delete_many(&deletions);
+            for key in &deletions {
+                sequential.delete(key);
+            }
+            proptest::prop_assert_eq!(root, sequential.root_or_empty());
+            
+            // Deleting leaves the same trie as never inserting
+            let mut rebuilt = MPTBuilder::new();
+            for (key, value) in batched.flatten() {
+                rebuilt.insert(&key, &value);
+            }
+            proptest::prop_assert_eq!(root, rebuilt.root_or_empty());
+            for key in &deletions {
+                proptest::prop_assert_eq!(batched.get(key), None);
+            }
+            proptest::prop_assert!(batched.verify_internal_consistency().is_ok());
+        }
     }
 }
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use crate::mpt::verify_proof;
+use crate::types::H256;
+
+/// Proofs for several keys sharing one dictionary of nodes
+///
+/// Each proof is the path of node hashes from the root, as in `MPTBuilder::get_proof`,
+/// with every node stored once in `node_dict` however many proofs pass through it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompactProof {
+    pub node_dict: HashMap<H256, Vec<u8>>,
+    pub proofs: Vec<Vec<H256>>,
+}
+
+impl CompactProof {
+    /// Add a proof given as RLP-encoded nodes, keyed by `hash`
+    pub(crate) fn push<F: Fn(&[u8]) -> H256>(&mut self, nodes: Vec<Vec<u8>>, hash: F) {
+        let path = nodes
+            .into_iter()
+            .map(|rlp| {
+                let node_hash = hash(&rlp);
+                self.node_dict.entry(node_hash).or_insert(rlp);
+                node_hash
+            })
+            .collect();
+        self.proofs.push(path);
+    }
+
+    /// Expand proof `proof_index` back into RLP-encoded nodes
+    ///
+    /// Returns `None` if the index is out of range or a reference is missing from `node_dict`.
+    pub fn expand(&self, proof_index: usize) -> Option<Vec<Vec<u8>>> {
+        self.proofs
+            .get(proof_index)?
+            .iter()
+            .map(|hash| self.node_dict.get(hash).cloned())
+            .collect()
+    }
+
+    /// Bytes transmitted: every dictionary node once plus 32 bytes per reference
+    pub fn size_bytes(&self) -> usize {
+        let nodes: usize = self.node_dict.values().map(Vec::len).sum();
+        let references: usize = self.proofs.iter().map(Vec::len).sum();
+        nodes + 32 * references
+    }
+}
+
+/// Verify proof `proof_index` of a `CompactProof`
+///
+/// The dictionary's keys are not trusted: the expanded nodes are checked with
+/// `verify_proof` from the root down, exactly like a full proof.
+pub fn verify_compact_proof(
+    root: &H256,
+    key: &[u8],
+    value: &[u8],
+    proof: &CompactProof,
+    proof_index: usize,
+) -> bool {
+    match proof.expand(proof_index) {
+        Some(nodes) => verify_proof(root, key, value, &nodes),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::MPTBuilder;
+    use crate::mpt::proof_size_bytes;
+    use crate::rlp_encoding::keccak256;
+
+    #[test]
+    fn test_compact_proof_roundtrip() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy-value-long-enough-to-be-hashed");
+        let root = builder.insert(b"horse", b"stallion-value-long-enough-to-be-hashed");
+
+        let single = builder.get_compact_proof(b"dog").unwrap();
+        assert_eq!(single.proofs.len(), 1);
+        assert_eq!(single.expand(0), builder.get_proof(b"dog"));
+        assert!(verify_compact_proof(&root, b"dog", b"puppy-value-long-enough-to-be-hashed", &single, 0));
+        assert!(!verify_compact_proof(&root, b"dog", b"kitten", &single, 0));
+        assert!(!verify_compact_proof(&root, b"dog", b"puppy-value-long-enough-to-be-hashed", &single, 1));
+
+        let keys: [&[u8]; 2] = [b"dog", b"horse"];
+        let multi = builder.get_multi_compact_proof(&keys).unwrap();
+        assert!(verify_compact_proof(&root, b"horse", b"stallion-value-long-enough-to-be-hashed", &multi, 1));
+        assert!(!verify_compact_proof(&root, b"horse", b"stallion-value-long-enough-to-be-hashed", &multi, 0));
+
+        // A tampered dictionary entry no longer hashes to the root
+        let mut tampered = multi.clone();
+        tampered.node_dict.insert(multi.proofs[0][0], b"garbage".to_vec());
+        assert!(!verify_compact_proof(&root, b"dog", b"puppy-value-long-enough-to-be-hashed", &tampered, 0));
+
+        assert_eq!(builder.get_multi_compact_proof(&[b"dog", b"missing"]), None);
+        assert_eq!(builder.get_compact_proof(b"missing"), None);
+    }
+
+    #[test]
+    fn test_compact_proof_size_shared_prefix() {
+        // 100 keys under the prefix 0xabcd among 1000 random ones
+        let mut builder = MPTBuilder::new();
+        for i in 0..1000u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        let keys: Vec<Vec<u8>> = (0..100u32)
+            .map(|i| [&[0xab, 0xcd][..], &keccak256(&i.to_le_bytes())].concat())
+            .collect();
+        for key in &keys {
+            builder.insert(key, &[0x42; 40]);
+        }
+        let root = builder.root().unwrap();
+
+        let key_refs: Vec<&[u8]> = keys.iter().map(Vec::as_slice).collect();
+        let compact = builder.get_multi_compact_proof(&key_refs).unwrap();
+        for (i, key) in keys.iter().enumerate() {
+            assert!(verify_compact_proof(&root, key, &[0x42; 40], &compact, i));
+        }
+
+        let full: usize = keys.iter().map(|key| proof_size_bytes(&builder.get_proof(key).unwrap())).sum();
+        let compact_size = compact.size_bytes();
+        assert!(compact_size * 3 < full, "compact {} bytes vs full {} bytes", compact_size, full);
+    }
+}
@@ -0,0 +1,292 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use crate::rlp_encoding::{encode_u256, keccak256};
+use crate::types::{EthAccount, MPTProofInput, ProofType, H256, PROOF_FORMAT_VERSION};
+
+/// An `eth_getProof` response (EIP-1186), with quantities and data as `0x` hex strings
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EIP1186AccountProofResponse {
+    pub address: String,
+    pub account_proof: Vec<String>,
+    pub balance: String,
+    pub code_hash: String,
+    pub nonce: String,
+    pub storage_hash: String,
+    pub storage_proof: Vec<EIP1186StorageProof>,
+}
+
+/// One entry of `storageProof` in an `eth_getProof` response
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EIP1186StorageProof {
+    pub key: String,
+    pub value: String,
+    pub proof: Vec<String>,
+}
+
+/// Error converting an `eth_getProof` response
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eip1186Error {
+    /// A field is not valid hex
+    InvalidHex,
+    /// A field has the wrong length (e.g. a word longer than 32 bytes)
+    InvalidLength,
+    /// The nonce does not fit in a u64
+    InvalidNonce,
+    /// `storageProof` has no entry at this index
+    MissingStorageProof(usize),
+    /// The storage proof is for a different slot than the one requested
+    SlotMismatch,
+}
+
+impl EIP1186AccountProofResponse {
+    /// The 20-byte account address
+    pub fn address(&self) -> Result<[u8; 20], Eip1186Error> {
+        decode_hex(&self.address)?.try_into().map_err(|_| Eip1186Error::InvalidLength)
+    }
+
+    /// The account fields as stored in the state trie
+    pub fn account(&self) -> Result<EthAccount, Eip1186Error> {
+        let nonce = decode_word(&self.nonce)?;
+        if nonce[..24].iter().any(|&b| b != 0) {
+            return Err(Eip1186Error::InvalidNonce);
+        }
+        Ok(EthAccount {
+            nonce: u64::from_be_bytes(nonce[24..].try_into().unwrap()),
+            balance: decode_word(&self.balance)?,
+            storage_root: decode_word(&self.storage_hash)?,
+            code_hash: decode_word(&self.code_hash)?,
+        })
+    }
+
+    /// The RLP-encoded account proof nodes
+    pub fn account_proof_nodes(&self) -> Result<Vec<Vec<u8>>, Eip1186Error> {
+        self.account_proof.iter().map(|node| decode_hex(node)).collect()
+    }
+}
+
+impl MPTProofInput {
+    /// Storage proof `slot_index` of an `eth_getProof` response, against the account's `storageHash`
+    ///
+    /// The key is `keccak256(slot)`, and the value is RLP-encoded as a minimal big-endian
+    /// integer as in the storage trie, so `0x00..01` becomes `[0x01]`. A zero slot is not
+    /// stored at all, so its proof is an exclusion proof: the input gets an empty value and
+    /// `ProofType::Exclusion`.
+    pub fn from_eth_storage_proof(
+        proof_response: &EIP1186AccountProofResponse,
+        slot_index: usize,
+    ) -> Result<Self, Eip1186Error> {
+        let storage = proof_response
+            .storage_proof
+            .get(slot_index)
+            .ok_or(Eip1186Error::MissingStorageProof(slot_index))?;
+        let (value, proof_type) = storage_claim(&decode_word(&storage.value)?);
+        Ok(Self {
+            version: PROOF_FORMAT_VERSION,
+            key: keccak256(&decode_word(&storage.key)?).to_vec(),
+            value,
+            proof: storage.proof.iter().map(|node| decode_hex(node)).collect::<Result<_, _>>()?,
+            root: decode_word(&proof_response.storage_hash)?,
+            proof_type,
+        })
+    }
+
+    /// Storage proof for `slot` from alloy's `eth_getProof` response, against `storage_hash`
+    ///
+    /// Encodes the key and value, and turns a zero slot into an exclusion proof, as
+    /// [`Self::from_eth_storage_proof`] does.
+    #[cfg(feature = "alloy")]
+    pub fn from_alloy_storage_proof(
+        storage_hash: alloy_primitives::B256,
+        slot: alloy_primitives::B256,
+        proof: &alloy_rpc_types_eth::EIP1186StorageProof,
+    ) -> Result<Self, Eip1186Error> {
+        if proof.key.as_b256() != slot {
+            return Err(Eip1186Error::SlotMismatch);
+        }
+        let (value, proof_type) = storage_claim(&proof.value.to_be_bytes::<32>());
+        Ok(Self {
+            version: PROOF_FORMAT_VERSION,
+            key: keccak256(&slot.0).to_vec(),
+            value,
+            proof: proof.proof.iter().map(|node| node.to_vec()).collect(),
+            root: storage_hash.0,
+            proof_type,
+        })
+    }
+}
+
+/// The proof input value and claim for a storage slot holding `word`
+///
+/// Zero slots are deleted from the storage trie, so `eth_getProof` proves them absent.
+fn storage_claim(word: &H256) -> (Vec<u8>, ProofType) {
+    if word.iter().all(|&b| b == 0) {
+        (Vec::new(), ProofType::Exclusion)
+    } else {
+        (encode_u256(word), ProofType::Inclusion)
+    }
+}
+
+/// Decode `0x`-prefixed hex data with an even number of digits
+pub(crate) fn decode_hex(s: &str) -> Result<Vec<u8>, Eip1186Error> {
+    let digits = s.strip_prefix("0x").unwrap_or(s).as_bytes();
+    if digits.len() % 2 == 1 {
+        return Err(Eip1186Error::InvalidHex);
+    }
+    let hex = |digit: u8| (digit as char).to_digit(16).ok_or(Eip1186Error::InvalidHex);
+    digits
+        .iter()
+        .step_by(2)
+        .zip(digits.iter().skip(1).step_by(2))
+        .map(|(&hi, &lo)| Ok((hex(hi)? << 4 | hex(lo)?) as u8))
+        .collect()
+}
+
+/// Decode a hex quantity (`0x1f`) or word into a big-endian u256
+fn decode_word(s: &str) -> Result<H256, Eip1186Error> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    if digits.len() > 64 {
+        return Err(Eip1186Error::InvalidLength);
+    }
+    Ok(decode_hex(&alloc::format!("{:0>64}", digits))?.try_into().unwrap())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::builder::MPTBuilder;
+    use crate::state_trie::WorldStateTrieBuilder;
+    use crate::test_utils::{hex, word};
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    /// A response for slot 0 holding 1, with the value as a full 32-byte word
+    fn response() -> (EIP1186AccountProofResponse, H256) {
+        let mut storage = MPTBuilder::new();
+        storage.insert(&keccak256(&word(3)), &encode_u256(&word(0x1234)));
+        let storage_root = storage.insert(&keccak256(&word(0)), &encode_u256(&word(1)));
+
+        let address = [0x42; 20];
+        let account = EthAccount { nonce: 5, balance: word(10), storage_root, code_hash: keccak256(b"code") };
+        let mut state = WorldStateTrieBuilder::new();
+        state.insert_account(&[0x01; 20], &account);
+        let state_root = state.insert_account(&address, &account);
+
+        let response = EIP1186AccountProofResponse {
+            address: hex(&address),
+            account_proof: state.get_account_proof(&address).unwrap().iter().map(|n| hex(n)).collect(),
+            balance: "0xa".to_string(),
+            code_hash: hex(&account.code_hash),
+            nonce: "0x5".to_string(),
+            storage_hash: hex(&storage_root),
+            storage_proof: vec![EIP1186StorageProof {
+                key: "0x0".to_string(),
+                value: hex(&word(1)),
+                proof: storage.get_proof(&keccak256(&word(0))).unwrap().iter().map(|n| hex(n)).collect(),
+            }],
+        };
+        (response, state_root)
+    }
+
+    #[test]
+    fn test_from_eth_storage_proof_minimal_value() {
+        let (response, _) = response();
+        assert_eq!(response.storage_proof[0].value, format!("0x{}01", "0".repeat(62)));
+
+        let input = MPTProofInput::from_eth_storage_proof(&response, 0).unwrap();
+        // Leading zero bytes are stripped before RLP-encoding, as in the trie
+        assert_eq!(input.value, vec![0x01]);
+        assert_eq!(input.key, keccak256(&word(0)).to_vec());
+        assert!(crate::mpt::verify_proof(&input.root, &input.key, &input.value, &input.proof));
+
+        assert_eq!(
+            MPTProofInput::from_eth_storage_proof(&response, 1).unwrap_err(),
+            Eip1186Error::MissingStorageProof(1)
+        );
+    }
+
+    #[test]
+    fn test_from_eth_storage_proof_missing_slot() {
+        let mut storage = MPTBuilder::new();
+        storage.insert(&keccak256(&word(0)), &encode_u256(&word(1)));
+        let storage_root = storage.insert(&keccak256(&word(3)), &encode_u256(&word(0x1234)));
+
+        // `eth_getProof` reports an unset slot as `0x0` with a proof of its absence
+        let (mut response, _) = response();
+        response.storage_hash = hex(&storage_root);
+        response.storage_proof[0] = EIP1186StorageProof {
+            key: "0x7".to_string(),
+            value: "0x0".to_string(),
+            proof: storage.get_exclusion_proof(&keccak256(&word(7))).unwrap().iter().map(|n| hex(n)).collect(),
+        };
+
+        let input = MPTProofInput::from_eth_storage_proof(&response, 0).unwrap();
+        assert_eq!(input.proof_type, ProofType::Exclusion);
+        assert!(input.value.is_empty());
+        assert_eq!(input.verify(), Ok(true));
+    }
+
+    #[test]
+    fn test_eip1186_account_fields() {
+        let (response, state_root) = response();
+        let address = response.address().unwrap();
+        let account = response.account().unwrap();
+        assert_eq!(account.nonce, 5);
+        assert_eq!(account.balance, word(10));
+        let nodes = response.account_proof_nodes().unwrap();
+        assert_eq!(crate::mpt::verify_account_proof(&state_root, &address, &account, &nodes), Ok(()));
+
+        let mut bad = response.clone();
+        bad.storage_hash = "0xzz".to_string();
+        assert_eq!(MPTProofInput::from_eth_storage_proof(&bad, 0).unwrap_err(), Eip1186Error::InvalidHex);
+        bad.storage_hash = format!("0x{}", "1".repeat(66));
+        assert_eq!(MPTProofInput::from_eth_storage_proof(&bad, 0).unwrap_err(), Eip1186Error::InvalidLength);
+        bad.nonce = format!("0x1{}", "0".repeat(16));
+        assert_eq!(bad.account().unwrap_err(), Eip1186Error::InvalidNonce);
+        bad.address = "0x42".to_string();
+        assert_eq!(bad.address().unwrap_err(), Eip1186Error::InvalidLength);
+    }
+
+    #[cfg(feature = "alloy")]
+    #[test]
+    fn test_from_alloy_storage_proof() {
+        use alloy_primitives::{Bytes, B256, U256};
+        use alloy_rpc_types_eth::EIP1186StorageProof;
+
+        let mut storage = MPTBuilder::new();
+        storage.insert(&keccak256(&word(0)), &encode_u256(&word(1)));
+        let storage_root = storage.insert(&keccak256(&word(3)), &encode_u256(&word(0x1234)));
+        let nodes = storage.get_proof(&keccak256(&word(3))).unwrap();
+
+        let slot = B256::from(word(3));
+        let proof = EIP1186StorageProof::new(
+            slot.into(),
+            U256::from(0x1234),
+            nodes.iter().map(|n| Bytes::copy_from_slice(n)).collect(),
+        );
+        let input = MPTProofInput::from_alloy_storage_proof(B256::from(storage_root), slot, &proof).unwrap();
+        assert_eq!(input.value, vec![0x82, 0x12, 0x34]);
+        assert_eq!(input.key, keccak256(&word(3)).to_vec());
+        assert_eq!(input.proof, nodes);
+        assert_eq!(input.verify(), Ok(true));
+
+        assert_eq!(
+            MPTProofInput::from_alloy_storage_proof(B256::from(storage_root), B256::from(word(0)), &proof)
+                .unwrap_err(),
+            Eip1186Error::SlotMismatch
+        );
+
+        // An unset slot comes back as zero with an exclusion proof
+        let missing = B256::from(word(7));
+        let exclusion = storage.get_exclusion_proof(&keccak256(&word(7))).unwrap();
+        let proof = EIP1186StorageProof::new(
+            missing.into(),
+            U256::ZERO,
+            exclusion.iter().map(|n| Bytes::copy_from_slice(n)).collect(),
+        );
+        let input = MPTProofInput::from_alloy_storage_proof(B256::from(storage_root), missing, &proof).unwrap();
+        assert_eq!(input.proof_type, ProofType::Exclusion);
+        assert_eq!(input.verify(), Ok(true));
+    }
+}
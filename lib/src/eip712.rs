@@ -0,0 +1,117 @@
+use alloc::vec::Vec;
+use crate::types::H256;
+use crate::rlp_encoding::keccak256;
+use crate::MPTProofOutput;
+
+/// EIP-712 type string for `MPTProofOutput`
+pub const MPT_PROOF_OUTPUT_TYPE: &str = "MPTProofOutput(bool verified,bytes32 root,bytes key,bytes value)";
+
+/// Compute the EIP-712 `typeHash` of `MPTProofOutput`
+pub fn mpt_proof_output_typehash() -> H256 {
+    keccak256(MPT_PROOF_OUTPUT_TYPE.as_bytes())
+}
+
+/// Compute the EIP-712 `hashStruct` of an `MPTProofOutput`
+/// 
+/// `keccak256(typeHash || encodeData)`, where `bool` is encoded as a 32-byte word,
+/// `bytes32` as itself and dynamic `bytes` fields by their keccak256 hash.
+pub fn mpt_proof_output_struct_hash(output: &MPTProofOutput) -> H256 {
+    let mut encoded = Vec::with_capacity(5 * 32);
+    encoded.extend_from_slice(&mpt_proof_output_typehash());
+    
+    let mut verified = [0u8; 32];
+    verified[31] = output.verified as u8;
+    encoded.extend_from_slice(&verified);
+    
+    encoded.extend_from_slice(output.root.as_slice());
+    encoded.extend_from_slice(&keccak256(&output.key));
+    encoded.extend_from_slice(&keccak256(&output.value));
+    
+    keccak256(&encoded)
+}
+
+/// Compute the final EIP-712 digest `keccak256("\x19\x01" || domainSeparator || hashStruct)`
+/// 
+/// This is the hash a contract recovers signatures against when verifying a submitted
+/// `MPTProofOutput`.
+pub fn mpt_proof_output_digest(domain_separator: &H256, output: &MPTProofOutput) -> H256 {
+    typed_data_digest(domain_separator, &mpt_proof_output_struct_hash(output))
+}
+
+fn typed_data_digest(domain_separator: &H256, struct_hash: &H256) -> H256 {
+    let mut data = [0u8; 66];
+    data[0] = 0x19;
+    data[1] = 0x01;
+    data[2..34].copy_from_slice(domain_separator);
+    data[34..].copy_from_slice(struct_hash);
+    keccak256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::h256_from_hex;
+    use alloy_sol_types::{eip712_domain, SolStruct, SolValue};
+
+    fn sample_output() -> MPTProofOutput {
+        MPTProofOutput {
+            verified: true,
+            root: [0x42; 32].into(),
+            key: b"dog".to_vec().into(),
+            value: b"puppy".to_vec().into(),
+        }
+    }
+
+    #[test]
+    fn test_typehash_matches_alloy() {
+        let output = sample_output();
+        assert_eq!(mpt_proof_output_typehash(), output.eip712_type_hash().0);
+        assert_eq!(MPTProofOutput::eip712_root_type(), MPT_PROOF_OUTPUT_TYPE);
+    }
+
+    #[test]
+    fn test_struct_hash_matches_alloy() {
+        let output = sample_output();
+        assert_eq!(mpt_proof_output_struct_hash(&output), output.eip712_hash_struct().0);
+        
+        let failed = MPTProofOutput { verified: false, ..sample_output() };
+        assert_ne!(mpt_proof_output_struct_hash(&failed), mpt_proof_output_struct_hash(&output));
+    }
+
+    #[test]
+    fn test_encode_data_uses_abi_words() {
+        // bool and bytes32 occupy the same 32-byte words as in the ABI encoding
+        let output = sample_output();
+        let abi = (output.verified, output.root).abi_encode();
+        assert_eq!(abi.len(), 64);
+        
+        let mut encoded = mpt_proof_output_typehash().to_vec();
+        encoded.extend_from_slice(&abi);
+        encoded.extend_from_slice(&keccak256(&output.key));
+        encoded.extend_from_slice(&keccak256(&output.value));
+        assert_eq!(keccak256(&encoded), mpt_proof_output_struct_hash(&output));
+    }
+
+    #[test]
+    fn test_digest_matches_alloy() {
+        let domain = eip712_domain! {
+            name: "MPTVerifier",
+            version: "1",
+            chain_id: 1,
+        };
+        let output = sample_output();
+        
+        let digest = mpt_proof_output_digest(&domain.hash_struct().0, &output);
+        assert_eq!(digest, output.eip712_signing_hash(&domain).0);
+    }
+
+    #[test]
+    fn test_digest_eip712_spec_vector() {
+        // "Mail" example from the EIP-712 specification
+        let domain_separator = h256_from_hex("f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f");
+        let struct_hash = h256_from_hex("c52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e");
+        let expected = h256_from_hex("be609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2");
+        
+        assert_eq!(typed_data_digest(&domain_separator, &struct_hash), expected);
+    }
+}
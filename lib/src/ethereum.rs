@@ -0,0 +1,57 @@
+use crate::builder::MPTBuilder;
+use crate::rlp_encoding::{encode_u256, keccak256};
+use crate::types::H256;
+
+/// Compute an account's `storageRoot` from raw `(slot, value)` pairs, e.g. a `debug_dumpBlock` dump
+/// 
+/// Each slot is stored under `keccak256(slot)` with its value RLP-encoded as a minimal
+/// integer. Zero values are skipped, since Ethereum deletes zeroed slots from the trie.
+/// Later duplicates of a slot overwrite earlier ones.
+pub fn compute_storage_root(slots: &[(H256, H256)]) -> H256 {
+    let mut builder = MPTBuilder::new();
+    for (slot, value) in slots {
+        if value.iter().all(|&b| b == 0) {
+            continue;
+        }
+        builder.insert(&keccak256(slot), &encode_u256(value));
+    }
+    builder.root_or_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{h256_from_hex, word};
+    use crate::mpt::EMPTY_TRIE_ROOT;
+
+    /// Storage slot of `mapping(address => uint256)` at `slot` for `holder`
+    fn mapping_slot(holder: [u8; 20], slot: u64) -> H256 {
+        let mut preimage = [0u8; 64];
+        preimage[12..32].copy_from_slice(&holder);
+        preimage[32..].copy_from_slice(&word(slot));
+        keccak256(&preimage)
+    }
+
+    #[test]
+    fn test_compute_storage_root_erc20() {
+        // ERC-20 layout: `balances` mapping at slot 0, `totalSupply` at slot 2.
+        // The zero balance of 0x33.. is absent from the trie.
+        let slots = vec![
+            (mapping_slot([0x11; 20], 0), word(1_000_000)),
+            (mapping_slot([0x22; 20], 0), word(250)),
+            (mapping_slot([0x33; 20], 0), word(0)),
+            (word(2), word(1_000_250)),
+        ];
+        assert_eq!(
+            compute_storage_root(&slots),
+            h256_from_hex("e1e38610ed2a70557b7035fa913350f153271d8eef03a92ca537569f31d07ee4")
+        );
+        assert_eq!(compute_storage_root(&slots), compute_storage_root(&[slots[0], slots[1], slots[3]]));
+    }
+
+    #[test]
+    fn test_compute_storage_root_empty() {
+        assert_eq!(compute_storage_root(&[]), EMPTY_TRIE_ROOT);
+        assert_eq!(compute_storage_root(&[(word(0), word(0))]), EMPTY_TRIE_ROOT);
+    }
+}
@@ -0,0 +1,343 @@
+use std::path::Path;
+use serde_json::{json, Value};
+use crate::builder::MPTBuilder;
+use crate::hasher::NodeHasher;
+use crate::rlp_encoding::hex_string;
+use crate::types::{MPTProofInput, Node, ProofType, H256, PROOF_FORMAT_VERSION};
+
+/// Error reading a JSON trie fixture
+#[derive(Debug)]
+pub enum FixtureError {
+    /// The file could not be read
+    Io(std::io::Error),
+    /// The file is not valid JSON
+    Json(serde_json::Error),
+    /// The JSON is not a fixture in either supported layout
+    InvalidFormat(&'static str),
+    /// A `0x` key, value or root is not valid hex
+    InvalidHex(String),
+    /// `verify_against_fixture` was given a fixture without a `root`
+    MissingRoot,
+    /// An exported proof file has no proof at this index
+    MissingProof(usize),
+}
+
+impl From<std::io::Error> for FixtureError {
+    fn from(err: std::io::Error) -> Self {
+        FixtureError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for FixtureError {
+    fn from(err: serde_json::Error) -> Self {
+        FixtureError::Json(err)
+    }
+}
+
+impl MPTBuilder {
+    /// Build a trie from an ethereum/tests-style JSON fixture, returning it with the
+    /// fixture's expected root if it has one
+    ///
+    /// Two layouts are accepted: a plain `{"key": "value", ...}` object, or
+    /// `{"in": ..., "root": "0x..."}` where `in` is such an object or a list of
+    /// `[key, value]` pairs applied in order. Keys and values starting with `0x` are hex,
+    /// anything else is taken as UTF-8 bytes, and a `null` value deletes the key.
+    pub fn import_from_json_fixture(path: &Path) -> Result<(Self, Option<H256>), FixtureError> {
+        parse_fixture(&std::fs::read_to_string(path)?)
+    }
+
+    /// Whether the trie built from a fixture has the fixture's expected root
+    pub fn verify_against_fixture(path: &Path) -> Result<bool, FixtureError> {
+        let (builder, expected) = Self::import_from_json_fixture(path)?;
+        let expected = expected.ok_or(FixtureError::MissingRoot)?;
+        Ok(builder.root_or_empty() == expected)
+    }
+}
+
+impl<H: NodeHasher> MPTBuilder<H> {
+    /// Write proofs of `keys` to a JSON file for external verifiers (Foundry, Hardhat, Go)
+    ///
+    /// The layout is `{"root": "0x...", "proofs": [{"key": "0x...", "value": "0x...",
+    /// "nodes": ["0x...", ...]}]}` with every byte string `0x` hex; an absent key is
+    /// exported with an empty value. Fails with `InvalidInput` if a key has no proof.
+    pub fn export_proofs_to_file(&self, path: &Path, keys: &[&[u8]]) -> std::io::Result<()> {
+        let proofs = keys
+            .iter()
+            .map(|&key| {
+                let nodes = self.get_proof(key).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("no proof for key 0x{}", hex_string(key)),
+                    )
+                })?;
+                Ok(json!({
+                    "key": hex(key),
+                    "value": hex(&self.get(key).unwrap_or_default()),
+                    "nodes": nodes.iter().map(|node| hex(node)).collect::<Vec<_>>(),
+                }))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let export = json!({ "root": hex(&self.root_or_empty()), "proofs": proofs });
+        std::fs::write(path, serde_json::to_string_pretty(&export)?)
+    }
+
+    /// The trie as nested JSON, for inspection, visualizers or diffing two states
+    /// 
+    /// Each node is an object with its `type` (`Branch`, `Extension` or `Leaf`) and `0x`
+    /// `hash`. Branches have `children` keyed by nibble (empty slots omitted) and a `value`
+    /// or `null`, extensions a `path` and `child`, and leaves a `path` and `value`. Paths
+    /// have one hex digit per nibble. The empty trie, or a missing node, is `null`.
+    pub fn to_json_tree(&self) -> Value {
+        match self.root() {
+            Some(root) => self.json_subtree(&root),
+            None => Value::Null,
+        }
+    }
+
+    fn json_subtree(&self, hash: &H256) -> Value {
+        let nibbles = |path: &[u8]| path.iter().map(|n| format!("{:x}", n)).collect::<String>();
+        match self.node_at_hash(hash) {
+            Some(Node::Branch(branch)) => {
+                let children: serde_json::Map<String, Value> = branch
+                    .children
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, child)| Some((format!("{:x}", i), self.json_subtree(child.as_ref()?))))
+                    .collect();
+                json!({
+                    "type": "Branch",
+                    "hash": hex(hash),
+                    "children": children,
+                    "value": branch.value.map(|value| hex(&value)),
+                })
+            }
+            Some(Node::Extension(extension)) => json!({
+                "type": "Extension",
+                "hash": hex(hash),
+                "path": nibbles(&extension.path),
+                "child": self.json_subtree(&extension.child),
+            }),
+            Some(Node::Leaf(leaf)) => json!({
+                "type": "Leaf",
+                "hash": hex(hash),
+                "path": nibbles(&leaf.path),
+                "value": hex(&leaf.value),
+            }),
+            Some(Node::Empty) | None => Value::Null,
+        }
+    }
+}
+
+impl MPTProofInput {
+    /// Proof `index` of a file written by `MPTBuilder::export_proofs_to_file`
+    pub fn from_json(json: &str, index: usize) -> Result<Self, FixtureError> {
+        let export: Value = serde_json::from_str(json)?;
+        let root = match export.get("root") {
+            Some(Value::String(root)) => {
+                decode_hex(root)?.try_into().map_err(|_| FixtureError::InvalidHex(root.clone()))?
+            }
+            _ => return Err(FixtureError::MissingRoot),
+        };
+        let entry = export
+            .get("proofs")
+            .and_then(Value::as_array)
+            .ok_or(FixtureError::InvalidFormat("proofs must be a list"))?
+            .get(index)
+            .ok_or(FixtureError::MissingProof(index))?;
+        let field = |name: &'static str| match entry.get(name) {
+            Some(Value::String(s)) => decode_hex(s),
+            _ => Err(FixtureError::InvalidFormat("key and value must be hex strings")),
+        };
+        let proof = match entry.get("nodes") {
+            Some(Value::Array(nodes)) => nodes
+                .iter()
+                .map(|node| match node {
+                    Value::String(node) => decode_hex(node),
+                    _ => Err(FixtureError::InvalidFormat("nodes must be hex strings")),
+                })
+                .collect::<Result<_, _>>()?,
+            _ => return Err(FixtureError::InvalidFormat("nodes must be a list")),
+        };
+        Ok(Self { version: PROOF_FORMAT_VERSION, key: field("key")?, value: field("value")?, proof, root, proof_type: ProofType::Inclusion })
+    }
+}
+
+/// Build the trie described by a fixture's JSON text
+fn parse_fixture(json: &str) -> Result<(MPTBuilder, Option<H256>), FixtureError> {
+    let fixture: Value = serde_json::from_str(json)?;
+    let object = fixture.as_object().ok_or(FixtureError::InvalidFormat("fixture must be a JSON object"))?;
+
+    let (entries, root) = match object.get("in") {
+        Some(entries) => {
+            let root = match object.get("root") {
+                Some(Value::String(root)) => Some(
+                    decode_hex(root)?
+                        .try_into()
+                        .map_err(|_| FixtureError::InvalidHex(root.clone()))?,
+                ),
+                Some(_) => return Err(FixtureError::InvalidFormat("root must be a hex string")),
+                None => None,
+            };
+            (entries, root)
+        }
+        None => (&fixture, None),
+    };
+
+    let pairs: Vec<(&str, &Value)> = match entries {
+        Value::Object(map) => map.iter().map(|(key, value)| (key.as_str(), value)).collect(),
+        Value::Array(list) => list
+            .iter()
+            .map(|pair| match pair.as_array().map(Vec::as_slice) {
+                Some([Value::String(key), value]) => Ok((key.as_str(), value)),
+                _ => Err(FixtureError::InvalidFormat("in entries must be [key, value] pairs")),
+            })
+            .collect::<Result<_, _>>()?,
+        _ => return Err(FixtureError::InvalidFormat("in must be an object or a list")),
+    };
+
+    let mut builder = MPTBuilder::new();
+    for (key, value) in pairs {
+        let key = fixture_bytes(key)?;
+        match value {
+            Value::String(value) => {
+                builder.insert(&key, &fixture_bytes(value)?);
+            }
+            Value::Null => {
+                builder.delete(&key);
+            }
+            _ => return Err(FixtureError::InvalidFormat("values must be strings or null")),
+        }
+    }
+    Ok((builder, root))
+}
+
+/// A fixture key or value: `0x`-prefixed hex, or the UTF-8 bytes of the string
+fn fixture_bytes(s: &str) -> Result<Vec<u8>, FixtureError> {
+    if s.starts_with("0x") {
+        decode_hex(s)
+    } else {
+        Ok(s.as_bytes().to_vec())
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex_string(bytes))
+}
+
+fn decode_hex(s: &str) -> Result<Vec<u8>, FixtureError> {
+    crate::eip1186::decode_hex(s).map_err(|_| FixtureError::InvalidHex(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fixture_layouts() {
+        // A plain object has no expected root
+        let (plain, root) = parse_fixture(r#"{"foo": "bar", "food": "bass"}"#).unwrap();
+        assert_eq!(root, None);
+        assert_eq!(plain.get(b"food"), Some(b"bass".to_vec()));
+
+        let (listed, root) = parse_fixture(
+            r#"{"in": [["foo", "bar"], ["food", "bass"]],
+                "root": "0x17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3"}"#,
+        )
+        .unwrap();
+        assert_eq!(listed.root(), plain.root());
+        assert_eq!(listed.root(), root);
+
+        // Hex keys and values, and a null value deleting an earlier entry
+        let (hex, _) = parse_fixture(r#"{"in": [["0x0045", "0x0123"], ["0x4500", "ab"], ["0x4500", null]]}"#).unwrap();
+        assert_eq!(hex.flatten(), vec![(vec![0x00, 0x45], vec![0x01, 0x23])]);
+    }
+
+    #[test]
+    fn test_parse_fixture_errors() {
+        assert!(matches!(parse_fixture("[1, 2]"), Err(FixtureError::InvalidFormat(_))));
+        assert!(matches!(parse_fixture("{"), Err(FixtureError::Json(_))));
+        assert!(matches!(parse_fixture(r#"{"in": [["a"]]}"#), Err(FixtureError::InvalidFormat(_))));
+        assert!(matches!(parse_fixture(r#"{"a": 1}"#), Err(FixtureError::InvalidFormat(_))));
+        assert!(matches!(parse_fixture(r#"{"0xzz": "a"}"#), Err(FixtureError::InvalidHex(_))));
+        assert!(matches!(parse_fixture(r#"{"in": {}, "root": "0x1234"}"#), Err(FixtureError::InvalidHex(_))));
+        assert!(matches!(
+            MPTBuilder::import_from_json_fixture(Path::new("does/not/exist.json")),
+            Err(FixtureError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_proofs_roundtrip() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        let root = builder.insert(b"horse", b"stallion");
+
+        let keys: [&[u8]; 4] = [b"do", b"dog", b"doge", b"horse"];
+        let path = std::env::temp_dir().join(format!("mpt-export-{}.json", std::process::id()));
+        builder.export_proofs_to_file(&path, &keys).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        for (i, key) in keys.iter().enumerate() {
+            let input = MPTProofInput::from_json(&json, i).unwrap();
+            assert_eq!(input.root, root);
+            assert_eq!(input.key, key.to_vec());
+            assert_eq!(input.value, builder.get(key).unwrap());
+            assert!(crate::mpt::verify_proof(&input.root, &input.key, &input.value, &input.proof));
+        }
+        assert!(matches!(MPTProofInput::from_json(&json, keys.len()), Err(FixtureError::MissingProof(4))));
+
+        // "cat" diverges from the trie before reaching a node that could prove its absence
+        let err = builder.export_proofs_to_file(&path, &[b"cat"]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_to_json_tree() {
+        assert_eq!(MPTBuilder::new().to_json_tree(), Value::Null);
+
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        let root = builder.insert(b"horse", b"stallion");
+        let tree = builder.to_json_tree();
+        assert_eq!(tree["hash"], hex(&root));
+
+        // Walk every node, collecting types and checking each has exactly its own keys
+        let mut types = Vec::new();
+        let mut stack = vec![&tree];
+        while let Some(node) = stack.pop() {
+            let object = node.as_object().unwrap();
+            let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+            keys.sort_unstable();
+            let node_type = object["type"].as_str().unwrap();
+            match node_type {
+                "Branch" => {
+                    assert_eq!(keys, ["children", "hash", "type", "value"]);
+                    let children = object["children"].as_object().unwrap();
+                    assert!(children.values().all(|child| !child.is_null()));
+                    stack.extend(children.values());
+                }
+                "Extension" => {
+                    assert_eq!(keys, ["child", "hash", "path", "type"]);
+                    stack.push(&object["child"]);
+                }
+                "Leaf" => assert_eq!(keys, ["hash", "path", "type", "value"]),
+                other => panic!("unexpected type {}", other),
+            }
+            types.push(node_type);
+        }
+        for expected in ["Branch", "Extension", "Leaf"] {
+            assert!(types.contains(&expected), "{}", expected);
+        }
+
+        // The root is the extension over the shared first nibble 6
+        assert_eq!(tree["type"], "Extension");
+        assert_eq!(tree["path"], "6");
+        assert_eq!(tree["child"]["children"]["8"]["value"], hex(b"stallion"));
+    }
+}
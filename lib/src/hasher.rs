@@ -0,0 +1,141 @@
+use crate::types::H256;
+use crate::rlp_encoding::keccak256;
+
+/// Hash function used to reference trie nodes
+pub trait NodeHasher: Send + Sync {
+    fn hash(&self, data: &[u8]) -> H256;
+}
+
+/// Keccak-256, as used by Ethereum (the default)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Keccak256Hasher;
+
+impl NodeHasher for Keccak256Hasher {
+    fn hash(&self, data: &[u8]) -> H256 {
+        keccak256(data)
+    }
+}
+
+/// SHA-256
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sha256Hasher;
+
+impl NodeHasher for Sha256Hasher {
+    fn hash(&self, data: &[u8]) -> H256 {
+        use sha2::{Digest, Sha256};
+        Sha256::digest(data).into()
+    }
+}
+
+/// A `NodeHasher` that remembers the hash of every input it has seen
+/// 
+/// Hashing the same node twice returns the cached hash instead of recomputing it. Node
+/// encodings rarely repeat while building a trie: inserting 5000 distinct keys hashes
+/// ~24k nodes without a single repeat, and the lookups make it ~40% slower than plain
+/// Keccak-256 (see the `trie_hasher` bench). Rewriting keys with unchanged values
+/// rebuilds identical nodes, which all come from the cache; that matters most for
+/// hashers far costlier than Keccak-256.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct TrieHasher<H: NodeHasher = Keccak256Hasher> {
+    inner: H,
+    cache: std::sync::Mutex<TrieHasherCache>,
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+struct TrieHasherCache {
+    hashes: std::collections::HashMap<alloc::vec::Vec<u8>, H256>,
+    hits: usize,
+    misses: usize,
+}
+
+#[cfg(feature = "std")]
+impl<H: NodeHasher> TrieHasher<H> {
+    /// Cache the hashes computed by `inner`
+    pub fn new(inner: H) -> Self {
+        Self { inner, cache: Default::default() }
+    }
+
+    /// Number of `hash` calls answered from the cache
+    pub fn hits(&self) -> usize {
+        self.lock().hits
+    }
+
+    /// Number of `hash` calls that had to compute the hash
+    pub fn misses(&self) -> usize {
+        self.lock().misses
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TrieHasherCache> {
+        // The cache stays consistent even if another thread panicked while holding it
+        self.cache.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: NodeHasher> NodeHasher for TrieHasher<H> {
+    fn hash(&self, data: &[u8]) -> H256 {
+        let mut cache = self.lock();
+        if let Some(&hash) = cache.hashes.get(data) {
+            cache.hits += 1;
+            return hash;
+        }
+        let hash = self.inner.hash(data);
+        cache.misses += 1;
+        cache.hashes.insert(data.to_vec(), hash);
+        hash
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: NodeHasher + Clone> Clone for TrieHasher<H> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), cache: std::sync::Mutex::new(self.lock().clone()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hashers_empty_input() {
+        assert_eq!(
+            Keccak256Hasher.hash(&[])[..4],
+            [0xc5, 0xd2, 0x46, 0x01]
+        );
+        assert_eq!(
+            Sha256Hasher.hash(&[])[..4],
+            [0xe3, 0xb0, 0xc4, 0x42]
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_trie_hasher_caches() {
+        let hasher = TrieHasher::new(Keccak256Hasher);
+        assert_eq!(hasher.hash(b"node"), keccak256(b"node"));
+        assert_eq!(hasher.hash(b"node"), keccak256(b"node"));
+        assert_eq!(hasher.hash(b"other"), keccak256(b"other"));
+        assert_eq!((hasher.hits(), hasher.misses()), (1, 2));
+
+        // Rewriting unchanged values rebuilds identical nodes, all served from the cache
+        let mut builder = crate::builder::MPTBuilder::with_hasher(TrieHasher::new(Keccak256Hasher));
+        for i in 0..100u32 {
+            builder.insert(&i.to_be_bytes(), &[7; 40]);
+        }
+        let misses = builder.hasher().misses();
+        for i in 0..100u32 {
+            builder.insert(&i.to_be_bytes(), &[7; 40]);
+        }
+        assert_eq!(builder.hasher().misses(), misses);
+        assert!(builder.hasher().hits() >= 100);
+
+        let mut keccak = crate::builder::MPTBuilder::new();
+        for i in 0..100u32 {
+            keccak.insert(&i.to_be_bytes(), &[7; 40]);
+        }
+        assert_eq!(builder.root(), keccak.root());
+    }
+}
@@ -29,24 +29,91 @@
 //! assert!(verified);
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
 use alloy_sol_types::sol;
 
 pub mod types;
 pub mod rlp_encoding;
+pub mod hasher;
 pub mod path;
 pub mod mpt;
+pub mod eip712;
+pub mod transaction;
+pub mod state_trie;
+pub mod witness;
+pub mod block_header;
+pub mod account_storage;
+pub mod eip1186;
+pub mod proof_replay;
+
+#[cfg(feature = "poseidon")]
+pub mod poseidon;
 
 #[cfg(feature = "std")]
 pub mod builder;
 
+#[cfg(feature = "std")]
+pub mod read_only;
+
+#[cfg(feature = "std")]
+pub mod compact_proof;
+
+#[cfg(feature = "std")]
+pub mod state_sync;
+
+#[cfg(feature = "std")]
+pub mod fixture;
+
+#[cfg(feature = "std")]
+pub mod receipt;
+
+#[cfg(feature = "std")]
+pub mod ethereum;
+
+#[cfg(test)]
+pub(crate) mod test_utils;
+
 pub use types::*;
 pub use rlp_encoding::*;
+pub use hasher::*;
 pub use path::*;
 pub use mpt::*;
+pub use eip712::*;
+pub use transaction::*;
+pub use state_trie::*;
+pub use witness::*;
+pub use block_header::*;
+pub use account_storage::*;
+pub use eip1186::*;
+pub use proof_replay::*;
+
+#[cfg(feature = "poseidon")]
+pub use poseidon::*;
 
 #[cfg(feature = "std")]
 pub use builder::*;
 
+#[cfg(feature = "std")]
+pub use read_only::*;
+
+#[cfg(feature = "std")]
+pub use compact_proof::*;
+
+#[cfg(feature = "std")]
+pub use state_sync::*;
+
+#[cfg(feature = "std")]
+pub use fixture::*;
+
+#[cfg(feature = "std")]
+pub use receipt::*;
+
+#[cfg(feature = "std")]
+pub use ethereum::*;
+
 sol! {
     /// The public values encoded as a struct for Solidity verification.
     struct MPTProofOutput {
@@ -55,4 +122,11 @@ sol! {
         bytes key;
         bytes value;
     }
+
+    /// Public values of an aggregated proof over many single-key MPT proofs.
+    struct AggregatedMPTProofOutput {
+        MPTProofOutput[] proofs;
+        bytes32 aggregate_root;
+        bytes32 mpt_vkey;
+    }
 }
@@ -1,9 +1,49 @@
-use crate::types::H256;
-use crate::path::{to_nibbles, decode_path};
-use crate::rlp_encoding::{keccak256, decode_list, decode_bytes};
+use alloc::vec::Vec;
+use crate::types::{EthAccount, MPTProofInput, ProofResult, SiblingProof, VerificationError, H256};
+use crate::hasher::{Keccak256Hasher, NodeHasher};
+use crate::path::{to_nibbles, encode_path, decode_path, account_address_to_state_key, common_prefix_len, NibbleSlice};
+use crate::rlp_encoding::{
+    assert_canonical, keccak256, encode_bytes, encode_list, decode_list, decode_bytes, decode_storage_word, is_rlp_null,
+    RLP_EMPTY,
+};
+
+/// Root of a trie with no entries: `keccak256(RLP(""))`
+pub const EMPTY_TRIE_ROOT: H256 = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// Strictness of proof verification, for `verify_proof_with_config`
+/// 
+/// The defaults are what `verify_proof` and the other verifiers use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofVerifierConfig {
+    /// Require the first proof node to hash to `root`
+    pub check_root_hash: bool,
+    /// Accept children under 32 bytes embedded in their parent, as Ethereum tries have
+    pub allow_inline_nodes: bool,
+    /// Most nodes a walk may visit, embedded ones included, bounding work on hostile proofs
+    pub max_proof_depth: usize,
+    /// Reject proof nodes that are not canonical RLP (see `assert_canonical`)
+    pub strict_canonical_rlp: bool,
+}
+
+impl Default for ProofVerifierConfig {
+    fn default() -> Self {
+        Self {
+            check_root_hash: true,
+            allow_inline_nodes: true,
+            max_proof_depth: 64,
+            strict_canonical_rlp: true,
+        }
+    }
+}
 
 /// Verify a Merkle Patricia Trie proof
 /// 
+/// An empty proof against `EMPTY_TRIE_ROOT` proves that the key is absent, so it is
+/// valid only for an empty `expected_value`. Uses the default `ProofVerifierConfig`.
+/// 
 /// # Arguments
 /// * `root` - The expected root hash of the trie
 /// * `key` - The key to verify
@@ -12,171 +52,233 @@ use crate::rlp_encoding::{keccak256, decode_list, decode_bytes};
 /// 
 /// # Returns
 /// * `true` if the proof is valid, `false` otherwise
+#[must_use = "verify_proof returns false on failure; ignoring this is likely a bug"]
 pub fn verify_proof(
     root: &H256,
     key: &[u8],
     expected_value: &[u8],
     proof: &[Vec<u8>],
 ) -> bool {
-    if proof.is_empty() {
-        return false;
+    verify_proof_with_config(root, key, expected_value, proof, &ProofVerifierConfig::default()).is_ok()
+}
+
+/// Verify a proof with the given strictness, reporting why it failed
+pub fn verify_proof_with_config(
+    root: &H256,
+    key: &[u8],
+    expected_value: &[u8],
+    proof: &[Vec<u8>],
+    config: &ProofVerifierConfig,
+) -> Result<(), VerificationError> {
+    let value = walk_proof(&Keccak256Hasher, root, key, proof, config)?;
+    if value != expected_value {
+        return Err(VerificationError::ValueMismatch);
     }
+    Ok(())
+}
+
+/// Verify a Merkle Patricia Trie proof whose nodes are hashed with `hasher`
+/// 
+/// Same as `verify_proof`, for tries built with a non-default `NodeHasher`.
+#[must_use = "verify_proof_with_hasher returns false on failure; ignoring this is likely a bug"]
+pub fn verify_proof_with_hasher<H: NodeHasher + ?Sized>(
+    hasher: &H,
+    root: &H256,
+    key: &[u8],
+    expected_value: &[u8],
+    proof: &[Vec<u8>],
+) -> bool {
+    proof_value_with_hasher(hasher, root, key, proof).is_some_and(|value| value == expected_value)
+}
+
+/// The value a valid proof shows for `key`, or `None` if the proof is invalid
+/// 
+/// An empty value means the key is absent: an empty proof against `EMPTY_TRIE_ROOT`, or
+/// a key ending at a branch without a value.
+pub fn proof_value(root: &H256, key: &[u8], proof: &[Vec<u8>]) -> Option<Vec<u8>> {
+    proof_value_with_hasher(&Keccak256Hasher, root, key, proof)
+}
+
+/// `proof_value` for tries built with a non-default `NodeHasher`
+pub fn proof_value_with_hasher<H: NodeHasher + ?Sized>(
+    hasher: &H,
+    root: &H256,
+    key: &[u8],
+    proof: &[Vec<u8>],
+) -> Option<Vec<u8>> {
+    walk_proof(hasher, root, key, proof, &ProofVerifierConfig::default()).ok()
+}
+
+/// Walk `proof` from `root` along `key` and return the value it shows
+fn walk_proof<H: NodeHasher + ?Sized>(
+    hasher: &H,
+    root: &H256,
+    key: &[u8],
+    proof: &[Vec<u8>],
+    config: &ProofVerifierConfig,
+) -> Result<Vec<u8>, VerificationError> {
+    match walk_partial_proof(hasher, root, key, proof, config)? {
+        ProofResult::Complete(value) => Ok(value),
+        // A full proof has every hashed node on the path
+        ProofResult::Partial(_) => Err(VerificationError::InvalidProof),
+    }
+}
+
+/// Walk `proof` from `root` along `key`, stopping at the first child the proof omits
+fn walk_partial_proof<H: NodeHasher + ?Sized>(
+    hasher: &H,
+    root: &H256,
+    key: &[u8],
+    proof: &[Vec<u8>],
+    config: &ProofVerifierConfig,
+) -> Result<ProofResult, VerificationError> {
+    // Every key is absent from the empty trie, which has no nodes to prove
+    let Some((root_node, rest)) = proof.split_first() else {
+        if *root != hasher.hash(RLP_EMPTY) {
+            return Err(VerificationError::InvalidProof);
+        }
+        return Ok(ProofResult::Complete(Vec::new()));
+    };
     
-    // Convert key to nibbles
-    let nibbles = to_nibbles(key);
-    let mut nibble_idx = 0;
-    let mut expected_hash = *root;
+    // Only the root hash is trusted, so the root node is checked like any other
+    if config.check_root_hash && hasher.hash(root_node) != *root {
+        return Err(VerificationError::RootMismatch);
+    }
+    if config.strict_canonical_rlp {
+        assert_canonical(root_node).map_err(|_| VerificationError::NonCanonicalNode)?;
+    }
+    let mut proof_nodes = rest.iter();
+    let value = resolve_node(hasher, config, root_node, NibbleSlice::new(key), &mut proof_nodes, 0)?;
     
-    for (i, node_rlp) in proof.iter().enumerate() {
-        // Verify hash matches expected (skip for root node since we already trust it)
-        if i > 0 {
-            let node_hash = if node_rlp.len() < 32 {
-                // Short nodes are embedded directly
-                let mut hash = [0u8; 32];
-                if node_rlp.len() <= 32 {
-                    hash[..node_rlp.len()].copy_from_slice(node_rlp);
+    // The node holding the value must be the last node of the proof
+    match proof_nodes.next() {
+        Some(_) => Err(VerificationError::InvalidProof),
+        None => Ok(value),
+    }
+}
+
+/// Walk from `node_rlp` along `nibbles` and return the value found there
+/// 
+/// Embedded children are resolved by recursing into them directly; a hash-referenced
+/// child must be the next entry of `proof_nodes` and hash to the reference, or the walk
+/// ends with `ProofResult::Partial` once `proof_nodes` runs out. `depth` is the number
+/// of nodes visited before this one.
+fn resolve_node<H: NodeHasher + ?Sized>(
+    hasher: &H,
+    config: &ProofVerifierConfig,
+    node_rlp: &[u8],
+    nibbles: NibbleSlice<'_>,
+    proof_nodes: &mut core::slice::Iter<'_, Vec<u8>>,
+    depth: usize,
+) -> Result<ProofResult, VerificationError> {
+    if depth >= config.max_proof_depth {
+        return Err(VerificationError::ProofTooDeep);
+    }
+    let invalid = |_| VerificationError::InvalidProof;
+    let items = decode_list(node_rlp).map_err(invalid)?;
+    
+    let (child, remaining) = match items.len() {
+        // Leaf or Extension node (2 items)
+        2 => {
+            let (path, is_leaf) = decode_path(&decode_bytes(&items[0]).map_err(invalid)?);
+            if is_leaf {
+                if !nibbles.eq_nibbles(&path) {
+                    return Err(VerificationError::InvalidProof);
                 }
-                hash
-            } else {
-                keccak256(node_rlp)
-            };
-            
-            if node_hash != expected_hash {
-                return false;
+                return decode_bytes(&items[1]).map(ProofResult::Complete).map_err(invalid);
             }
-        }
-        
-        // Decode RLP node
-        let decoded = match decode_list(node_rlp) {
-            Ok(d) => d,
-            Err(_) => return false,
-        };
-        
-        match decoded.len() {
-            // Leaf or Extension node (2 items)
-            2 => {
-                let path_encoded = match decode_bytes(&decoded[0]) {
-                    Ok(p) => p,
-                    Err(_) => return false,
-                };
-                
-                let (path, is_leaf) = decode_path(&path_encoded);
-                
-                if is_leaf {
-                    // Leaf node - should be last in proof
-                    if i != proof.len() - 1 {
-                        return false;
-                    }
-                    
-                    // Check path matches remaining nibbles
-                    let remaining = &nibbles[nibble_idx..];
-                    if path != remaining {
-                        return false;
-                    }
-                    
-                    // Verify value
-                    let value = match decode_bytes(&decoded[1]) {
-                        Ok(v) => v,
-                        Err(_) => return false,
-                    };
-                    
-                    return value == expected_value;
-                } else {
-                    // Extension node
-                    if nibble_idx + path.len() > nibbles.len() {
-                        return false;
-                    }
-                    
-                    let remaining = &nibbles[nibble_idx..nibble_idx + path.len()];
-                    if path != remaining {
-                        return false;
-                    }
-                    
-                    nibble_idx += path.len();
-                    
-                    // Get next hash
-                    let next_node = match decode_bytes(&decoded[1]) {
-                        Ok(n) => n,
-                        Err(_) => return false,
-                    };
-                    
-                    if next_node.len() == 32 {
-                        expected_hash.copy_from_slice(&next_node);
-                    } else if next_node.len() < 32 {
-                        // Short node embedded
-                        expected_hash = [0u8; 32];
-                        expected_hash[..next_node.len()].copy_from_slice(&next_node);
-                    } else {
-                        return false;
-                    }
-                }
+            if !nibbles.starts_with_nibbles(&path) {
+                return Err(VerificationError::InvalidProof);
             }
-            // Branch node (17 items)
-            17 => {
-                if nibble_idx > nibbles.len() {
-                    return false;
-                }
-                
-                if nibble_idx == nibbles.len() {
-                    // Value is in branch node itself (index 16)
-                    let value = match decode_bytes(&decoded[16]) {
-                        Ok(v) => v,
-                        Err(_) => {
-                            // Empty value
-                            if decoded[16].is_empty() || decoded[16] == vec![0x80] {
-                                return false;
-                            }
-                            return false;
-                        }
-                    };
-                    
-                    return value == expected_value;
-                }
-                
-                let nibble = nibbles[nibble_idx] as usize;
-                if nibble >= 16 {
-                    return false;
-                }
-                
-                nibble_idx += 1;
-                
-                let child = &decoded[nibble];
-                if child.is_empty() || child == &vec![0x80] {
-                    // Empty child
-                    return false;
-                }
-                
-                // Get next hash
-                let next_node = match decode_bytes(child) {
-                    Ok(n) => n,
-                    Err(_) => {
-                        // Might be a raw hash
-                        if child.len() == 32 {
-                            child.clone()
-                        } else {
-                            return false;
-                        }
-                    }
-                };
-                
-                if next_node.len() == 32 {
-                    expected_hash.copy_from_slice(&next_node);
-                } else if next_node.len() < 32 {
-                    // Short node embedded
-                    expected_hash = [0u8; 32];
-                    expected_hash[..next_node.len()].copy_from_slice(&next_node);
-                } else {
-                    return false;
-                }
+            (&items[1], nibbles.advance(path.len()))
+        }
+        // Branch node (17 items)
+        17 => {
+            // Value is in the branch itself
+            if nibbles.is_empty() {
+                return decode_bytes(&items[16]).map(ProofResult::Complete).map_err(invalid);
             }
-            _ => return false,
+            (&items[nibbles.get(0) as usize], nibbles.advance(1))
+        }
+        _ => return Err(VerificationError::InvalidProof),
+    };
+    
+    // An empty branch slot (RLP null) has no subtrie, so the key is not in the trie
+    if is_rlp_null(child) {
+        return Err(VerificationError::InvalidProof);
+    }
+    
+    // Nodes under 32 bytes are embedded in their parent instead of appearing in the proof
+    if child.first().is_some_and(|&prefix| prefix >= 0xc0) {
+        if !config.allow_inline_nodes {
+            return Err(VerificationError::InlineNodeNotAllowed);
         }
+        return resolve_node(hasher, config, child, remaining, proof_nodes, depth + 1);
+    }
+    
+    let hash: H256 = decode_bytes(child)
+        .map_err(invalid)?
+        .try_into()
+        .map_err(|_| VerificationError::InvalidProof)?;
+    let Some(next) = proof_nodes.next() else {
+        return Ok(ProofResult::Partial(hash));
+    };
+    if hasher.hash(next) != hash {
+        return Err(VerificationError::InvalidProof);
+    }
+    if config.strict_canonical_rlp {
+        assert_canonical(next).map_err(|_| VerificationError::NonCanonicalNode)?;
+    }
+    resolve_node(hasher, config, next, remaining, proof_nodes, depth + 1)
+}
+
+/// Verify a proof cut off after at most `max_depth` nodes, as from `MPTBuilder::get_truncated_proof`
+/// 
+/// Non-standard extension for "shallow proof" protocols: a proof of exactly `max_depth`
+/// nodes may stop before the key, giving `ProofResult::Partial` with the hash of the
+/// first omitted node. Everything above that hash is verified, but nothing below it,
+/// so a partial result says nothing about the key's value. A shorter proof must reach
+/// the key, giving `ProofResult::Complete`; a longer one is `ProofTooDeep`.
+pub fn verify_truncated_proof(
+    root: &H256,
+    key: &[u8],
+    proof: &[Vec<u8>],
+    max_depth: usize,
+) -> Result<ProofResult, VerificationError> {
+    if proof.len() > max_depth {
+        return Err(VerificationError::ProofTooDeep);
+    }
+    if max_depth == 0 {
+        return Ok(ProofResult::Partial(*root));
+    }
+    let result = walk_partial_proof(&Keccak256Hasher, root, key, proof, &ProofVerifierConfig::default())?;
+    if matches!(result, ProofResult::Partial(_)) && proof.len() < max_depth {
+        return Err(VerificationError::InvalidProof);
+    }
+    Ok(result)
+}
+
+/// Resolve a child reference: an embedded node becomes the next node to visit, and a
+/// 32-byte hash becomes the expected hash of the next proof node
+/// 
+/// Returns `false` for an empty or malformed reference.
+pub(crate) fn follow_child(item: Vec<u8>, expected_hash: &mut H256, inline_node: &mut Option<Vec<u8>>) -> bool {
+    if item.first().is_some_and(|&prefix| prefix >= 0xc0) {
+        *inline_node = Some(item);
+        return true;
     }
     
-    false
+    match decode_bytes(&item) {
+        Ok(hash) if hash.len() == 32 => {
+            expected_hash.copy_from_slice(&hash);
+            true
+        }
+        _ => false,
+    }
 }
 
 /// Get the hash of a node
+#[must_use]
 pub fn hash_node(node_rlp: &[u8]) -> H256 {
     if node_rlp.len() < 32 {
         let mut hash = [0u8; 32];
@@ -187,6 +289,292 @@ pub fn hash_node(node_rlp: &[u8]) -> H256 {
     }
 }
 
+/// Total size of a proof's RLP-encoded nodes in bytes
+/// 
+/// Proofs are dominated by branch nodes near the root: a full branch with 16 hashed
+/// children is 532 bytes, while leaves and extensions are usually under 150 bytes.
+pub fn proof_size_bytes(proof: &[Vec<u8>]) -> usize {
+    proof.iter().map(|node| node.len()).sum()
+}
+
+/// Drop stand-alone copies of embedded nodes from a proof
+/// 
+/// A node under 32 bytes is embedded in its parent rather than referenced by hash, so
+/// any such node after the root is redundant; `verify_proof` resolves embedded nodes
+/// from their parent. `get_proof` never emits them, but other proof sources may.
+pub fn optimize_proof(proof: &[Vec<u8>]) -> Vec<Vec<u8>> {
+    proof
+        .iter()
+        .enumerate()
+        .filter(|(i, node)| *i == 0 || node.len() >= 32)
+        .map(|(_, node)| node.clone())
+        .collect()
+}
+
+/// Compute the aggregate root of several proofs: `keccak256(root_0 || root_1 || ...)`
+pub fn aggregate_root(roots: &[H256]) -> H256 {
+    keccak256(&roots.concat())
+}
+
+/// Verify an Ethereum account proof against a state root
+/// 
+/// The state trie is keyed by `keccak256(address)` and stores the RLP-encoded account.
+/// 
+/// # Arguments
+/// * `state_root` - The state root of the block
+/// * `address` - The 20-byte account address
+/// * `account` - The expected account fields
+/// * `proof` - Vec of RLP-encoded nodes from root to leaf (as returned by `eth_getProof`)
+/// 
+/// # Returns
+/// * `Ok(())` if the proof shows `account` stored at `address`
+/// * `Err(VerificationError::InvalidAccountRlp(_))` if the proof is valid but the value it
+///   shows is not an account
+/// * Any other `VerificationError` if the proof is invalid or shows a different account
+pub fn verify_account_proof(
+    state_root: &H256,
+    address: &[u8; 20],
+    account: &EthAccount,
+    proof: &[Vec<u8>],
+) -> Result<(), VerificationError> {
+    let key = account_address_to_state_key(address);
+    let value = walk_proof(&Keccak256Hasher, state_root, &key, proof, &ProofVerifierConfig::default())?;
+    // An empty value means no account at the address, which is a mismatch, not bad RLP
+    if value.is_empty() {
+        return Err(VerificationError::ValueMismatch);
+    }
+    let proven = EthAccount::from_rlp(&value).map_err(VerificationError::InvalidAccountRlp)?;
+    if proven != *account {
+        return Err(VerificationError::ValueMismatch);
+    }
+    Ok(())
+}
+
+/// Verify an RLP-encoded account under `keccak256(address)` in the state trie
+/// 
+/// Like `verify_account_proof`, for an account already in its trie encoding (e.g. an
+/// `eth_getProof` leaf value), reporting why verification failed.
+pub fn verify_account_proof_rlp(
+    state_root: &H256,
+    address: &[u8; 20],
+    account_rlp: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<(), VerificationError> {
+    let key = account_address_to_state_key(address);
+    verify_proof_with_config(state_root, &key, account_rlp, proof, &ProofVerifierConfig::default())
+}
+
+/// Verify an RLP-encoded storage value under `keccak256(slot)` in an account's storage trie
+/// 
+/// `value_rlp` is the trie encoding of the word (a minimal big-endian integer, see
+/// `encode_u256`); an empty `value_rlp` proves the slot is unset.
+pub fn verify_storage_proof(
+    storage_root: &H256,
+    slot: &[u8; 32],
+    value_rlp: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<(), VerificationError> {
+    verify_proof_with_config(storage_root, &keccak256(slot), value_rlp, proof, &ProofVerifierConfig::default())
+}
+
+/// The word a storage proof shows for `slot`, as a big-endian u256
+/// 
+/// Zero slots are not stored, so a proof that the slot is absent (as `eth_getProof`
+/// returns for them) reads as zero.
+pub(crate) fn verified_storage_word(
+    storage_root: &H256,
+    slot: &[u8; 32],
+    proof: &[Vec<u8>],
+) -> Result<H256, VerificationError> {
+    let key = keccak256(slot);
+    match proof_value(storage_root, &key, proof) {
+        Some(value_rlp) => decode_storage_word(&value_rlp).ok_or(VerificationError::InvalidStorageValue),
+        None if verify_non_inclusion_proof(storage_root, &key, proof) => Ok([0u8; 32]),
+        None => Err(VerificationError::InvalidStorageProof),
+    }
+}
+
+/// Verify that `proof` shows `key` is absent from the trie at `root`
+/// 
+/// The proof follows the key's path until it leaves the trie: at a leaf with another
+/// path, an extension the key diverges from, an empty branch slot, or a branch without
+/// a value where the key ends. An empty proof shows every key absent from the empty trie.
+pub fn verify_non_inclusion_proof(root: &H256, key: &[u8], proof: &[Vec<u8>]) -> bool {
+    let Some(path) = walk_key_path(root, key, proof) else {
+        return false;
+    };
+    match path.end {
+        PathEnd::Empty | PathEnd::Branch(Some(_)) => true,
+        PathEnd::Branch(None) => decode_bytes(&path.items[16]).is_ok_and(|value| value.is_empty()),
+        PathEnd::Short { path: node_path, is_leaf, .. } => !is_leaf || node_path != path.remaining,
+    }
+}
+
+/// Verify that setting `key` to `value` in the trie at `old_root` gives `new_root`
+/// 
+/// `proof` is the key's proof under `old_root`: an inclusion proof to update a value,
+/// or a non-inclusion proof (see `verify_non_inclusion_proof`) to insert one. The new
+/// root is recomputed from the proof's nodes alone. Deletion would need the nodes next
+/// to the path as well, so an empty `value` does not verify.
+pub fn verify_state_transition(
+    old_root: &H256,
+    new_root: &H256,
+    key: &[u8],
+    value: &[u8],
+    proof: &[Vec<u8>],
+) -> bool {
+    if value.is_empty() {
+        return false;
+    }
+    let Some(KeyPath { ancestors, mut items, end, remaining }) = walk_key_path(old_root, key, proof) else {
+        return false;
+    };
+    
+    let mut node = match end {
+        PathEnd::Empty => leaf_rlp(&remaining, value),
+        PathEnd::Short { path, is_leaf: true, .. } if path == remaining => leaf_rlp(&path, value),
+        PathEnd::Short { path, is_leaf, item } => split_node(&path, is_leaf, &item, &remaining, value),
+        PathEnd::Branch(None) => {
+            items[16] = encode_bytes(value);
+            encode_list(&items)
+        }
+        PathEnd::Branch(Some(nibble)) => {
+            items[nibble as usize] = node_ref(&leaf_rlp(&remaining, value));
+            encode_list(&items)
+        }
+    };
+    for (mut items, slot) in ancestors.into_iter().rev() {
+        items[slot] = node_ref(&node);
+        node = encode_list(&items);
+    }
+    keccak256(&node) == *new_root
+}
+
+/// The path of a key through a hash-checked proof, down to the node where it ends
+struct KeyPath {
+    /// The items of each node above the last, root first, with the index of the child taken
+    ancestors: Vec<(Vec<Vec<u8>>, usize)>,
+    /// The items of the last node, empty for the empty trie
+    items: Vec<Vec<u8>>,
+    end: PathEnd,
+    /// The key's nibbles below the last node (after the empty slot, for a branch)
+    remaining: Vec<u8>,
+}
+
+/// How a key's path ends
+enum PathEnd {
+    /// The trie is empty
+    Empty,
+    /// At a leaf, or at an extension whose path the key leaves, with its path and second item
+    Short { path: Vec<u8>, is_leaf: bool, item: Vec<u8> },
+    /// At a branch where the key ends (`None`) or reaches an empty slot
+    Branch(Option<u8>),
+}
+
+/// Walk `proof` from `root` along `key`, checking each hash-referenced node, until the
+/// key reaches a leaf or leaves the trie; `None` if the proof is invalid or has nodes left
+fn walk_key_path(root: &H256, key: &[u8], proof: &[Vec<u8>]) -> Option<KeyPath> {
+    let nibbles = to_nibbles(key);
+    let mut proof_nodes = proof.iter();
+    let Some(root_node) = proof_nodes.next() else {
+        let empty = KeyPath { ancestors: Vec::new(), items: Vec::new(), end: PathEnd::Empty, remaining: nibbles };
+        return (*root == EMPTY_TRIE_ROOT).then_some(empty);
+    };
+    if keccak256(root_node) != *root {
+        return None;
+    }
+    let mut node = root_node.clone();
+    let mut ancestors = Vec::new();
+    let mut depth = 0;
+    
+    loop {
+        let mut items = decode_list(&node).ok()?;
+        let rest = &nibbles[depth..];
+        let slot = match items.len() {
+            2 => {
+                let (path, is_leaf) = decode_path(&decode_bytes(&items[0]).ok()?);
+                if is_leaf || !rest.starts_with(&path) {
+                    let end = PathEnd::Short { path, is_leaf, item: items.swap_remove(1) };
+                    let remaining = rest.to_vec();
+                    return proof_nodes.next().is_none().then_some(KeyPath { ancestors, items, end, remaining });
+                }
+                depth += path.len();
+                1
+            }
+            17 => match rest.split_first() {
+                Some((&nibble, _)) if !is_rlp_null(&items[nibble as usize]) => {
+                    depth += 1;
+                    nibble as usize
+                }
+                ending => {
+                    let end = PathEnd::Branch(ending.map(|(&nibble, _)| nibble));
+                    let remaining = ending.map_or_else(Vec::new, |(_, tail)| tail.to_vec());
+                    return proof_nodes.next().is_none().then_some(KeyPath { ancestors, items, end, remaining });
+                }
+            },
+            _ => return None,
+        };
+        
+        // Embedded children are the next node; referenced ones are the next proof node
+        let child = &items[slot];
+        node = if child.first().is_some_and(|&prefix| prefix >= 0xc0) {
+            child.clone()
+        } else {
+            let next = proof_nodes.next()?;
+            if decode_bytes(child).ok()? != keccak256(next) {
+                return None;
+            }
+            next.clone()
+        };
+        ancestors.push((items, slot));
+    }
+}
+
+/// RLP of a leaf holding `value` at `path`
+fn leaf_rlp(path: &[u8], value: &[u8]) -> Vec<u8> {
+    encode_list(&[encode_bytes(&encode_path(path, true)), encode_bytes(value)])
+}
+
+/// The item referencing a node from its parent: the node itself if short, else its hash
+fn node_ref(node_rlp: &[u8]) -> Vec<u8> {
+    if node_rlp.len() < 32 {
+        node_rlp.to_vec()
+    } else {
+        encode_bytes(&keccak256(node_rlp))
+    }
+}
+
+/// Split a leaf or extension at `path` that the key (`remaining`) leaves into a branch
+/// holding both, under an extension for their common prefix if there is one
+/// 
+/// `item` is the node's second item: a leaf's value or an extension's child.
+fn split_node(path: &[u8], is_leaf: bool, item: &[u8], remaining: &[u8], value: &[u8]) -> Vec<u8> {
+    let common = common_prefix_len(path, remaining);
+    let mut branch = alloc::vec![RLP_EMPTY.to_vec(); 17];
+    match path.get(common) {
+        // An extension of a single nibble is replaced by the branch slot itself
+        Some(&nibble) if !is_leaf && common + 1 == path.len() => branch[nibble as usize] = item.to_vec(),
+        Some(&nibble) => {
+            let node = encode_list(&[encode_bytes(&encode_path(&path[common + 1..], is_leaf)), item.to_vec()]);
+            branch[nibble as usize] = node_ref(&node);
+        }
+        None => branch[16] = item.to_vec(),
+    }
+    match remaining.get(common) {
+        Some(&nibble) => branch[nibble as usize] = node_ref(&leaf_rlp(&remaining[common + 1..], value)),
+        None => branch[16] = encode_bytes(value),
+    }
+    
+    let branch = encode_list(&branch);
+    if common == 0 {
+        return branch;
+    }
+    encode_list(&[encode_bytes(&encode_path(&path[..common], false)), node_ref(&branch)])
+}
+
+/// A `(key, value, proof)` tuple, as taken by the batch verifiers
+pub type KeyValueProof = (Vec<u8>, Vec<u8>, Vec<Vec<u8>>);
+
 /// Verify multiple proofs against the same root
 /// 
 /// # Arguments
@@ -197,7 +585,7 @@ pub fn hash_node(node_rlp: &[u8]) -> H256 {
 /// * Vector of booleans indicating verification result for each proof
 pub fn verify_batch_proofs(
     root: &H256,
-    proofs: &[(Vec<u8>, Vec<u8>, Vec<Vec<u8>>)],
+    proofs: &[KeyValueProof],
 ) -> Vec<bool> {
     proofs
         .iter()
@@ -208,16 +596,129 @@ pub fn verify_batch_proofs(
 /// Verify multiple proofs and return true only if all are valid
 pub fn verify_all_proofs(
     root: &H256,
-    proofs: &[(Vec<u8>, Vec<u8>, Vec<Vec<u8>>)],
+    proofs: &[KeyValueProof],
 ) -> bool {
     proofs
         .iter()
         .all(|(key, value, proof)| verify_proof(root, key, value, proof))
 }
 
+/// Verify a `SiblingProof`: each proof shows its key in the trie at `root`, and no other
+/// key lies between the target and its neighbours
+/// 
+/// Without a left (right) neighbour, the proof must show that no key precedes (follows)
+/// the target. The gaps are checked on the branch nodes of the proofs: every branch
+/// slot that would hold a key in between must be empty.
+pub fn verify_sibling_proof(root: &H256, proof: &SiblingProof) -> bool {
+    let target = &proof.target;
+    let proven = |input: &MPTProofInput| {
+        input.root == *root && !input.value.is_empty() && input.verify() == Ok(true)
+    };
+    if !proven(target) {
+        return false;
+    }
+    let Some(target_branches) = branches_on_path(&target.key, &target.proof) else {
+        return false;
+    };
+    let target_nibbles = to_nibbles(&target.key);
+    
+    let left_ok = match &proof.left_neighbor {
+        Some(left) => proven(left) && left.key < target.key && proves_adjacent(left, target),
+        None => no_keys_before(&target_branches, &target_nibbles, 0),
+    };
+    let right_ok = match &proof.right_neighbor {
+        Some(right) => proven(right) && target.key < right.key && proves_adjacent(target, right),
+        None => no_keys_after(&target_branches, &target_nibbles, 0),
+    };
+    left_ok && right_ok
+}
+
+/// Whether the proofs of keys `a < b` show that no key lies between them
+fn proves_adjacent(a: &MPTProofInput, b: &MPTProofInput) -> bool {
+    let (Some(a_branches), Some(b_branches)) =
+        (branches_on_path(&a.key, &a.proof), branches_on_path(&b.key, &b.proof))
+    else {
+        return false;
+    };
+    let (a_nibbles, b_nibbles) = (to_nibbles(&a.key), to_nibbles(&b.key));
+    let split = a_nibbles.iter().zip(&b_nibbles).take_while(|(x, y)| x == y).count();
+    
+    // The keys part at a branch (or `a` is that branch's value): the slots between
+    // theirs must be empty
+    let Some((_, items)) = b_branches.iter().find(|(depth, _)| *depth == split) else {
+        return false;
+    };
+    let first_between = a_nibbles.get(split).map_or(0, |&nibble| nibble as usize + 1);
+    let between_empty = items[first_between..b_nibbles[split] as usize].iter().all(|item| is_rlp_null(item));
+    
+    between_empty
+        && no_keys_after(&a_branches, &a_nibbles, split + 1)
+        && no_keys_before(&b_branches, &b_nibbles, split + 1)
+}
+
+/// Whether no key below depth `from` follows the key with `nibbles` in its subtrie
+fn no_keys_after(branches: &[(usize, Vec<Vec<u8>>)], nibbles: &[u8], from: usize) -> bool {
+    branches.iter().filter(|(depth, _)| *depth >= from).all(|(depth, items)| {
+        let first_after = nibbles.get(*depth).map_or(0, |&nibble| nibble as usize + 1);
+        items[first_after..16].iter().all(|item| is_rlp_null(item))
+    })
+}
+
+/// Whether no key below depth `from` precedes the key with `nibbles` in its subtrie
+fn no_keys_before(branches: &[(usize, Vec<Vec<u8>>)], nibbles: &[u8], from: usize) -> bool {
+    branches.iter().filter(|(depth, _)| *depth >= from).all(|(depth, items)| match nibbles.get(*depth) {
+        // A branch value is a key that is a prefix of this one, so it comes first
+        Some(&nibble) => items[..nibble as usize].iter().chain([&items[16]]).all(|item| is_rlp_null(item)),
+        None => true,
+    })
+}
+
+/// The branch nodes on the path of `key` through an already verified `proof`, as
+/// `(nibbles consumed before the branch, branch items)`
+fn branches_on_path(key: &[u8], proof: &[Vec<u8>]) -> Option<Vec<(usize, Vec<Vec<u8>>)>> {
+    let nibbles = to_nibbles(key);
+    let mut proof_nodes = proof.iter();
+    let mut node = proof_nodes.next()?.clone();
+    let mut depth = 0;
+    let mut branches = Vec::new();
+    
+    loop {
+        let mut items = decode_list(&node).ok()?;
+        let child = match items.len() {
+            2 => {
+                let (path, is_leaf) = decode_path(&decode_bytes(&items[0]).ok()?);
+                if is_leaf {
+                    return Some(branches);
+                }
+                depth += path.len();
+                items.swap_remove(1)
+            }
+            17 => {
+                let Some(&nibble) = nibbles.get(depth) else {
+                    branches.push((depth, items));
+                    return Some(branches);
+                };
+                let child = items[nibble as usize].clone();
+                branches.push((depth, items));
+                depth += 1;
+                child
+            }
+            _ => return None,
+        };
+        
+        // Embedded children are the next node; referenced ones are the next proof node
+        node = if child.first().is_some_and(|&prefix| prefix >= 0xc0) {
+            child
+        } else {
+            proof_nodes.next()?.clone()
+        };
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
     use crate::rlp_encoding::encode_bytes;
     use crate::path::encode_path;
 
@@ -265,6 +766,28 @@ mod tests {
         assert!(!verify_proof(&root, key, wrong_value, &proof));
     }
 
+    #[test]
+    fn test_verify_rejects_forged_root_node() {
+        let key = b"test";
+        let value = b"a value long enough to make the leaf node hashed";
+        
+        let nibbles = to_nibbles(key);
+        let leaf_rlp = crate::rlp_encoding::encode_list(&[
+            encode_bytes(&encode_path(&nibbles, true)),
+            encode_bytes(value),
+        ]);
+        let root = keccak256(&leaf_rlp);
+        assert!(verify_proof(&root, key, value, &[leaf_rlp]));
+        
+        // A syntactically valid leaf for a different value, not hashing to `root`
+        let forged = b"a forged value that is not committed to by the root";
+        let forged_rlp = crate::rlp_encoding::encode_list(&[
+            encode_bytes(&encode_path(&nibbles, true)),
+            encode_bytes(forged),
+        ]);
+        assert!(!verify_proof(&root, key, forged, &[forged_rlp]));
+    }
+
     #[test]
     fn test_verify_empty_proof() {
         let key = b"test";
@@ -275,6 +798,17 @@ mod tests {
         assert!(!verify_proof(&root, key, value, &proof));
     }
     
+    #[test]
+    fn test_verify_empty_trie_proof() {
+        assert_eq!(EMPTY_TRIE_ROOT, keccak256(&[0x80]));
+        
+        // Absence in the empty trie needs no nodes, but inclusion can't be proven
+        assert!(verify_proof(&EMPTY_TRIE_ROOT, b"test", b"", &[]));
+        assert!(!verify_proof(&EMPTY_TRIE_ROOT, b"test", b"value", &[]));
+        assert!(!verify_proof(&EMPTY_TRIE_ROOT, b"test", b"", &[vec![0x80]]));
+        assert!(!verify_proof(&[0u8; 32], b"test", b"", &[]));
+    }
+    
     #[test]
     fn test_verify_batch_proofs() {
         // Create multiple simple leaf proofs
@@ -306,4 +840,319 @@ mod tests {
             assert!(verify_proof(&root, key, value, proof));
         }
     }
+
+    #[test]
+    fn test_aggregate_root() {
+        let a = [0x11; 32];
+        let b = [0x22; 32];
+        
+        let mut concat = a.to_vec();
+        concat.extend_from_slice(&b);
+        assert_eq!(aggregate_root(&[a, b]), keccak256(&concat));
+        
+        // Order matters
+        assert_ne!(aggregate_root(&[a, b]), aggregate_root(&[b, a]));
+        assert_eq!(aggregate_root(&[]), keccak256(&[]));
+    }
+
+    #[test]
+    fn test_verify_account_proof() {
+        let address = [0xab; 20];
+        let account = EthAccount {
+            nonce: 1,
+            balance: [0u8; 32],
+            storage_root: [0x11; 32],
+            code_hash: [0x22; 32],
+        };
+        
+        // Single-leaf state trie keyed by keccak256(address)
+        let nibbles = to_nibbles(&keccak256(&address));
+        let leaf_items = vec![
+            encode_bytes(&encode_path(&nibbles, true)),
+            encode_bytes(&account.to_rlp()),
+        ];
+        let leaf_rlp = crate::rlp_encoding::encode_list(&leaf_items);
+        let state_root = keccak256(&leaf_rlp);
+        let proof = vec![leaf_rlp];
+        
+        assert_eq!(verify_account_proof(&state_root, &address, &account, &proof), Ok(()));
+        
+        // A different nonce must not verify
+        let wrong = EthAccount { nonce: 2, ..account.clone() };
+        assert_eq!(
+            verify_account_proof(&state_root, &address, &wrong, &proof),
+            Err(VerificationError::ValueMismatch)
+        );
+        
+        // Neither must a different address
+        assert_eq!(
+            verify_account_proof(&state_root, &[0xcd; 20], &account, &proof),
+            Err(VerificationError::InvalidProof)
+        );
+        
+        // A valid proof of a value that is not an account is told apart from a bad proof
+        let leaf_rlp = crate::rlp_encoding::encode_list(&[
+            encode_bytes(&encode_path(&nibbles, true)),
+            encode_bytes(b"not an account"),
+        ]);
+        let state_root = keccak256(&leaf_rlp);
+        assert!(matches!(
+            verify_account_proof(&state_root, &address, &account, &[leaf_rlp]),
+            Err(VerificationError::InvalidAccountRlp(_))
+        ));
+    }
+    
+    #[test]
+    fn test_verify_account_proof_mainnet_fixture() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/account_proof.json")).unwrap();
+        let word = |field: &str| -> H256 {
+            crate::eip1186::decode_hex(fixture[field].as_str().unwrap()).unwrap().try_into().unwrap()
+        };
+        let address: [u8; 20] =
+            crate::eip1186::decode_hex(fixture["address"].as_str().unwrap()).unwrap().try_into().unwrap();
+        let proof: Vec<Vec<u8>> = fixture["accountProof"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| crate::eip1186::decode_hex(node.as_str().unwrap()).unwrap())
+            .collect();
+        let state_root = word("stateRoot");
+        assert_eq!(keccak256(&proof[0]), state_root);
+        
+        let account = EthAccount {
+            nonce: 1,
+            balance: [0u8; 32],
+            storage_root: word("storageHash"),
+            code_hash: word("codeHash"),
+        };
+        assert_eq!(verify_account_proof(&state_root, &address, &account, &proof), Ok(()));
+        assert_eq!(
+            verify_account_proof(&state_root, &address, &EthAccount { nonce: 2, ..account.clone() }, &proof),
+            Err(VerificationError::ValueMismatch)
+        );
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_account_and_storage_proof_hash_keys() {
+        let address = [0xab; 20];
+        let slot = [0x01; 32];
+        let value_rlp = crate::rlp_encoding::encode_u256(&[0x42; 32]);
+        
+        let mut storage = crate::builder::MPTBuilder::new();
+        storage.insert(&keccak256(&[0x02; 32]), &crate::rlp_encoding::encode_u256(&[0x07; 32]));
+        let storage_root = storage.insert(&keccak256(&slot), &value_rlp);
+        let storage_proof = storage.get_proof(&keccak256(&slot)).unwrap();
+        
+        assert_eq!(verify_storage_proof(&storage_root, &slot, &value_rlp, &storage_proof), Ok(()));
+        assert_eq!(
+            verify_storage_proof(&storage_root, &slot, b"", &storage_proof),
+            Err(VerificationError::ValueMismatch)
+        );
+        // The unhashed slot is not the key
+        assert!(!verify_proof(&storage_root, &slot, &value_rlp, &storage_proof));
+        
+        let account = EthAccount { nonce: 1, balance: [0u8; 32], storage_root, code_hash: [0x22; 32] };
+        let mut state = crate::builder::MPTBuilder::new();
+        state.insert(&keccak256(&[0xcd; 20]), &EthAccount { nonce: 9, ..account.clone() }.to_rlp());
+        let state_root = state.insert(&keccak256(&address), &account.to_rlp());
+        let account_proof = state.get_proof(&keccak256(&address)).unwrap();
+        
+        assert_eq!(verify_account_proof_rlp(&state_root, &address, &account.to_rlp(), &account_proof), Ok(()));
+        assert!(verify_account_proof_rlp(&state_root, &[0xcd; 20], &account.to_rlp(), &account_proof).is_err());
+        assert_eq!(
+            verify_account_proof_rlp(&storage_root, &address, &account.to_rlp(), &account_proof),
+            Err(VerificationError::RootMismatch)
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_nested_embedded_nodes() {
+        // "do"/"dog"/"doge" embed a branch and its leaves inside their parents
+        let mut builder = crate::builder::MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        let root = builder.insert(b"horse", b"stallion");
+        
+        for (key, value) in [(&b"do"[..], &b"verb"[..]), (b"dog", b"puppy"), (b"doge", b"coin")] {
+            let proof = builder.get_proof(key).unwrap();
+            assert!(verify_proof(&root, key, value, &proof));
+            
+            // Nothing may follow the node holding the value
+            let mut padded = proof.clone();
+            padded.push(proof[0].clone());
+            assert!(!verify_proof(&root, key, value, &padded));
+        }
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_optimize_proof() {
+        // Short keys and values leave several leaves small enough to embed
+        let mut builder = crate::builder::MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        let root = builder.insert(b"horse", b"stallion");
+        
+        for (key, value) in [(&b"dog"[..], &b"puppy"[..]), (b"doge", b"coin")] {
+            // Every node on the path, including the embedded ones
+            let full: Vec<Vec<u8>> = builder.path_nodes(key).unwrap().into_iter().map(|(_, rlp, _)| rlp).collect();
+            let optimized = optimize_proof(&full);
+            
+            assert!(optimized.len() < full.len());
+            assert!(proof_size_bytes(&optimized) < proof_size_bytes(&full));
+            assert_eq!(optimized, builder.get_proof(key).unwrap());
+            assert!(verify_proof(&root, key, value, &optimized));
+            assert!(!verify_proof(&root, key, value, &full));
+        }
+        
+        // The root is kept even when it is small
+        let mut single = crate::builder::MPTBuilder::new();
+        let root = single.insert(b"a", b"b");
+        let proof = single.get_proof(b"a").unwrap();
+        assert!(proof[0].len() < 32);
+        assert_eq!(optimize_proof(&proof), proof);
+        assert!(verify_proof(&root, b"a", b"b", &optimize_proof(&proof)));
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_proof_with_config() {
+        let mut builder = crate::builder::MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        let root = builder.insert(b"horse", b"stallion");
+        let proof = builder.get_proof(b"doge").unwrap();
+        let default = ProofVerifierConfig::default();
+        
+        assert_eq!(verify_proof_with_config(&root, b"doge", b"coin", &proof, &default), Ok(()));
+        assert_eq!(
+            verify_proof_with_config(&root, b"doge", b"dollar", &proof, &default),
+            Err(VerificationError::ValueMismatch)
+        );
+        assert_eq!(
+            verify_proof_with_config(&root, b"dogs", b"coin", &proof, &default),
+            Err(VerificationError::InvalidProof)
+        );
+        
+        // The root check can be skipped when the first node is already trusted
+        let wrong_root = [0u8; 32];
+        assert_eq!(
+            verify_proof_with_config(&wrong_root, b"doge", b"coin", &proof, &default),
+            Err(VerificationError::RootMismatch)
+        );
+        let unchecked = ProofVerifierConfig { check_root_hash: false, ..default };
+        assert_eq!(verify_proof_with_config(&wrong_root, b"doge", b"coin", &proof, &unchecked), Ok(()));
+        
+        // "doge" sits in nodes embedded under the root's children
+        let no_inline = ProofVerifierConfig { allow_inline_nodes: false, ..default };
+        assert_eq!(
+            verify_proof_with_config(&root, b"doge", b"coin", &proof, &no_inline),
+            Err(VerificationError::InlineNodeNotAllowed)
+        );
+        let shallow = ProofVerifierConfig { max_proof_depth: 2, ..default };
+        assert_eq!(
+            verify_proof_with_config(&root, b"doge", b"coin", &proof, &shallow),
+            Err(VerificationError::ProofTooDeep)
+        );
+    }
+    
+    #[test]
+    fn test_verify_rejects_non_canonical_node() {
+        // A leaf whose one-byte value carries a redundant string prefix
+        let path = encode_bytes(&encode_path(&to_nibbles(b"key"), true));
+        let leaf = [&[0xc0 + path.len() as u8 + 2][..], &path, &[0x81, 0x05]].concat();
+        let root = keccak256(&leaf);
+        let proof = vec![leaf];
+        
+        assert!(!verify_proof(&root, b"key", &[0x05], &proof));
+        assert_eq!(
+            verify_proof_with_config(&root, b"key", &[0x05], &proof, &ProofVerifierConfig::default()),
+            Err(VerificationError::NonCanonicalNode)
+        );
+        let lenient = ProofVerifierConfig { strict_canonical_rlp: false, ..Default::default() };
+        assert_eq!(verify_proof_with_config(&root, b"key", &[0x05], &proof, &lenient), Ok(()));
+    }
+    
+    #[cfg(feature = "std")]
+    /// The do/dog/doge/horse trie, whose short nodes are embedded in their parents
+    fn small_trie() -> crate::builder::MPTBuilder {
+        let mut builder = crate::builder::MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        builder.insert(b"horse", b"stallion");
+        builder
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_non_inclusion_proof() {
+        let builder = small_trie();
+        let root = builder.root().unwrap();
+        
+        // Leaving the trie at an extension, an empty slot, a leaf, and a branch without a value
+        for key in [&b""[..], b"d", b"cat", b"dogs", b"doga", b"dox", b"hors", b"horses"] {
+            let proof = builder.get_exclusion_proof(key).unwrap();
+            assert!(verify_non_inclusion_proof(&root, key, &proof), "{:?}", key);
+            assert!(!verify_non_inclusion_proof(&keccak256(b"other"), key, &proof));
+            assert!(!verify_proof(&root, key, b"verb", &proof));
+            
+            let mut extra = proof.clone();
+            extra.push(builder.get_proof(b"horse").unwrap()[0].clone());
+            assert!(!verify_non_inclusion_proof(&root, key, &extra));
+        }
+        
+        for key in [&b"do"[..], b"dog", b"doge", b"horse"] {
+            assert_eq!(builder.get_exclusion_proof(key), None);
+            assert!(!verify_non_inclusion_proof(&root, key, &builder.get_proof(key).unwrap()));
+        }
+        // A proof for one key does not show another absent if their paths differ
+        assert!(!verify_non_inclusion_proof(&root, b"dogs", &builder.get_exclusion_proof(b"cat").unwrap()));
+        
+        assert!(verify_non_inclusion_proof(&EMPTY_TRIE_ROOT, b"any", &[]));
+        assert!(!verify_non_inclusion_proof(&root, b"any", &[]));
+    }
+    
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_verify_state_transition() {
+        let mut random = crate::builder::MPTBuilder::new();
+        for i in 0..100u32 {
+            random.insert(&keccak256(&i.to_be_bytes()), &[i as u8; 40]);
+        }
+        let random_keys: Vec<Vec<u8>> = (95..105u32).map(|i| keccak256(&i.to_be_bytes()).to_vec()).collect();
+        let small_keys: Vec<Vec<u8>> = [&b"do"[..], b"dog", b"horse", b"", b"d", b"dogs", b"doga", b"hors", b"horses"]
+            .iter()
+            .map(|key| key.to_vec())
+            .collect();
+        
+        let cases = [
+            (crate::builder::MPTBuilder::new(), vec![b"first".to_vec()]),
+            (small_trie(), small_keys),
+            (random, random_keys),
+        ];
+        for (builder, keys) in cases {
+            let old_root = builder.root_or_empty();
+            for key in keys {
+                for value in [&b"v"[..], &[0xab; 40]] {
+                    let proof = builder.get_proof(&key).filter(|_| builder.contains_key(&key));
+                    let proof = proof.or_else(|| builder.get_exclusion_proof(&key)).unwrap();
+                    let mut updated = builder.clone();
+                    let new_root = updated.insert(&key, value);
+                    
+                    assert!(verify_state_transition(&old_root, &new_root, &key, value, &proof), "{:?}", key);
+                    assert!(!verify_state_transition(&old_root, &new_root, &key, b"other", &proof));
+                    assert!(!verify_state_transition(&new_root, &new_root, &key, value, &proof));
+                }
+                // Deletion is not supported
+                assert!(!verify_state_transition(&old_root, &old_root, &key, b"", &[]));
+            }
+        }
+    }
 }
@@ -1,7 +1,15 @@
+use alloc::vec::Vec;
+use crate::types::H256;
+use crate::rlp_encoding::keccak256;
+
 /// Encode path with compact encoding
 /// First nibble contains: odd_flag (bit 0) and leaf_flag (bit 1)
+///
+/// An empty path encodes to the single prefix byte (`[0x00]` or `[0x20]`);
+/// this is unambiguous since any non-empty even path adds at least one byte.
+#[must_use]
 pub fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
-    let mut encoded = Vec::new();
+    let mut encoded = Vec::with_capacity(hp_encoded_len(nibbles.len()));
     let odd_len = nibbles.len() % 2 == 1;
     
     // Prefix encoding:
@@ -37,6 +45,7 @@ pub fn encode_path(nibbles: &[u8], is_leaf: bool) -> Vec<u8> {
 
 /// Decode compact-encoded path
 /// Returns (nibbles, is_leaf)
+#[must_use]
 pub fn decode_path(encoded: &[u8]) -> (Vec<u8>, bool) {
     if encoded.is_empty() {
         return (Vec::new(), false);
@@ -63,7 +72,36 @@ pub fn decode_path(encoded: &[u8]) -> (Vec<u8>, bool) {
     (nibbles, is_leaf)
 }
 
+/// Hex-prefix (HP) encoding, Yellow Paper Appendix C
+///
+/// HP packs a nibble sequence into bytes and records whether the path ends at a leaf.
+/// The high nibble of the first byte is a flag; for an odd number of nibbles its low
+/// nibble is the first path nibble, otherwise it is zero padding:
+///
+/// | flag nibble | node      | path length |
+/// |-------------|-----------|-------------|
+/// | `0`         | extension | even        |
+/// | `1`         | extension | odd         |
+/// | `2`         | leaf      | even        |
+/// | `3`         | leaf      | odd         |
+///
+/// The remaining nibbles follow two per byte, high nibble first. For example
+/// `[1, 2, 3, 4, 5]` as a leaf encodes to `0x312345`.
+pub use encode_path as hex_prefix_encode;
+
+/// Inverse of `hex_prefix_encode`: returns the nibbles and whether the leaf flag is set
+pub use decode_path as hex_prefix_decode;
+
+/// Byte length of the HP encoding of `nibble_count` nibbles, without encoding
+#[must_use]
+#[inline]
+pub const fn hp_encoded_len(nibble_count: usize) -> usize {
+    nibble_count / 2 + 1
+}
+
 /// Convert bytes to nibbles (hex digits)
+#[must_use]
+#[inline]
 pub fn to_nibbles(data: &[u8]) -> Vec<u8> {
     let mut nibbles = Vec::with_capacity(data.len() * 2);
     for &byte in data {
@@ -73,9 +111,195 @@ pub fn to_nibbles(data: &[u8]) -> Vec<u8> {
     nibbles
 }
 
+/// The nibbles of a byte slice from `start_nibble` on, read in place
+/// 
+/// A zero-copy alternative to `to_nibbles` for paths that are only read, such as the
+/// key during proof verification.
+#[derive(Clone, Copy, Debug)]
+pub struct NibbleSlice<'a> {
+    data: &'a [u8],
+    start_nibble: usize,
+}
+
+impl<'a> NibbleSlice<'a> {
+    /// All nibbles of `data`, high nibble first
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, start_nibble: 0 }
+    }
+    
+    /// Nibble `index` of the view; panics if out of range
+    #[inline]
+    pub fn get(&self, index: usize) -> u8 {
+        let nibble = self.start_nibble + index;
+        if nibble % 2 == 1 {
+            self.data[nibble / 2] & 0x0F
+        } else {
+            self.data[nibble / 2] >> 4
+        }
+    }
+    
+    /// The view without its first `n` nibbles; panics if `n` exceeds `len`
+    #[inline]
+    pub fn advance(&self, n: usize) -> Self {
+        assert!(n <= self.len(), "advance past the end of a NibbleSlice");
+        Self { data: self.data, start_nibble: self.start_nibble + n }
+    }
+    
+    /// Number of nibbles in the view
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len() * 2 - self.start_nibble
+    }
+    
+    /// Whether the view has no nibbles left
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    
+    /// Whether the view begins with `nibbles`
+    pub fn starts_with_nibbles(&self, nibbles: &[u8]) -> bool {
+        nibbles.len() <= self.len() && nibbles.iter().enumerate().all(|(i, &nibble)| self.get(i) == nibble)
+    }
+    
+    /// Whether the view is exactly `nibbles`
+    pub fn eq_nibbles(&self, nibbles: &[u8]) -> bool {
+        nibbles.len() == self.len() && self.starts_with_nibbles(nibbles)
+    }
+    
+    /// The nibbles of the view, unpacked
+    pub fn to_vec(&self) -> Vec<u8> {
+        (0..self.len()).map(|i| self.get(i)).collect()
+    }
+}
+
+/// A key's nibble path, as produced by `key_to_nibble_path`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct NibblePath(pub Vec<u8>);
+
+impl NibblePath {
+    /// The byte key this path spells out; see `path_to_key`
+    pub fn to_key(&self) -> Result<Vec<u8>, PathError> {
+        path_to_key(&self.0)
+    }
+}
+
+impl core::ops::Deref for NibblePath {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// A nibble path that does not correspond to a byte key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// The path has this odd number of nibbles, so it ends halfway through a byte
+    OddLength(usize),
+}
+
+/// The nibble path of `key`; `to_nibbles` with a typed result
+#[must_use]
+pub fn key_to_nibble_path(key: &[u8]) -> NibblePath {
+    NibblePath(to_nibbles(key))
+}
+
+/// The byte key whose nibbles are `nibbles`
+/// 
+/// Unlike `from_nibbles`, which pads an odd-length path with a zero nibble, this rejects
+/// paths that end halfway through a byte, such as the path to an inner node.
+pub fn path_to_key(nibbles: &[u8]) -> Result<Vec<u8>, PathError> {
+    if nibbles.len() % 2 == 1 {
+        return Err(PathError::OddLength(nibbles.len()));
+    }
+    Ok(from_nibbles(nibbles))
+}
+
+/// Expand a 32-byte trie key (e.g. `keccak256(slot)`) into exactly 64 nibbles
+///
+/// Leading zero bytes are kept, so every storage key has the same path length.
+pub fn storage_key_to_nibbles(slot: &H256) -> [u8; 64] {
+    let mut nibbles = [0u8; 64];
+    for (i, &byte) in slot.iter().enumerate() {
+        nibbles[2 * i] = byte >> 4;
+        nibbles[2 * i + 1] = byte & 0x0F;
+    }
+    nibbles
+}
+
+/// Compute the state trie key of an account: `keccak256(address)`
+pub fn account_address_to_state_key(address: &[u8; 20]) -> H256 {
+    keccak256(address)
+}
+
+/// How a `TupleKey` joins its parts before hashing
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TupleKeyEncoding {
+    /// The keccak256 of each part, back to back
+    #[default]
+    HashedParts,
+    /// Each part prefixed with its length as a big-endian u32, so parts cannot run together
+    LengthPrefixed,
+    /// The parts back to back, as Solidity lays out `keccak256(abi.encode(key, slot))`
+    /// for a mapping entry when each part is a 32-byte word
+    Raw,
+}
+
+/// A key derived from several parts, e.g. `(address, slot)`
+///
+/// The trie key is `keccak256` of the parts joined per the `TupleKeyEncoding`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TupleKey {
+    parts: Vec<Vec<u8>>,
+    encoding: TupleKeyEncoding,
+}
+
+impl TupleKey {
+    /// A key over `parts`, joined as the concatenation of their hashes
+    pub fn new(parts: &[&[u8]]) -> Self {
+        Self::with_encoding(parts, TupleKeyEncoding::default())
+    }
+
+    /// A key over `parts`, joined with `encoding`
+    pub fn with_encoding(parts: &[&[u8]], encoding: TupleKeyEncoding) -> Self {
+        Self { parts: parts.iter().map(|part| part.to_vec()).collect(), encoding }
+    }
+
+    /// The parts joined per the encoding, before hashing
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for part in &self.parts {
+            match self.encoding {
+                TupleKeyEncoding::HashedParts => out.extend_from_slice(&keccak256(part)),
+                TupleKeyEncoding::LengthPrefixed => {
+                    out.extend_from_slice(&(part.len() as u32).to_be_bytes());
+                    out.extend_from_slice(part);
+                }
+                TupleKeyEncoding::Raw => out.extend_from_slice(part),
+            }
+        }
+        out
+    }
+
+    /// `keccak256` of the encoded parts
+    pub fn to_trie_key(&self) -> H256 {
+        keccak256(&self.encode())
+    }
+
+    /// Nibbles of `to_trie_key`
+    pub fn to_nibbles(&self) -> Vec<u8> {
+        to_nibbles(&self.to_trie_key())
+    }
+}
+
 /// Convert nibbles back to bytes
+///
+/// An odd-length input is padded with a trailing zero nibble, so `[1]`
+/// becomes `[0x10]`. Only even-length input round-trips through `to_nibbles`.
+#[must_use]
 pub fn from_nibbles(nibbles: &[u8]) -> Vec<u8> {
-    let mut bytes = Vec::with_capacity((nibbles.len() + 1) / 2);
+    let mut bytes = Vec::with_capacity(nibbles.len().div_ceil(2));
     for i in (0..nibbles.len()).step_by(2) {
         if i + 1 < nibbles.len() {
             bytes.push((nibbles[i] << 4) | nibbles[i + 1]);
@@ -86,9 +310,67 @@ pub fn from_nibbles(nibbles: &[u8]) -> Vec<u8> {
     bytes
 }
 
+/// Number of leading nibbles (or bytes) that `a` and `b` have in common
+#[inline]
+pub fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// `common_prefix_len` comparing 32 bytes at a time with AVX2, or 16 with SSE2
+///
+/// AVX2 is used only when compiled in (`-C target-feature=+avx2`); targets other than
+/// x86_64 fall back to the scalar loop. The builder uses this when the `simd` feature is on.
+#[cfg(feature = "simd")]
+pub fn common_prefix_len_simd(a: &[u8], b: &[u8]) -> usize {
+    let len = a.len().min(b.len());
+    let start = simd_prefix_len(&a[..len], &b[..len]);
+    start + common_prefix_len(&a[start..len], &b[start..len])
+}
+
+/// Compare whole chunks of two equal-length slices, returning the index of the first
+/// mismatch or where the unchunked tail starts
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn simd_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    use core::arch::x86_64::*;
+    
+    let mut i = 0;
+    #[cfg(target_feature = "avx2")]
+    while i + 32 <= a.len() {
+        // SAFETY: AVX2 is enabled at compile time and both loads are in bounds
+        let equal = unsafe {
+            let x = _mm256_loadu_si256(a.as_ptr().add(i) as *const __m256i);
+            let y = _mm256_loadu_si256(b.as_ptr().add(i) as *const __m256i);
+            _mm256_movemask_epi8(_mm256_cmpeq_epi8(x, y)) as u32
+        };
+        if equal != u32::MAX {
+            return i + equal.trailing_ones() as usize;
+        }
+        i += 32;
+    }
+    while i + 16 <= a.len() {
+        // SAFETY: SSE2 is part of the x86_64 baseline and both loads are in bounds
+        let equal = unsafe {
+            let x = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
+            let y = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
+            _mm_movemask_epi8(_mm_cmpeq_epi8(x, y)) as u16
+        };
+        if equal != u16::MAX {
+            return i + equal.trailing_ones() as usize;
+        }
+        i += 16;
+    }
+    i
+}
+
+#[cfg(all(feature = "simd", not(target_arch = "x86_64")))]
+fn simd_prefix_len(_a: &[u8], _b: &[u8]) -> usize {
+    0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_encode_decode_path_leaf_odd() {
@@ -120,6 +402,61 @@ mod tests {
         assert!(!is_leaf);
     }
 
+    #[test]
+    fn test_encode_path_short_combinations() {
+        // is_leaf x nibbles.len() in {0, 1}
+        assert_eq!(encode_path(&[], false), vec![0x00]);
+        assert_eq!(encode_path(&[], true), vec![0x20]);
+        assert_eq!(encode_path(&[0xa], false), vec![0x1a]);
+        assert_eq!(encode_path(&[0xa], true), vec![0x3a]);
+        
+        assert_eq!(decode_path(&[0x00]), (vec![], false));
+        assert_eq!(decode_path(&[0x20]), (vec![], true));
+        assert_eq!(decode_path(&[0x1a]), (vec![0xa], false));
+        assert_eq!(decode_path(&[0x3a]), (vec![0xa], true));
+    }
+
+    #[test]
+    fn test_empty_extension_not_confused_with_zero_nibbles() {
+        // [0x00] is the empty extension; two zero nibbles need a second byte
+        assert_eq!(encode_path(&[0, 0], false), vec![0x00, 0x00]);
+        assert_eq!(decode_path(&[0x00, 0x00]), (vec![0, 0], false));
+        assert_ne!(encode_path(&[], false), encode_path(&[0, 0], false));
+    }
+
+    #[test]
+    fn test_encode_path_yellow_paper_vectors() {
+        // Hex-prefix examples from the Ethereum Yellow Paper (Appendix C)
+        assert_eq!(encode_path(&[1, 2, 3, 4, 5], false), vec![0x11, 0x23, 0x45]);
+        assert_eq!(encode_path(&[0, 1, 2, 3, 4, 5], false), vec![0x00, 0x01, 0x23, 0x45]);
+        assert_eq!(encode_path(&[0, 0xf, 1, 0xc, 0xb, 8], true), vec![0x20, 0x0f, 0x1c, 0xb8]);
+        assert_eq!(encode_path(&[0xf, 1, 0xc, 0xb, 8], true), vec![0x3f, 0x1c, 0xb8]);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_encode_decode_path_roundtrip(
+            nibbles in proptest::collection::vec(0u8..16, 0..64),
+            is_leaf: bool,
+        ) {
+            let encoded = encode_path(&nibbles, is_leaf);
+            proptest::prop_assert_eq!(decode_path(&encoded), (nibbles, is_leaf));
+        }
+    }
+
+    #[test]
+    fn test_hex_prefix_aliases() {
+        let nibbles = [1, 2, 3, 4, 5];
+        assert_eq!(hex_prefix_encode(&nibbles, true), vec![0x31, 0x23, 0x45]);
+        assert_eq!(hex_prefix_decode(&[0x31, 0x23, 0x45]), (nibbles.to_vec(), true));
+        
+        for len in 0..10 {
+            let nibbles = vec![0xa; len];
+            assert_eq!(hp_encoded_len(len), encode_path(&nibbles, false).len());
+            assert_eq!(hp_encoded_len(len), encode_path(&nibbles, true).len());
+        }
+    }
+
     #[test]
     fn test_to_nibbles() {
         let data = vec![0x12, 0x34, 0xab];
@@ -127,10 +464,143 @@ mod tests {
         assert_eq!(nibbles, vec![1, 2, 3, 4, 10, 11]);
     }
 
+    #[test]
+    fn test_storage_key_to_nibbles_keeps_leading_zeros() {
+        let mut slot = [0u8; 32];
+        slot[31] = 0x01;
+        let nibbles = storage_key_to_nibbles(&slot);
+        
+        assert_eq!(nibbles.len(), 64);
+        assert!(nibbles[..63].iter().all(|&n| n == 0));
+        assert_eq!(nibbles[63], 1);
+        assert_eq!(nibbles.to_vec(), to_nibbles(&slot));
+    }
+
+    #[test]
+    fn test_account_address_to_state_key() {
+        let address = [0xab; 20];
+        let key = account_address_to_state_key(&address);
+        assert_eq!(key, keccak256(&address));
+        assert_eq!(storage_key_to_nibbles(&key).to_vec(), to_nibbles(&key));
+    }
+
     #[test]
     fn test_from_nibbles() {
         let nibbles = vec![1, 2, 3, 4, 10, 11];
         let data = from_nibbles(&nibbles);
         assert_eq!(data, vec![0x12, 0x34, 0xab]);
     }
+
+    #[test]
+    fn test_from_nibbles_odd_length_pads_low_nibble() {
+        assert_eq!(from_nibbles(&[1]), vec![0x10]);
+        assert_eq!(from_nibbles(&[1, 2, 3]), vec![0x12, 0x30]);
+        
+        // Padding makes odd input indistinguishable from its zero-extended form
+        assert_eq!(from_nibbles(&[1, 2, 3]), from_nibbles(&[1, 2, 3, 0]));
+        assert_eq!(to_nibbles(&from_nibbles(&[1, 2, 3])), vec![1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn test_tuple_key_erc20_balance_slot() {
+        // balances[0x00..00] in a mapping at slot 0: keccak256(abi.encode(address(0), 0))
+        let zero_word = [0u8; 32];
+        let key = TupleKey::with_encoding(&[&zero_word, &zero_word], TupleKeyEncoding::Raw);
+        assert_eq!(
+            key.to_trie_key(),
+            [
+                0xad, 0x32, 0x28, 0xb6, 0x76, 0xf7, 0xd3, 0xcd, 0x42, 0x84, 0xa5, 0x44, 0x3f, 0x17, 0xf1, 0x96,
+                0x2b, 0x36, 0xe4, 0x91, 0xb3, 0x0a, 0x40, 0xb2, 0x40, 0x58, 0x49, 0xe5, 0x97, 0xba, 0x5f, 0xb5,
+            ]
+        );
+        
+        // The address is left-padded to a word, as abi.encode does
+        let mut holder = [0u8; 32];
+        holder[12..].fill(0x42);
+        let mut slot = [0u8; 32];
+        slot[31] = 3;
+        let key = TupleKey::with_encoding(&[&holder, &slot], TupleKeyEncoding::Raw);
+        assert_eq!(key.to_trie_key(), keccak256(&[holder, slot].concat()));
+        assert_eq!(key.to_nibbles(), to_nibbles(&key.to_trie_key()));
+    }
+
+    #[test]
+    fn test_tuple_key_encodings() {
+        let hashed = TupleKey::new(&[b"ab", b"c"]);
+        assert_eq!(hashed.encode(), [keccak256(b"ab"), keccak256(b"c")].concat());
+        
+        // Raw concatenation cannot tell where parts split; the other encodings can
+        let raw = |parts: &[&[u8]]| TupleKey::with_encoding(parts, TupleKeyEncoding::Raw).to_trie_key();
+        assert_eq!(raw(&[b"ab", b"c"]), raw(&[b"a", b"bc"]));
+        let prefixed = |parts: &[&[u8]]| TupleKey::with_encoding(parts, TupleKeyEncoding::LengthPrefixed);
+        assert_eq!(prefixed(&[b"ab", b"c"]).encode(), [&[0, 0, 0, 2][..], b"ab", &[0, 0, 0, 1], b"c"].concat());
+        assert_ne!(prefixed(&[b"ab", b"c"]).to_trie_key(), prefixed(&[b"a", b"bc"]).to_trie_key());
+        assert_ne!(TupleKey::new(&[b"ab", b"c"]).to_trie_key(), TupleKey::new(&[b"a", b"bc"]).to_trie_key());
+    }
+
+    #[test]
+    fn test_nibble_slice() {
+        let key = [0x12, 0x34, 0xab];
+        let slice = NibbleSlice::new(&key);
+        assert_eq!(slice.len(), 6);
+        assert_eq!(slice.to_vec(), to_nibbles(&key));
+        assert_eq!((slice.get(0), slice.get(1), slice.get(5)), (1, 2, 0xb));
+        
+        // Advancing by an odd count starts the view mid-byte
+        let rest = slice.advance(3);
+        assert_eq!(rest.len(), 3);
+        assert_eq!(rest.get(0), 4);
+        assert!(rest.starts_with_nibbles(&[4, 0xa]));
+        assert!(!rest.starts_with_nibbles(&[4, 0xb]));
+        assert!(!rest.starts_with_nibbles(&[4, 0xa, 0xb, 0]));
+        assert!(rest.eq_nibbles(&[4, 0xa, 0xb]));
+        assert!(!rest.eq_nibbles(&[4, 0xa]));
+        assert!(rest.starts_with_nibbles(&[]));
+        
+        let end = rest.advance(3);
+        assert!(end.is_empty());
+        assert!(end.eq_nibbles(&[]));
+        assert!(NibbleSlice::new(&[]).is_empty());
+    }
+    
+    #[test]
+    fn test_path_to_key() {
+        let path = key_to_nibble_path(b"dog");
+        assert_eq!(&path[..], &to_nibbles(b"dog")[..]);
+        assert_eq!(path.to_key(), Ok(b"dog".to_vec()));
+        assert_eq!(path_to_key(&[]), Ok(vec![]));
+        
+        // from_nibbles would pad these to 0x10 and 0x6460
+        assert_eq!(path_to_key(&[1]), Err(PathError::OddLength(1)));
+        assert_eq!(path_to_key(&path[..3]), Err(PathError::OddLength(3)));
+    }
+    
+    #[test]
+    fn test_common_prefix_len() {
+        assert_eq!(common_prefix_len(&[1, 2, 3], &[1, 2, 4]), 2);
+        assert_eq!(common_prefix_len(&[1, 2], &[1, 2, 3]), 2);
+        assert_eq!(common_prefix_len(&[], &[1]), 0);
+    }
+    
+    #[cfg(feature = "simd")]
+    #[test]
+    fn test_common_prefix_len_simd_matches_scalar() {
+        let a = to_nibbles(&keccak256(b"key"));
+        // A mismatch in every position, inside and after each 16 or 32 byte chunk
+        for i in 0..a.len() {
+            let mut b = a.clone();
+            b[i] ^= 1;
+            assert_eq!(common_prefix_len_simd(&a, &b), i);
+            assert_eq!(common_prefix_len_simd(&a[..i + 1], &b[..i + 1]), common_prefix_len(&a[..i + 1], &b[..i + 1]));
+        }
+        assert_eq!(common_prefix_len_simd(&a, &a), 64);
+        assert_eq!(common_prefix_len_simd(&a[..40], &a), 40);
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_from_nibbles_roundtrip(bytes in proptest::collection::vec(proptest::num::u8::ANY, 0..64)) {
+            proptest::prop_assert_eq!(from_nibbles(&to_nibbles(&bytes)), bytes);
+        }
+    }
 }
@@ -0,0 +1,229 @@
+use alloc::vec::Vec;
+use crate::mpt::follow_child;
+use crate::path::{decode_path, to_nibbles};
+use crate::rlp_encoding::{decode_bytes, decode_list, keccak256};
+use crate::types::H256;
+use crate::witness::NodeKind;
+
+/// One node visited by a `ProofReplayer`
+///
+/// `consumed_nibbles` and `remaining_nibbles` split the key after this node. `hash_valid`
+/// is whether the node hashes to the reference its parent (or the root) gave; embedded
+/// nodes are part of their parent and always valid.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofReplayStep {
+    pub step: usize,
+    pub node_kind: NodeKind,
+    pub consumed_nibbles: Vec<u8>,
+    pub remaining_nibbles: Vec<u8>,
+    pub node_hash: H256,
+    pub hash_valid: bool,
+}
+
+/// Walks a proof node by node the way `verify_proof` does, yielding each step
+///
+/// The walk stops after the first step that fails (bad hash, path mismatch, missing
+/// child) or at the node holding the key's value. Nodes that are not valid trie nodes
+/// end the walk without a step.
+#[derive(Clone, Debug)]
+pub struct ProofReplayer<'a> {
+    proof: &'a [Vec<u8>],
+    nibbles: Vec<u8>,
+    step: usize,
+    expected_hash: H256,
+    next_node: usize,
+    consumed: usize,
+    inline_node: Option<Vec<u8>>,
+    done: bool,
+    value: Option<Vec<u8>>,
+}
+
+impl<'a> ProofReplayer<'a> {
+    /// Replay `proof` for `key` against `root`
+    pub fn new(root: &H256, key: &[u8], proof: &'a [Vec<u8>]) -> Self {
+        Self {
+            proof,
+            nibbles: to_nibbles(key),
+            step: 0,
+            expected_hash: *root,
+            next_node: 0,
+            consumed: 0,
+            inline_node: None,
+            done: false,
+            value: None,
+        }
+    }
+
+    /// The value the proof shows for the key, once the replay has ended
+    ///
+    /// `None` while steps remain, or if any step failed or the key is absent.
+    pub fn final_value(&self) -> Option<Vec<u8>> {
+        self.value.clone().filter(|_| self.done)
+    }
+
+    /// Decode the next node and advance; returns its kind and whether the walk goes on
+    fn visit(&mut self, node_rlp: &[u8]) -> Option<(NodeKind, bool)> {
+        let mut items = decode_list(node_rlp).ok()?;
+        let rest = &self.nibbles[self.consumed..];
+        match items.len() {
+            2 => {
+                let (path, is_leaf) = decode_path(&decode_bytes(&items[0]).ok()?);
+                if is_leaf {
+                    // The leaf must be the last node of the proof
+                    if path == rest && self.next_node == self.proof.len() {
+                        self.consumed = self.nibbles.len();
+                        self.value = decode_bytes(&items[1]).ok().filter(|value| !value.is_empty());
+                    }
+                    return Some((NodeKind::Leaf, false));
+                }
+                if !rest.starts_with(&path) {
+                    return Some((NodeKind::Extension, false));
+                }
+                self.consumed += path.len();
+                let ok = follow_child(items.swap_remove(1), &mut self.expected_hash, &mut self.inline_node);
+                Some((NodeKind::Extension, ok))
+            }
+            17 => {
+                let Some(&nibble) = rest.first() else {
+                    self.value = decode_bytes(&items[16]).ok().filter(|value| !value.is_empty());
+                    return Some((NodeKind::Branch, false));
+                };
+                self.consumed += 1;
+                let child = items.swap_remove(nibble as usize);
+                let ok = follow_child(child, &mut self.expected_hash, &mut self.inline_node);
+                Some((NodeKind::Branch, ok))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Iterator for ProofReplayer<'_> {
+    type Item = ProofReplayStep;
+
+    fn next(&mut self) -> Option<ProofReplayStep> {
+        if self.done {
+            return None;
+        }
+
+        let (node_rlp, embedded) = match self.inline_node.take() {
+            Some(node) => (node, true),
+            None => match self.proof.get(self.next_node) {
+                Some(node) => {
+                    self.next_node += 1;
+                    (node.clone(), false)
+                }
+                None => {
+                    self.done = true;
+                    return None;
+                }
+            },
+        };
+
+        let node_hash = keccak256(&node_rlp);
+        let hash_valid = embedded || node_hash == self.expected_hash;
+        let Some((node_kind, continues)) = self.visit(&node_rlp) else {
+            self.done = true;
+            self.value = None;
+            return None;
+        };
+        if !hash_valid || !continues {
+            self.done = true;
+        }
+        if !hash_valid {
+            self.value = None;
+        }
+
+        let step = ProofReplayStep {
+            step: self.step,
+            node_kind,
+            consumed_nibbles: self.nibbles[..self.consumed].to_vec(),
+            remaining_nibbles: self.nibbles[self.consumed..].to_vec(),
+            node_hash,
+            hash_valid,
+        };
+        self.step += 1;
+        Some(step)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::builder::MPTBuilder;
+    use crate::mpt::proof_value;
+    use alloc::vec;
+
+    /// Ten one-byte keys: extension [0] -> branch -> leaf, all hashed
+    fn three_node_trie() -> (MPTBuilder, H256) {
+        let mut builder = MPTBuilder::new();
+        for i in 0..10u8 {
+            builder.insert(&[i], &[i; 40]);
+        }
+        let root = builder.root().unwrap();
+        (builder, root)
+    }
+
+    #[test]
+    fn test_replay_three_node_proof() {
+        let (builder, root) = three_node_trie();
+        let proof = builder.get_proof(&[7]).unwrap();
+        assert_eq!(proof.len(), 3);
+
+        let mut replayer = ProofReplayer::new(&root, &[7], &proof);
+        let steps: Vec<ProofReplayStep> = replayer.by_ref().collect();
+        let kinds: Vec<NodeKind> = steps.iter().map(|s| s.node_kind).collect();
+        assert_eq!(kinds, vec![NodeKind::Extension, NodeKind::Branch, NodeKind::Leaf]);
+        assert!(steps.iter().all(|s| s.hash_valid));
+        assert_eq!(steps.iter().map(|s| s.step).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(steps[0].node_hash, root);
+        assert_eq!(steps[0].consumed_nibbles, vec![0]);
+        assert_eq!(steps[1].consumed_nibbles, vec![0, 7]);
+        assert!(steps[2].remaining_nibbles.is_empty());
+
+        assert_eq!(replayer.final_value(), Some(vec![7; 40]));
+        assert_eq!(replayer.final_value(), proof_value(&root, &[7], &proof));
+    }
+
+    #[test]
+    fn test_replay_stops_where_proof_diverges() {
+        let (builder, root) = three_node_trie();
+        let mut proof = builder.get_proof(&[7]).unwrap();
+
+        // Not exhausted yet
+        let mut replayer = ProofReplayer::new(&root, &[7], &proof);
+        replayer.next();
+        assert_eq!(replayer.final_value(), None);
+
+        // A tampered leaf fails the hash check at step 2
+        proof[2] = builder.get_proof(&[8]).unwrap()[2].clone();
+        let mut replayer = ProofReplayer::new(&root, &[7], &proof);
+        let steps: Vec<ProofReplayStep> = replayer.by_ref().collect();
+        assert_eq!(steps.len(), 3);
+        assert!(steps[1].hash_valid);
+        assert!(!steps[2].hash_valid);
+        assert_eq!(replayer.final_value(), None);
+
+        // A key leaving the extension stops right there
+        let proof = builder.get_proof(&[7]).unwrap();
+        let steps: Vec<ProofReplayStep> = ProofReplayer::new(&root, &[0x17], &proof).collect();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].remaining_nibbles, vec![1, 7]);
+    }
+
+    #[test]
+    fn test_replay_embedded_nodes() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        let root = builder.insert(b"doge", b"coin");
+        let proof = builder.get_proof(b"doge").unwrap();
+
+        let mut replayer = ProofReplayer::new(&root, b"doge", &proof);
+        let steps: Vec<ProofReplayStep> = replayer.by_ref().collect();
+        assert!(steps.len() > proof.len());
+        assert!(steps.iter().all(|s| s.hash_valid));
+        assert_eq!(steps.last().unwrap().node_kind, NodeKind::Leaf);
+        assert_eq!(replayer.final_value(), Some(b"coin".to_vec()));
+    }
+}
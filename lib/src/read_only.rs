@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::path::{decode_path, to_nibbles};
+use crate::rlp_encoding::{decode_bytes, decode_list};
+use crate::types::H256;
+
+/// The hashed nodes visited by a lookup and the value found, if any
+type Lookup<'a> = (Vec<&'a [u8]>, Option<Vec<u8>>);
+
+/// An immutable trie for serving lookups and proofs against a fixed root
+///
+/// Created with `MPTBuilder::into_read_only`. Nodes are decoded from their RLP on each
+/// lookup and shared behind an `Arc`, so an `Arc<ReadOnlyMPT>` (or a clone) can be used
+/// from many threads without locking.
+#[derive(Clone, Debug)]
+pub struct ReadOnlyMPT {
+    nodes: Arc<HashMap<H256, Vec<u8>>>,
+    root: H256,
+}
+
+impl ReadOnlyMPT {
+    pub(crate) fn new(nodes: Arc<HashMap<H256, Vec<u8>>>, root: H256) -> Self {
+        Self { nodes, root }
+    }
+
+    /// Get the root hash
+    pub fn root(&self) -> H256 {
+        self.root
+    }
+
+    /// Get a value by key
+    pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        self.lookup(key)?.1
+    }
+
+    /// Generate a proof for a key, in the same form as `MPTBuilder::get_proof`
+    pub fn get_proof(&self, key: &[u8]) -> Option<Vec<Vec<u8>>> {
+        let (proof, _) = self.lookup(key)?;
+        Some(proof.into_iter().map(<[u8]>::to_vec).collect())
+    }
+
+    /// Walk towards `key`, returning the hashed nodes visited and the value found
+    ///
+    /// Returns `None` if the key diverges from an extension or hits an empty branch slot.
+    /// Embedded children are walked in place and do not appear in the proof.
+    fn lookup(&self, key: &[u8]) -> Option<Lookup<'_>> {
+        let nibbles = to_nibbles(key);
+        let mut remaining = &nibbles[..];
+        let root_rlp = self.nodes.get(&self.root)?;
+        let mut proof = vec![&root_rlp[..]];
+        let mut node: Cow<[u8]> = Cow::Borrowed(root_rlp);
+
+        loop {
+            let mut items = decode_list(&node).ok()?;
+            let child = match items.len() {
+                2 => {
+                    let (path, is_leaf) = decode_path(&decode_bytes(&items[0]).ok()?);
+                    if is_leaf {
+                        let value = if path == remaining { decode_bytes(&items[1]).ok() } else { None };
+                        return Some((proof, value));
+                    }
+                    remaining = remaining.strip_prefix(&path[..])?;
+                    items.swap_remove(1)
+                }
+                17 => match remaining.split_first() {
+                    None => {
+                        let value = decode_bytes(&items[16]).ok().filter(|v| !v.is_empty());
+                        return Some((proof, value));
+                    }
+                    Some((&nibble, rest)) => {
+                        remaining = rest;
+                        items.swap_remove(nibble as usize)
+                    }
+                },
+                _ => return None,
+            };
+
+            node = if child.first().is_some_and(|&prefix| prefix >= 0xc0) {
+                Cow::Owned(child)
+            } else {
+                let hash: H256 = decode_bytes(&child).ok()?.try_into().ok()?;
+                let rlp = self.nodes.get(&hash)?;
+                proof.push(rlp);
+                Cow::Borrowed(rlp)
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::MPTBuilder;
+    use crate::mpt::verify_proof;
+
+    type Entries = Vec<(Vec<u8>, Vec<u8>)>;
+
+    fn build(count: u32) -> (MPTBuilder, Entries) {
+        let mut builder = MPTBuilder::new();
+        let entries: Entries = (0..count)
+            .map(|i| (format!("key-{}", i).into_bytes(), format!("v{}", i).into_bytes()))
+            .collect();
+        for (key, value) in &entries {
+            builder.insert(key, value);
+        }
+        (builder, entries)
+    }
+
+    #[test]
+    fn test_read_only_matches_builder() {
+        let (builder, entries) = build(200);
+        let expected: Vec<_> = entries.iter().map(|(key, _)| builder.get_proof(key)).collect();
+        let root = builder.root().unwrap();
+
+        let trie = builder.into_read_only();
+        assert_eq!(trie.root(), root);
+        for ((key, value), proof) in entries.iter().zip(expected) {
+            assert_eq!(trie.get(key).as_ref(), Some(value));
+            assert_eq!(trie.get_proof(key), proof);
+        }
+        assert_eq!(trie.get(b"key-"), None);
+        assert_eq!(trie.get(b"missing"), None);
+
+        let empty = MPTBuilder::new().into_read_only();
+        assert_eq!(empty.get(b"key-1"), None);
+        assert_eq!(empty.get_proof(b"key-1"), None);
+    }
+
+    #[test]
+    fn test_read_only_concurrent_proofs() {
+        let (builder, entries) = build(500);
+        let trie = Arc::new(builder.into_read_only());
+        let entries = Arc::new(entries);
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let trie = Arc::clone(&trie);
+                let entries = Arc::clone(&entries);
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        let (key, value) = &entries[(t * 100 + i) % entries.len()];
+                        let proof = trie.get_proof(key).unwrap();
+                        assert!(verify_proof(&trie.root(), key, value, &proof));
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+}
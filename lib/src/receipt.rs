@@ -0,0 +1,96 @@
+use crate::builder::MPTBuilder;
+use crate::rlp_encoding::encode_u64;
+use crate::types::H256;
+
+/// Builds an Ethereum receipt trie (`RLP(txIndex) -> receipt`)
+///
+/// Receipts are inserted as their consensus encoding: the plain RLP list for
+/// legacy receipts, or `type || RLP(list)` for typed (EIP-2718) receipts.
+pub struct ReceiptTrieBuilder {
+    builder: MPTBuilder,
+}
+
+impl ReceiptTrieBuilder {
+    pub fn new() -> Self {
+        Self {
+            builder: MPTBuilder::new(),
+        }
+    }
+
+    /// Insert a receipt at its transaction index and return the new root
+    pub fn insert_receipt(&mut self, tx_index: u64, receipt_rlp: &[u8]) -> H256 {
+        self.builder.insert(&encode_u64(tx_index), receipt_rlp)
+    }
+
+    /// Get the current receipts root (the empty trie root if nothing was inserted)
+    pub fn root(&self) -> H256 {
+        self.builder.root_or_empty()
+    }
+
+    /// Generate a proof for the receipt at `tx_index`
+    pub fn get_proof(&self, tx_index: u64) -> Option<Vec<Vec<u8>>> {
+        self.builder.get_proof(&encode_u64(tx_index))
+    }
+}
+
+impl Default for ReceiptTrieBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compute the `receiptsRoot` of a block from its receipts, in transaction order
+pub fn receipts_root(receipts: &[Vec<u8>]) -> H256 {
+    let mut builder = ReceiptTrieBuilder::new();
+    for (i, receipt) in receipts.iter().enumerate() {
+        builder.insert_receipt(i as u64, receipt);
+    }
+    builder.root()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::from_hex;
+    use crate::mpt::verify_proof;
+
+    #[test]
+    fn test_receipts_root_fixture() {
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/receipts.json")).unwrap();
+        let receipts: Vec<Vec<u8>> = fixture["receipts"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| from_hex(r.as_str().unwrap()))
+            .collect();
+        let expected = from_hex(fixture["receiptsRoot"].as_str().unwrap());
+
+        assert_eq!(receipts_root(&receipts).to_vec(), expected);
+    }
+
+    #[test]
+    fn test_receipts_root_empty() {
+        assert_eq!(
+            receipts_root(&[]).to_vec(),
+            from_hex("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421")
+        );
+    }
+
+    #[test]
+    fn test_receipt_proof() {
+        let receipts: Vec<Vec<u8>> = (0..20u8).map(|i| vec![i; 40]).collect();
+
+        let mut builder = ReceiptTrieBuilder::new();
+        for (i, receipt) in receipts.iter().enumerate() {
+            builder.insert_receipt(i as u64, receipt);
+        }
+        let root = builder.root();
+        assert_eq!(root, receipts_root(&receipts));
+
+        for (i, receipt) in receipts.iter().enumerate() {
+            let proof = builder.get_proof(i as u64).unwrap();
+            assert!(verify_proof(&root, &encode_u64(i as u64), receipt, &proof));
+        }
+    }
+}
@@ -1,19 +1,38 @@
+use alloc::vec;
+use alloc::vec::Vec;
 use crate::types::H256;
 
 /// Compute Keccak256 hash
 /// 
 /// When running in SP1 zkVM, tiny_keccak automatically uses the optimized
 /// KECCAK_PERMUTE precompile syscall for better performance.
+#[must_use]
+#[inline]
 pub fn keccak256(data: &[u8]) -> H256 {
     use tiny_keccak::{Hasher, Keccak};
     let mut hasher = Keccak::v256();
     hasher.update(data);
     let mut output = [0u8; 32];
     hasher.finalize(&mut output);
-    output.into()
+    output
+}
+
+/// RLP encoding of the empty string, `encode_bytes(&[])`
+/// 
+/// In a trie this is the null reference: an empty branch slot, an absent branch value,
+/// and (hashed) the root of the empty trie.
+pub const RLP_EMPTY: &[u8] = &[0x80];
+
+/// Whether `data` is the RLP-encoded empty string (`RLP_EMPTY`)
+#[must_use]
+#[inline]
+pub fn is_rlp_null(data: &[u8]) -> bool {
+    data == RLP_EMPTY
 }
 
 /// Encode a byte string using RLP
+#[must_use]
+#[inline]
 pub fn encode_bytes(data: &[u8]) -> Vec<u8> {
     if data.len() == 1 && data[0] < 0x80 {
         // Single byte less than 128: encode as itself
@@ -33,26 +52,114 @@ pub fn encode_bytes(data: &[u8]) -> Vec<u8> {
     }
 }
 
+/// Encode an unsigned integer using RLP (minimal big-endian, zero is `0x80`)
+pub fn encode_u64(value: u64) -> Vec<u8> {
+    rlp_encode_integer(value)
+}
+
+/// Encode a 256-bit big-endian unsigned integer using RLP (minimal, zero is `0x80`)
+pub fn encode_u256(value: &H256) -> Vec<u8> {
+    rlp_encode_bigint(value)
+}
+
+/// Encode an integer as Ethereum does: big-endian without leading zeros, so 0 is `0x80`
+/// 
+/// Unlike `encode_bytes(&[0])`, which is the one-byte string `0x00`.
+pub fn rlp_encode_integer(n: u64) -> Vec<u8> {
+    rlp_encode_bigint(&n.to_be_bytes())
+}
+
+/// Encode a big-endian integer of any width, stripping its leading zeros
+pub fn rlp_encode_bigint(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    encode_bytes(&bytes[start..])
+}
+
+/// Decode an integer encoded by `rlp_encode_integer`
+/// 
+/// The input must be exactly one byte string of at most 8 bytes without leading zeros,
+/// so each integer has a single accepted encoding.
+pub fn rlp_decode_integer(data: &[u8]) -> Result<u64, &'static str> {
+    let (bytes, end) = decode_bytes_at(data, 0)?;
+    if end != data.len() {
+        return rlp_error("Invalid RLP: trailing bytes");
+    }
+    if bytes.first() == Some(&0) {
+        return rlp_error("Non-canonical integer: leading zero");
+    }
+    if bytes.len() > 8 {
+        return rlp_error("Integer overflows u64");
+    }
+    Ok(bytes.iter().fold(0, |n, &byte| (n << 8) | byte as u64))
+}
+
 /// Encode a list using RLP
+#[must_use]
 pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
-    let mut payload = Vec::new();
-    for item in items {
-        payload.extend_from_slice(item);
-    }
+    let mut encoded = Vec::new();
+    encode_list_into(items, &mut encoded);
+    encoded
+}
+
+/// Length of `encode_list(items)` without encoding it
+/// 
+/// Like `encode_list`, the items are already RLP-encoded, so each adds its own length.
+pub fn encode_list_len(items: &[impl AsRef<[u8]>]) -> usize {
+    rlp_size_of_list(items.iter().map(|item| item.as_ref().len()).sum())
+}
+
+/// Append `encode_list(items)` to `buf`, reserving the exact length up front
+/// 
+/// Nothing is allocated if `buf` already has the capacity.
+pub fn encode_list_into(items: &[impl AsRef<[u8]>], buf: &mut Vec<u8>) {
+    let payload_len: usize = items.iter().map(|item| item.as_ref().len()).sum();
+    buf.reserve(rlp_size_of_list(payload_len));
     
-    if payload.len() < 56 {
+    if payload_len < 56 {
         // Short list
-        let mut encoded = vec![0xc0 + payload.len() as u8];
-        encoded.extend_from_slice(&payload);
-        encoded
+        buf.push(0xc0 + payload_len as u8);
     } else {
-        // Long list
-        let len_bytes = length_to_bytes(payload.len());
-        let mut encoded = vec![0xf7 + len_bytes.len() as u8];
-        encoded.extend_from_slice(&len_bytes);
-        encoded.extend_from_slice(&payload);
-        encoded
+        // Long list, its length taken from the big-endian bytes on the stack
+        let len_bytes = payload_len.to_be_bytes();
+        let len_of_len = len_of_len(payload_len);
+        buf.push(0xf7 + len_of_len as u8);
+        buf.extend_from_slice(&len_bytes[len_bytes.len() - len_of_len..]);
     }
+    for item in items {
+        buf.extend_from_slice(item.as_ref());
+    }
+}
+
+/// `encode_bytes(hash)` without allocating: the `0xa0` prefix and the 32 bytes
+pub(crate) fn encode_hash_item(hash: &H256) -> [u8; 33] {
+    let mut item = [0xa0; 33];
+    item[1..].copy_from_slice(hash);
+    item
+}
+
+/// Size of `encode_bytes(data)` without encoding it
+pub fn rlp_size_of_bytes(data: &[u8]) -> usize {
+    if data.len() == 1 && data[0] < 0x80 {
+        1
+    } else if data.len() < 56 {
+        1 + data.len()
+    } else {
+        1 + len_of_len(data.len()) + data.len()
+    }
+}
+
+/// Size of an RLP list whose items take `items_total_encoded_len` bytes once encoded
+pub fn rlp_size_of_list(items_total_encoded_len: usize) -> usize {
+    if items_total_encoded_len < 56 {
+        1 + items_total_encoded_len
+    } else {
+        1 + len_of_len(items_total_encoded_len) + items_total_encoded_len
+    }
+}
+
+/// Number of bytes in the big-endian encoding of a non-zero length
+fn len_of_len(len: usize) -> usize {
+    (usize::BITS - len.leading_zeros()).div_ceil(8) as usize
 }
 
 /// Convert length to big-endian bytes
@@ -72,78 +179,232 @@ fn length_to_bytes(len: usize) -> Vec<u8> {
 }
 
 /// Decode RLP-encoded data into a list of byte vectors
+/// 
+/// Each item is returned with its own RLP prefix. Data that is not a list is returned as
+/// a single item.
 pub fn decode_list(data: &[u8]) -> Result<Vec<Vec<u8>>, &'static str> {
-    if data.is_empty() {
-        return Err("Empty input");
+    let Some(&prefix) = data.first() else {
+        return rlp_error("Empty input");
+    };
+    if prefix < 0xc0 {
+        // Single item, wrap in list
+        return Ok(vec![data.to_vec()]);
     }
     
-    let prefix = data[0];
+    let (payload_start, payload_len) = item_header_at(data, 0)?;
+    let end = match payload_start.checked_add(payload_len) {
+        Some(end) if end <= data.len() => end,
+        _ => return rlp_error("Invalid RLP: payload too short"),
+    };
     
-    // Handle list
-    if prefix >= 0xc0 {
-        let (payload_start, payload_len) = if prefix <= 0xf7 {
-            // Short list
-            (1, (prefix - 0xc0) as usize)
-        } else {
-            // Long list
-            let len_of_len = (prefix - 0xf7) as usize;
-            if data.len() < 1 + len_of_len {
-                return Err("Invalid RLP: insufficient data");
+    // Items are measured in place, without slicing the payload for each one
+    let mut items = Vec::new();
+    let mut pos = payload_start;
+    while pos < end {
+        let item_len = decode_item_len_at(data, pos)?;
+        if item_len > end - pos {
+            return rlp_error("Invalid RLP: item exceeds payload");
+        }
+        items.push(data[pos..pos + item_len].to_vec());
+        pos += item_len;
+    }
+    
+    Ok(items)
+}
+
+/// Decode a single RLP-encoded byte string
+pub fn decode_bytes(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    decode_bytes_at(data, 0).map(|(bytes, _)| bytes)
+}
+
+/// Decode a storage trie value into its slot word, a big-endian u256
+/// 
+/// An empty value (a slot the proof shows absent) is zero, as zero slots are not stored.
+/// Returns `None` unless the value is an RLP string of at most 32 bytes.
+pub(crate) fn decode_storage_word(value_rlp: &[u8]) -> Option<H256> {
+    let mut word = [0u8; 32];
+    if value_rlp.is_empty() {
+        return Some(word);
+    }
+    let bytes = decode_bytes(value_rlp).ok()?;
+    word.get_mut(32usize.checked_sub(bytes.len())?..)?.copy_from_slice(&bytes);
+    Some(word)
+}
+
+/// Decode the RLP byte string at `offset` in `data`, returning it and the offset just
+/// past it
+pub fn decode_bytes_at(data: &[u8], offset: usize) -> Result<(Vec<u8>, usize), &'static str> {
+    if data.get(offset).is_some_and(|&prefix| prefix >= 0xc0) {
+        return rlp_error("Not a byte string (it's a list)");
+    }
+    let (payload_start, payload_len) = item_header_at(data, offset)?;
+    let payload = payload_start
+        .checked_add(payload_len)
+        .and_then(|end| data.get(payload_start..end));
+    match payload {
+        Some(payload) => Ok((payload.to_vec(), payload_start + payload_len)),
+        None => rlp_error("Invalid RLP: insufficient data"),
+    }
+}
+
+/// Total encoded length, prefix included, of the RLP item (string or list) at `offset`
+pub fn decode_item_len_at(data: &[u8], offset: usize) -> Result<usize, &'static str> {
+    let (payload_start, payload_len) = item_header_at(data, offset)?;
+    match payload_start.checked_add(payload_len) {
+        Some(end) if end <= data.len() => Ok(end - offset),
+        _ => rlp_error("Invalid RLP: insufficient data"),
+    }
+}
+
+/// Where the payload of the item at `offset` starts, and its length
+/// 
+/// A single byte below 0x80 is its own payload.
+fn item_header_at(data: &[u8], offset: usize) -> Result<(usize, usize), &'static str> {
+    let Some(&prefix) = data.get(offset) else {
+        return rlp_error("Empty input");
+    };
+    match prefix {
+        0x00..=0x7f => Ok((offset, 1)),
+        0x80..=0xb7 => Ok((offset + 1, (prefix - 0x80) as usize)),
+        0xc0..=0xf7 => Ok((offset + 1, (prefix - 0xc0) as usize)),
+        _ => {
+            let len_of_len = (if prefix <= 0xbf { prefix - 0xb7 } else { prefix - 0xf7 }) as usize;
+            match data.get(offset + 1..offset + 1 + len_of_len) {
+                Some(len_bytes) => Ok((offset + 1 + len_of_len, bytes_to_length(len_bytes))),
+                None => rlp_error("Invalid RLP: insufficient length bytes"),
             }
-            let payload_len = bytes_to_length(&data[1..1 + len_of_len]);
-            (1 + len_of_len, payload_len)
-        };
-        
-        if data.len() < payload_start + payload_len {
-            return Err("Invalid RLP: payload too short");
         }
-        
-        // Parse items from payload
-        let mut items = Vec::new();
-        let mut pos = payload_start;
-        let end = payload_start + payload_len;
-        
-        while pos < end {
-            let item_prefix = data[pos];
-            
-            let item_len = if item_prefix < 0x80 {
-                // Single byte
-                1
-            } else if item_prefix <= 0xb7 {
-                // Short string
-                1 + (item_prefix - 0x80) as usize
-            } else if item_prefix <= 0xbf {
-                // Long string
-                let len_of_len = (item_prefix - 0xb7) as usize;
-                let data_len = bytes_to_length(&data[pos + 1..pos + 1 + len_of_len]);
-                1 + len_of_len + data_len
-            } else if item_prefix <= 0xf7 {
-                // Short list
-                1 + (item_prefix - 0xc0) as usize
-            } else {
-                // Long list
-                let len_of_len = (item_prefix - 0xf7) as usize;
-                let data_len = bytes_to_length(&data[pos + 1..pos + 1 + len_of_len]);
-                1 + len_of_len + data_len
+    }
+}
+
+/// Check that `data` is exactly one RLP item in canonical (minimal) form
+/// 
+/// A single byte below 0x80 must be its own encoding, payloads up to 55 bytes must use the
+/// short form, and long-form lengths must not have leading zeros; list items are checked
+/// recursively, at most `MAX_RLP_DEPTH` lists deep. `decode_list` and `decode_bytes` accept
+/// all of these, so without this check two encodings of the same node could both decode.
+pub fn assert_canonical(data: &[u8]) -> Result<(), &'static str> {
+    if canonical_item_len(data, 0)? != data.len() {
+        return rlp_error("Non-canonical RLP: trailing bytes");
+    }
+    Ok(())
+}
+
+/// Length of the canonical RLP item at the start of `data`, inside `depth` lists
+fn canonical_item_len(data: &[u8], depth: usize) -> Result<usize, &'static str> {
+    let Some(&prefix) = data.first() else {
+        return rlp_error("Empty input");
+    };
+    
+    let (header_len, payload_len) = match prefix {
+        0x00..=0x7f => return Ok(1),
+        0x80..=0xb7 => (1, (prefix - 0x80) as usize),
+        0xc0..=0xf7 => (1, (prefix - 0xc0) as usize),
+        _ => {
+            let len_of_len = (if prefix <= 0xbf { prefix - 0xb7 } else { prefix - 0xf7 }) as usize;
+            let Some(len_bytes) = data.get(1..1 + len_of_len) else {
+                return rlp_error("Invalid RLP: insufficient length bytes");
             };
-            
-            if pos + item_len > end {
-                return Err("Invalid RLP: item exceeds payload");
+            if len_bytes[0] == 0 || len_of_len > core::mem::size_of::<usize>() {
+                return rlp_error("Non-canonical RLP: length has leading zeros");
             }
-            
-            items.push(data[pos..pos + item_len].to_vec());
-            pos += item_len;
+            let len = bytes_to_length(len_bytes);
+            if len <= 55 {
+                return rlp_error("Non-canonical RLP: long form for a short payload");
+            }
+            (1 + len_of_len, len)
+        }
+    };
+    
+    let Some(payload) = header_len.checked_add(payload_len).and_then(|end| data.get(header_len..end)) else {
+        return rlp_error("Invalid RLP: insufficient data");
+    };
+    if prefix == 0x81 && payload[0] < 0x80 {
+        return rlp_error("Non-canonical RLP: single byte with a string prefix");
+    }
+    if prefix >= 0xc0 {
+        if depth == MAX_RLP_DEPTH {
+            return rlp_error("Invalid RLP: lists nested too deeply");
+        }
+        let mut pos = 0;
+        while pos < payload.len() {
+            pos += canonical_item_len(&payload[pos..], depth + 1)?;
         }
-        
-        Ok(items)
-    } else {
-        // Single item, wrap in list
-        Ok(vec![data.to_vec()])
     }
+    Ok(header_len + payload_len)
 }
 
-/// Decode a single RLP-encoded byte string
-pub fn decode_bytes(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+/// Error path of `decode_list` and `decode_bytes`, kept out of line so the hot path stays small
+#[cold]
+#[inline(never)]
+fn rlp_error<T>(msg: &'static str) -> Result<T, &'static str> {
+    Err(msg)
+}
+
+/// A fully decoded RLP item: either a byte string or a (possibly nested) list
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RlpValue {
+    Bytes(Vec<u8>),
+    List(Vec<RlpValue>),
+}
+
+impl RlpValue {
+    /// Unwrap a byte string item
+    pub fn into_bytes(self) -> Result<Vec<u8>, &'static str> {
+        match self {
+            RlpValue::Bytes(bytes) => Ok(bytes),
+            RlpValue::List(_) => Err("Not a byte string (it's a list)"),
+        }
+    }
+    
+    /// Unwrap a list item
+    pub fn into_list(self) -> Result<Vec<RlpValue>, &'static str> {
+        match self {
+            RlpValue::List(items) => Ok(items),
+            RlpValue::Bytes(_) => Err("Not a list (it's a byte string)"),
+        }
+    }
+}
+
+/// An RLP item tree for encoding with `encode_item`
+/// 
+/// This is the same type `decode_recursive` returns, so encoded items round-trip.
+pub type RlpItem = RlpValue;
+
+/// Recursively encode an RLP item tree
+/// 
+/// `encode_item(&RlpItem::Bytes(b))` equals `encode_bytes(&b)`, and a list of byte strings
+/// equals `encode_list` over their encodings, so both remain available for flat structures.
+pub fn encode_item(item: &RlpItem) -> Vec<u8> {
+    match item {
+        RlpValue::Bytes(bytes) => encode_bytes(bytes),
+        RlpValue::List(items) => {
+            let encoded: Vec<Vec<u8>> = items.iter().map(encode_item).collect();
+            encode_list(&encoded)
+        }
+    }
+}
+
+/// Deepest list nesting `decode_recursive` accepts
+/// 
+/// Each level of nesting costs one byte of input but a stack frame to decode, so
+/// untrusted input could otherwise overflow the stack.
+pub const MAX_RLP_DEPTH: usize = 64;
+
+/// Recursively decode an RLP item, descending into nested lists
+/// 
+/// Unlike `decode_list`, embedded lists are decoded rather than returned as raw bytes.
+/// The input must contain exactly one item, with lists nested at most `MAX_RLP_DEPTH` deep.
+pub fn decode_recursive(data: &[u8]) -> Result<RlpValue, &'static str> {
+    let (value, consumed) = decode_item(data, 0)?;
+    if consumed != data.len() {
+        return Err("Invalid RLP: trailing bytes");
+    }
+    Ok(value)
+}
+
+/// Decode the item at the start of `data`, inside `depth` lists, returning it with its encoded length
+fn decode_item(data: &[u8], depth: usize) -> Result<(RlpValue, usize), &'static str> {
     if data.is_empty() {
         return Err("Empty input");
     }
@@ -152,28 +413,127 @@ pub fn decode_bytes(data: &[u8]) -> Result<Vec<u8>, &'static str> {
     
     if prefix < 0x80 {
         // Single byte
-        Ok(data.to_vec())
-    } else if prefix <= 0xb7 {
-        // Short string
-        let len = (prefix - 0x80) as usize;
-        if data.len() < 1 + len {
-            return Err("Invalid RLP: insufficient data");
-        }
-        Ok(data[1..1 + len].to_vec())
-    } else if prefix <= 0xbf {
-        // Long string
-        let len_of_len = (prefix - 0xb7) as usize;
+        return Ok((RlpValue::Bytes(vec![prefix]), 1));
+    }
+    
+    let (payload_start, payload_len) = if prefix <= 0xb7 || (0xc0..=0xf7).contains(&prefix) {
+        // Short string or short list
+        let base = if prefix <= 0xb7 { 0x80 } else { 0xc0 };
+        (1, (prefix - base) as usize)
+    } else {
+        // Long string or long list
+        let base = if prefix <= 0xbf { 0xb7 } else { 0xf7 };
+        let len_of_len = (prefix - base) as usize;
         if data.len() < 1 + len_of_len {
             return Err("Invalid RLP: insufficient length bytes");
         }
-        let str_len = bytes_to_length(&data[1..1 + len_of_len]);
-        if data.len() < 1 + len_of_len + str_len {
-            return Err("Invalid RLP: insufficient data");
+        (1 + len_of_len, bytes_to_length(&data[1..1 + len_of_len]))
+    };
+    
+    let end = payload_start
+        .checked_add(payload_len)
+        .ok_or("Invalid RLP: length overflow")?;
+    if data.len() < end {
+        return Err("Invalid RLP: insufficient data");
+    }
+    
+    if prefix < 0xc0 {
+        return Ok((RlpValue::Bytes(data[payload_start..end].to_vec()), end));
+    }
+    
+    if depth == MAX_RLP_DEPTH {
+        return Err("Invalid RLP: lists nested too deeply");
+    }
+    let mut items = Vec::new();
+    let mut pos = payload_start;
+    while pos < end {
+        let (item, item_len) = decode_item(&data[pos..end], depth + 1)?;
+        items.push(item);
+        pos += item_len;
+    }
+    
+    Ok((RlpValue::List(items), end))
+}
+
+/// Format RLP data as an indented, human-readable tree (for debugging)
+/// 
+/// Byte strings are shown as `bytes(0x..)`, single bytes as `byte(0x..)` and lists as
+/// `list[ .. ]`. Two-item lists with a valid compact-encoded path are annotated with their
+/// leaf/extension interpretation, and 17-item lists are annotated as branches.
+#[cfg(feature = "std")]
+pub fn rlp_pretty_print(data: &[u8], indent: usize) -> String {
+    let mut out = String::new();
+    match decode_recursive(data) {
+        Ok(value) => pretty_print_value(&value, indent, &mut out),
+        Err(_) => {
+            out.push_str(&"  ".repeat(indent));
+            out.push_str(&format!("invalid(0x{})", hex_string(data)));
         }
-        Ok(data[1 + len_of_len..1 + len_of_len + str_len].to_vec())
-    } else {
-        Err("Not a byte string (it's a list)")
     }
+    out
+}
+
+#[cfg(feature = "std")]
+fn pretty_print_value(value: &RlpValue, indent: usize, out: &mut String) {
+    let pad = "  ".repeat(indent);
+    match value {
+        RlpValue::Bytes(bytes) if bytes.len() == 1 && bytes[0] < 0x80 => {
+            out.push_str(&format!("{}byte(0x{:02x})", pad, bytes[0]));
+        }
+        RlpValue::Bytes(bytes) => {
+            out.push_str(&format!("{}bytes(0x{})", pad, hex_string(bytes)));
+        }
+        RlpValue::List(items) => {
+            out.push_str(&pad);
+            out.push_str("list[");
+            if let Some(annotation) = node_annotation(items) {
+                out.push_str("  // ");
+                out.push_str(&annotation);
+            }
+            out.push('\n');
+            for item in items {
+                pretty_print_value(item, indent + 1, out);
+                out.push('\n');
+            }
+            out.push_str(&pad);
+            out.push(']');
+        }
+    }
+}
+
+/// Describe a decoded list as a trie node, if it looks like one
+#[cfg(feature = "std")]
+fn node_annotation(items: &[RlpValue]) -> Option<String> {
+    match items {
+        [RlpValue::Bytes(path), second] => {
+            let first = *path.first()?;
+            let flag = first >> 4;
+            // Valid compact prefixes are 0-3, and even-length paths pad the low nibble with 0
+            if flag > 3 || (flag & 0x1 == 0 && first & 0x0F != 0) {
+                return None;
+            }
+            let (nibbles, is_leaf) = crate::path::decode_path(path);
+            let path_hex: String = nibbles.iter().map(|n| format!("{:x}", n)).collect();
+            match (is_leaf, second) {
+                (true, RlpValue::Bytes(v)) => {
+                    Some(format!("leaf path={} value=0x{}", path_hex, hex_string(v)))
+                }
+                (false, RlpValue::Bytes(child)) => {
+                    Some(format!("extension path={} child=0x{}", path_hex, hex_string(child)))
+                }
+                (false, RlpValue::List(_)) => Some(format!("extension path={} child=inline", path_hex)),
+                _ => None,
+            }
+        }
+        _ if items.len() == 17 => Some("branch".to_string()),
+        _ => None,
+    }
+}
+
+/// Lowercase hex encoding without prefix
+#[cfg(feature = "std")]
+pub(crate) fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
 }
 
 /// Convert big-endian bytes to length
@@ -188,6 +548,7 @@ fn bytes_to_length(bytes: &[u8]) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::from_hex;
 
     #[test]
     fn test_encode_single_byte() {
@@ -210,6 +571,58 @@ mod tests {
         assert_eq!(encoded, vec![0x80]);
     }
 
+    #[test]
+    fn test_encode_u64() {
+        assert_eq!(encode_u64(0), vec![0x80]);
+        assert_eq!(encode_u64(1), vec![0x01]);
+        assert_eq!(encode_u64(0x7f), vec![0x7f]);
+        assert_eq!(encode_u64(0x80), vec![0x81, 0x80]);
+        assert_eq!(encode_u64(1024), vec![0x82, 0x04, 0x00]);
+    }
+
+    #[test]
+    fn test_rlp_integer() {
+        assert_eq!(rlp_encode_integer(0), [0x80]);
+        assert_eq!(rlp_encode_integer(1), [0x01]);
+        assert_eq!(rlp_encode_integer(127), [0x7f]);
+        assert_eq!(rlp_encode_integer(128), [0x81, 0x80]);
+        assert_eq!(rlp_encode_integer(u64::MAX), [&[0x88][..], &[0xff; 8]].concat());
+        assert_eq!(rlp_encode_bigint(&[0, 0, 0x04, 0x00]), [0x82, 0x04, 0x00]);
+        assert_eq!(rlp_encode_bigint(&[]), [0x80]);
+        
+        assert_eq!(rlp_decode_integer(&[0x80]), Ok(0));
+        assert_eq!(rlp_decode_integer(&[0x81, 0x80]), Ok(128));
+        assert_eq!(rlp_decode_integer(&[0x00]), Err("Non-canonical integer: leading zero"));
+        assert_eq!(rlp_decode_integer(&[0x82, 0x00, 0x01]), Err("Non-canonical integer: leading zero"));
+        assert_eq!(rlp_decode_integer(&[&[0x89][..], &[0x01; 9]].concat()), Err("Integer overflows u64"));
+        assert_eq!(rlp_decode_integer(&[0x01, 0x02]), Err("Invalid RLP: trailing bytes"));
+        assert!(rlp_decode_integer(&[0xc0]).is_err());
+    }
+    
+    #[test]
+    fn test_encode_u256() {
+        let mut value = [0u8; 32];
+        assert_eq!(encode_u256(&value), vec![0x80]);
+        value[31] = 0x7f;
+        assert_eq!(encode_u256(&value), vec![0x7f]);
+        value[30] = 0x04;
+        assert_eq!(encode_u256(&value), vec![0x82, 0x04, 0x7f]);
+        assert_eq!(encode_u256(&[0xff; 32])[0], 0xa0);
+    }
+
+    #[test]
+    fn test_decode_storage_word() {
+        let mut value = [0u8; 32];
+        value[30] = 0x04;
+        value[31] = 0x7f;
+        assert_eq!(decode_storage_word(&encode_u256(&value)), Some(value));
+        assert_eq!(decode_storage_word(&encode_u256(&[0xff; 32])), Some([0xff; 32]));
+        // An absent slot reads as zero
+        assert_eq!(decode_storage_word(&[]), Some([0u8; 32]));
+        assert_eq!(decode_storage_word(&encode_bytes(&[0xff; 33])), None);
+        assert_eq!(decode_storage_word(&encode_list(&[])), None);
+    }
+
     #[test]
     fn test_encode_list() {
         let item1 = encode_bytes(b"cat");
@@ -219,6 +632,272 @@ mod tests {
         assert_eq!(encoded[0], 0xc8);
     }
 
+    #[test]
+    fn test_decode_bytes_single_byte_edge_cases() {
+        assert_eq!(decode_bytes(&[0x00]), Ok(vec![0x00]));
+        assert_eq!(decode_bytes(&[0x01]), Ok(vec![0x01]));
+        assert_eq!(decode_bytes(&[0x7f]), Ok(vec![0x7f]));
+        // 0x80 is the empty string (and integer zero), not the byte 0x80
+        assert_eq!(decode_bytes(&[0x80]), Ok(vec![]));
+        assert_eq!(encode_bytes(&[]), RLP_EMPTY);
+        assert!(is_rlp_null(&encode_bytes(&[])));
+        assert!(!is_rlp_null(&encode_bytes(&[0x80])));
+        assert!(!is_rlp_null(&[]));
+        assert_eq!(decode_bytes(&[0x81, 0x80]), Ok(vec![0x80]));
+        
+        // Only the first item is decoded, as for the other string forms
+        assert_eq!(decode_bytes(&[0x42, 0x43]), Ok(vec![0x42]));
+        assert_eq!(decode_bytes(&[0x81, 0x90, 0x43]), Ok(vec![0x90]));
+        
+        for byte in 0..=0xffu8 {
+            assert_eq!(decode_bytes(&encode_bytes(&[byte])), Ok(vec![byte]));
+        }
+        assert_eq!(encode_u64(0), vec![0x80]);
+        assert_eq!(decode_bytes(&encode_u64(0)), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_decode_recursive_nested_list() {
+        // [["cat", "dog"], ["horse"]]
+        let inner1 = encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")]);
+        let inner2 = encode_list(&[encode_bytes(b"horse")]);
+        let encoded = encode_list(&[inner1, inner2]);
+        
+        let decoded = decode_recursive(&encoded).unwrap();
+        assert_eq!(
+            decoded,
+            RlpValue::List(vec![
+                RlpValue::List(vec![
+                    RlpValue::Bytes(b"cat".to_vec()),
+                    RlpValue::Bytes(b"dog".to_vec()),
+                ]),
+                RlpValue::List(vec![RlpValue::Bytes(b"horse".to_vec())]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_decode_recursive_transaction() {
+        // EIP-155 example signing payload:
+        // [nonce, gasPrice, gasLimit, to, value, data, chainId, 0, 0]
+        let tx = from_hex(
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080",
+        );
+        
+        let fields = decode_recursive(&tx).unwrap().into_list().unwrap();
+        assert_eq!(fields.len(), 9);
+        
+        let fields: Vec<Vec<u8>> = fields.into_iter().map(|f| f.into_bytes().unwrap()).collect();
+        assert_eq!(fields[0], vec![0x09]);
+        assert_eq!(fields[1], from_hex("04a817c800"));
+        assert_eq!(fields[2], from_hex("5208"));
+        assert_eq!(fields[3], vec![0x35; 20]);
+        assert_eq!(fields[4], from_hex("0de0b6b3a7640000"));
+        assert!(fields[5].is_empty());
+        assert_eq!(fields[6], vec![0x01]);
+    }
+
+    #[test]
+    fn test_decode_recursive_roundtrip() {
+        let long_value = vec![0xaa; 60];
+        let nested = encode_list(&[encode_bytes(b"x"), encode_bytes(&long_value)]);
+        let encoded = encode_list(&[encode_bytes(b""), nested, encode_bytes(&[0x7f])]);
+        
+        let decoded = decode_recursive(&encoded).unwrap();
+        assert_eq!(
+            decoded,
+            RlpValue::List(vec![
+                RlpValue::Bytes(vec![]),
+                RlpValue::List(vec![
+                    RlpValue::Bytes(b"x".to_vec()),
+                    RlpValue::Bytes(long_value),
+                ]),
+                RlpValue::Bytes(vec![0x7f]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_decode_recursive_invalid() {
+        assert!(decode_recursive(&[]).is_err());
+        // Truncated short string
+        assert!(decode_recursive(&[0x83, b'd', b'o']).is_err());
+        // Trailing bytes after a complete item
+        assert!(decode_recursive(&[0x01, 0x02]).is_err());
+        assert!(RlpValue::Bytes(vec![]).into_list().is_err());
+        assert!(RlpValue::List(vec![]).into_bytes().is_err());
+    }
+
+    #[test]
+    fn test_decode_recursive_depth_limit() {
+        // `depth` lists, each holding the next, down to an empty list
+        fn nested(depth: usize) -> Vec<u8> {
+            (1..depth).fold(encode_list(&[]), |inner, _| encode_list(&[inner]))
+        }
+        assert!(decode_recursive(&nested(MAX_RLP_DEPTH)).is_ok());
+        assert_eq!(decode_recursive(&nested(MAX_RLP_DEPTH + 1)), Err("Invalid RLP: lists nested too deeply"));
+        // A few bytes per level would otherwise recurse until the stack overflows
+        assert_eq!(decode_recursive(&nested(10_000)), Err("Invalid RLP: lists nested too deeply"));
+        
+        assert_eq!(assert_canonical(&nested(MAX_RLP_DEPTH)), Ok(()));
+        assert_eq!(assert_canonical(&nested(10_000)), Err("Invalid RLP: lists nested too deeply"));
+    }
+
+    #[test]
+    fn test_encode_item_matches_flat_encoders() {
+        let item = RlpItem::List(vec![
+            RlpItem::Bytes(b"cat".to_vec()),
+            RlpItem::List(vec![RlpItem::Bytes(vec![0x7f]), RlpItem::Bytes(vec![0xaa; 60])]),
+        ]);
+        let nested = encode_list(&[encode_bytes(&[0x7f]), encode_bytes(&[0xaa; 60])]);
+        assert_eq!(encode_item(&item), encode_list(&[encode_bytes(b"cat"), nested]));
+        assert_eq!(encode_item(&RlpItem::Bytes(vec![])), vec![0x80]);
+        assert_eq!(encode_item(&RlpItem::List(vec![])), vec![0xc0]);
+    }
+
+    fn rlp_item_strategy() -> impl proptest::strategy::Strategy<Value = RlpItem> {
+        use proptest::prelude::*;
+        let leaf = proptest::collection::vec(any::<u8>(), 0..70).prop_map(RlpItem::Bytes);
+        leaf.prop_recursive(5, 64, 8, |inner| {
+            proptest::collection::vec(inner, 0..8).prop_map(RlpItem::List)
+        })
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_encode_item_roundtrip(item in rlp_item_strategy()) {
+            proptest::prop_assert_eq!(decode_recursive(&encode_item(&item)).unwrap(), item);
+        }
+        
+        #[test]
+        fn prop_rlp_integer_roundtrip(n in proptest::num::u64::ANY) {
+            proptest::prop_assert_eq!(rlp_decode_integer(&rlp_encode_integer(n)), Ok(n));
+        }
+        
+        #[test]
+        fn prop_rlp_size_matches_encoding(
+            items in proptest::collection::vec(proptest::collection::vec(proptest::num::u8::ANY, 0..300), 0..20),
+        ) {
+            let encoded: Vec<Vec<u8>> = items.iter().map(|item| encode_bytes(item)).collect();
+            for (item, encoding) in items.iter().zip(&encoded) {
+                proptest::prop_assert_eq!(rlp_size_of_bytes(item), encoding.len());
+            }
+            let payload_len = encoded.iter().map(Vec::len).sum();
+            let list = encode_list(&encoded);
+            proptest::prop_assert_eq!(rlp_size_of_list(payload_len), list.len());
+            proptest::prop_assert_eq!(encode_list_len(&encoded), list.len());
+            
+            let mut buf = vec![0xff];
+            encode_list_into(&encoded, &mut buf);
+            proptest::prop_assert_eq!(&buf[1..], &list[..]);
+        }
+    }
+    
+    #[test]
+    fn test_encode_list_into_reuses_capacity() {
+        let items: [&[u8]; 3] = [&encode_bytes(b"dog"), &[0x05], &encode_bytes(&[0xaa; 60])];
+        let len = encode_list_len(&items);
+        assert_eq!(len, 1 + 1 + (4 + 1 + 62));
+        
+        let mut buf = Vec::with_capacity(2 * len);
+        let ptr = buf.as_ptr();
+        encode_list_into(&items, &mut buf);
+        encode_list_into(&items, &mut buf);
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(buf.len(), 2 * len);
+        assert_eq!(&buf[..len], &buf[len..]);
+        assert_eq!(decode_list(&buf[..len]).unwrap().len(), 3);
+    }
+    
+    #[test]
+    fn test_rlp_size_boundaries() {
+        assert_eq!(rlp_size_of_bytes(&[0x7f]), 1);
+        assert_eq!(rlp_size_of_bytes(&[0x80]), 2);
+        assert_eq!(rlp_size_of_bytes(&[]), 1);
+        assert_eq!(rlp_size_of_bytes(&[0; 55]), 56);
+        assert_eq!(rlp_size_of_bytes(&[0; 56]), 58);
+        assert_eq!(rlp_size_of_bytes(&[0; 256]), 259);
+        assert_eq!(rlp_size_of_list(0), 1);
+        assert_eq!(rlp_size_of_list(55), 56);
+        assert_eq!(rlp_size_of_list(56), 58);
+        assert_eq!(rlp_size_of_list(532), 535);
+        assert_eq!(rlp_size_of_list(70_000), 70_004);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rlp_pretty_print_leaf() {
+        let path = crate::path::encode_path(&[6, 4, 6, 15, 6, 7], true);
+        let leaf = encode_list(&[encode_bytes(&path), encode_bytes(b"puppy")]);
+        
+        let printed = rlp_pretty_print(&leaf, 0);
+        assert!(printed.starts_with("list["));
+        assert!(printed.contains("leaf path=646f67"));
+        assert!(printed.contains("bytes(0x7075707079)"));
+        assert!(printed.ends_with(']'));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_rlp_pretty_print_scalars() {
+        assert_eq!(rlp_pretty_print(&[0x42], 0), "byte(0x42)");
+        assert_eq!(rlp_pretty_print(&encode_bytes(b"dog"), 1), "  bytes(0x646f67)");
+        assert!(rlp_pretty_print(&[0x83, 0x01], 0).starts_with("invalid("));
+    }
+
+    #[test]
+    fn test_decode_at_offsets() {
+        let long = vec![0xab; 60];
+        let list = encode_list(&[encode_bytes(b"dog"), encode_bytes(&[0x05]), encode_bytes(&long), encode_list(&[])]);
+        // Payload starts after the two-byte long-list header
+        assert_eq!(list[0], 0xf8);
+        let mut offset = 2;
+        
+        assert_eq!(decode_item_len_at(&list, offset), Ok(4));
+        let (dog, next) = decode_bytes_at(&list, offset).unwrap();
+        assert_eq!((dog.as_slice(), next), (&b"dog"[..], offset + 4));
+        offset = next;
+        
+        assert_eq!(decode_bytes_at(&list, offset), Ok((vec![0x05], offset + 1)));
+        offset += 1;
+        
+        assert_eq!(decode_item_len_at(&list, offset), Ok(62));
+        assert_eq!(decode_bytes_at(&list, offset), Ok((long, offset + 62)));
+        offset += 62;
+        
+        assert_eq!(decode_item_len_at(&list, offset), Ok(1));
+        assert!(decode_bytes_at(&list, offset).is_err());
+        assert_eq!(offset + 1, list.len());
+        assert_eq!(decode_item_len_at(&list, 0), Ok(list.len()));
+        
+        // Past the end, and items running past the data
+        assert!(decode_item_len_at(&list, list.len()).is_err());
+        assert!(decode_bytes_at(&list[..list.len() - 2], offset - 62).is_err());
+        assert!(decode_item_len_at(&[0xb9, 0x01], 0).is_err());
+        // A long-string header cut off inside the list used to panic
+        assert!(decode_list(&[0xc2, 0xb9, 0x01]).is_err());
+    }
+    
+    #[test]
+    fn test_assert_canonical() {
+        let long = encode_bytes(&[0xab; 60]);
+        let node = encode_list(&[encode_bytes(b"dog"), encode_bytes(&[0x05]), long.clone()]);
+        assert_eq!(assert_canonical(&node), Ok(()));
+        assert_eq!(assert_canonical(&encode_bytes(&[])), Ok(()));
+        
+        // A single low byte with a string prefix
+        assert!(assert_canonical(&[0x81, 0x05]).is_err());
+        assert_eq!(assert_canonical(&[0x81, 0x80]), Ok(()));
+        // Long form for a 3-byte string, and a length with a leading zero
+        assert!(assert_canonical(&[0xb8, 0x03, b'd', b'o', b'g']).is_err());
+        assert!(assert_canonical(&[&[0xb9, 0x00, 60][..], &[0xab; 60]].concat()).is_err());
+        // Non-canonical item nested in a list, trailing bytes, and truncation
+        assert!(assert_canonical(&[0xc2, 0x81, 0x05]).is_err());
+        assert!(assert_canonical(&[&node[..], &[0x00]].concat()).is_err());
+        assert!(assert_canonical(&node[..node.len() - 1]).is_err());
+        assert!(assert_canonical(&[]).is_err());
+    }
+
     #[test]
     fn test_keccak256() {
         let data = b"hello";
@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use crate::builder::MPTBuilder;
+use crate::mpt::EMPTY_TRIE_ROOT;
+use crate::path::decode_path;
+use crate::rlp_encoding::{decode_bytes, decode_list, keccak256};
+use crate::types::{NodeDecodeError, H256};
+
+/// Error returned by `StateSync::add_node`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncError {
+    /// The node's hash is not pending: it was never requested or was already received
+    UnexpectedNode(H256),
+    /// The node hashes to a pending request but is not a valid trie node
+    InvalidNode(NodeDecodeError),
+}
+
+impl From<NodeDecodeError> for SyncError {
+    fn from(err: NodeDecodeError) -> Self {
+        SyncError::InvalidNode(err)
+    }
+}
+
+/// Downloads a trie node by node from an untrusted peer, verifying each against the root
+///
+/// Starting from the root, every received node must hash to a pending request, and its
+/// children become new requests, so the peer cannot inject anything outside the trie.
+/// Nodes may arrive in any order.
+#[derive(Clone, Debug)]
+pub struct StateSync {
+    root: H256,
+    pending: HashSet<H256>,
+    received: HashMap<H256, Vec<u8>>,
+}
+
+impl StateSync {
+    /// Start syncing the trie with the given root; the empty trie is complete immediately
+    pub fn new(root: H256) -> Self {
+        let pending = if root == EMPTY_TRIE_ROOT { HashSet::new() } else { HashSet::from([root]) };
+        Self { root, pending, received: HashMap::new() }
+    }
+
+    /// Verify and store a requested node, returning the child hashes to request next
+    ///
+    /// Children embedded in the node are stored along with it. Child hashes that are already
+    /// pending or received are not returned again.
+    pub fn add_node(&mut self, node_rlp: Vec<u8>) -> Result<Vec<H256>, SyncError> {
+        let hash = keccak256(&node_rlp);
+        if !self.pending.contains(&hash) {
+            return Err(SyncError::UnexpectedNode(hash));
+        }
+
+        let mut embedded = Vec::new();
+        let mut children = Vec::new();
+        collect_children(&node_rlp, &mut embedded, &mut children)?;
+
+        self.pending.remove(&hash);
+        self.received.insert(hash, node_rlp);
+        for node in embedded {
+            self.received.insert(keccak256(&node), node);
+        }
+
+        let mut requests = Vec::new();
+        for child in children {
+            if !self.received.contains_key(&child) && self.pending.insert(child) {
+                requests.push(child);
+            }
+        }
+        Ok(requests)
+    }
+
+    /// Whether every requested node has been received
+    pub fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Hashes still waiting for a node
+    pub fn pending(&self) -> impl Iterator<Item = &H256> {
+        self.pending.iter()
+    }
+
+    /// Build the synced trie, or `None` while nodes are still pending
+    pub fn build_trie(&self) -> Option<MPTBuilder> {
+        if !self.is_complete() {
+            return None;
+        }
+        if self.root == EMPTY_TRIE_ROOT {
+            return Some(MPTBuilder::new());
+        }
+        let nodes = self.received.iter().map(|(hash, rlp)| (*hash, rlp.clone())).collect();
+        Some(MPTBuilder::from_sorted_node_list(nodes, Some(self.root)))
+    }
+}
+
+/// Validate a node, collecting its embedded child nodes and the hashes of its other children
+fn collect_children(
+    rlp: &[u8],
+    embedded: &mut Vec<Vec<u8>>,
+    children: &mut Vec<H256>,
+) -> Result<(), NodeDecodeError> {
+    let items = decode_list(rlp)?;
+    let refs = match items.len() {
+        2 => {
+            let (_, is_leaf) = decode_path(&decode_bytes(&items[0])?);
+            if is_leaf {
+                decode_bytes(&items[1])?;
+                return Ok(());
+            }
+            &items[1..]
+        }
+        17 => {
+            decode_bytes(&items[16])?;
+            &items[..16]
+        }
+        count => return Err(NodeDecodeError::InvalidItemCount(count)),
+    };
+
+    for item in refs {
+        if item.first().is_some_and(|&prefix| prefix >= 0xc0) {
+            collect_children(item, embedded, children)?;
+            embedded.push(item.clone());
+            continue;
+        }
+        let bytes = decode_bytes(item)?;
+        match bytes.len() {
+            // An empty branch slot; an extension must have a child
+            0 if items.len() == 17 => {}
+            32 => children.push(bytes.try_into().unwrap()),
+            _ => return Err(NodeDecodeError::InvalidChild),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_trie() -> MPTBuilder {
+        let mut builder = MPTBuilder::new();
+        for i in 0..200u32 {
+            builder.insert(&keccak256(&i.to_be_bytes()), &i.to_be_bytes().repeat(10));
+        }
+        // Short entries give nodes embedded in their parents
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+        builder
+    }
+
+    #[test]
+    fn test_state_sync_downloads_trie() {
+        let source = source_trie();
+        let root = source.root().unwrap();
+        let peer: HashMap<H256, Vec<u8>> = source.to_sorted_node_list().into_iter().collect();
+
+        let mut sync = StateSync::new(root);
+        let mut queue = vec![root];
+        assert!(sync.build_trie().is_none());
+
+        // Serve requests newest first, so nodes arrive out of breadth-first order
+        while let Some(hash) = queue.pop() {
+            queue.extend(sync.add_node(peer[&hash].clone()).unwrap());
+        }
+
+        assert!(sync.is_complete());
+        let synced = sync.build_trie().unwrap();
+        assert_eq!(synced.root(), Some(root));
+        assert_eq!(synced.flatten(), source.flatten());
+        assert_eq!(synced.get(b"doge"), Some(b"coin".to_vec()));
+    }
+
+    #[test]
+    fn test_state_sync_rejects_unrequested_nodes() {
+        let source = source_trie();
+        let root = source.root().unwrap();
+        let root_rlp = source.get_proof(b"dog").unwrap().remove(0);
+
+        let mut sync = StateSync::new(root);
+        let proof = source.get_proof(b"dog").unwrap();
+        let child = proof[1].clone();
+        assert_eq!(sync.add_node(child.clone()), Err(SyncError::UnexpectedNode(keccak256(&child))));
+
+        let requests = sync.add_node(root_rlp.clone()).unwrap();
+        assert!(requests.contains(&keccak256(&child)));
+        assert_eq!(sync.add_node(root_rlp.clone()), Err(SyncError::UnexpectedNode(root)));
+        assert!(!sync.is_complete());
+        assert_eq!(sync.pending().count(), requests.len());
+
+        // A node with the requested hash must still be a well-formed trie node
+        let garbage = vec![0xc3, 0x01, 0x02, 0x03];
+        let mut sync = StateSync::new(keccak256(&garbage));
+        assert_eq!(sync.add_node(garbage), Err(SyncError::InvalidNode(NodeDecodeError::InvalidItemCount(3))));
+        assert!(!sync.is_complete());
+    }
+
+    #[test]
+    fn test_state_sync_empty_trie() {
+        let sync = StateSync::new(EMPTY_TRIE_ROOT);
+        assert!(sync.is_complete());
+        assert_eq!(sync.build_trie().unwrap().root(), None);
+    }
+}
@@ -0,0 +1,123 @@
+use alloc::vec::Vec;
+use crate::mpt::verify_account_proof;
+use crate::types::{EthAccount, VerificationError, H256};
+
+#[cfg(feature = "std")]
+use crate::builder::MPTBuilder;
+#[cfg(feature = "std")]
+use crate::path::account_address_to_state_key;
+
+/// Builds an Ethereum world state trie (`keccak256(address) -> RLP(account)`)
+#[cfg(feature = "std")]
+pub struct WorldStateTrieBuilder {
+    builder: MPTBuilder,
+}
+
+#[cfg(feature = "std")]
+impl WorldStateTrieBuilder {
+    pub fn new() -> Self {
+        Self {
+            builder: MPTBuilder::new(),
+        }
+    }
+
+    /// Insert (or overwrite) an account and return the new state root
+    pub fn insert_account(&mut self, address: &[u8; 20], account: &EthAccount) -> H256 {
+        self.builder.insert(&account_address_to_state_key(address), &account.to_rlp())
+    }
+
+    /// Get the current state root
+    pub fn state_root(&self) -> Option<H256> {
+        self.builder.root()
+    }
+
+    /// Generate a proof for an account, in the same form as `eth_getProof`'s `accountProof`
+    pub fn get_account_proof(&self, address: &[u8; 20]) -> Option<Vec<Vec<u8>>> {
+        self.builder.get_proof(&account_address_to_state_key(address))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for WorldStateTrieBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Verify that `account` is stored at `address` under `state_root`
+///
+/// The same check as `verify_account_proof`, with the same errors.
+pub fn verify_account_inclusion(
+    state_root: &H256,
+    address: &[u8; 20],
+    account: &EthAccount,
+    proof: &[Vec<u8>],
+) -> Result<(), VerificationError> {
+    verify_account_proof(state_root, address, account, proof)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::rlp_encoding::keccak256;
+
+    fn account(nonce: u64, balance: u8) -> EthAccount {
+        let mut balance_bytes = [0u8; 32];
+        balance_bytes[31] = balance;
+        EthAccount {
+            nonce,
+            balance: balance_bytes,
+            storage_root: keccak256(&[0x80]),
+            code_hash: keccak256(&[]),
+        }
+    }
+
+    #[test]
+    fn test_three_account_state_trie() {
+        let accounts = [
+            ([0x11; 20], account(0, 100)),
+            ([0x22; 20], account(5, 0)),
+            ([0x33; 20], account(1, 42)),
+        ];
+
+        let mut builder = WorldStateTrieBuilder::new();
+        assert_eq!(builder.state_root(), None);
+        for (address, acc) in &accounts {
+            builder.insert_account(address, acc);
+        }
+        let root = builder.state_root().unwrap();
+
+        for (address, acc) in &accounts {
+            let proof = builder.get_account_proof(address).unwrap();
+            assert_eq!(verify_account_inclusion(&root, address, acc, &proof), Ok(()));
+        }
+
+        // Proofs do not vouch for another account's fields
+        let proof = builder.get_account_proof(&accounts[0].0).unwrap();
+        assert_eq!(
+            verify_account_inclusion(&root, &accounts[0].0, &accounts[1].1, &proof),
+            Err(VerificationError::ValueMismatch)
+        );
+        // Nor for another address
+        assert_eq!(
+            verify_account_inclusion(&root, &accounts[1].0, &accounts[0].1, &proof),
+            Err(VerificationError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn test_insert_account_overwrites() {
+        let address = [0xaa; 20];
+        let mut builder = WorldStateTrieBuilder::new();
+        let first = builder.insert_account(&address, &account(0, 1));
+        let second = builder.insert_account(&address, &account(1, 1));
+        assert_ne!(first, second);
+
+        let proof = builder.get_account_proof(&address).unwrap();
+        assert_eq!(verify_account_inclusion(&second, &address, &account(1, 1), &proof), Ok(()));
+        assert_eq!(
+            verify_account_inclusion(&second, &address, &account(0, 1), &proof),
+            Err(VerificationError::ValueMismatch)
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Helpers shared by the unit tests
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use alloc::string::String;
+use crate::types::H256;
+
+/// Decode a hex test vector, with or without a `0x` prefix
+pub(crate) fn from_hex(s: &str) -> Vec<u8> {
+    crate::eip1186::decode_hex(s).unwrap()
+}
+
+/// Decode a hex test vector of exactly 32 bytes
+pub(crate) fn h256_from_hex(s: &str) -> H256 {
+    from_hex(s).try_into().unwrap()
+}
+
+/// `value` as a big-endian u256 word, as stored in account and storage fields
+#[cfg(feature = "std")]
+pub(crate) fn word(value: u64) -> H256 {
+    let mut out = [0u8; 32];
+    out[24..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+/// `bytes` as a `0x`-prefixed hex string, as returned by JSON-RPC
+#[cfg(feature = "std")]
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    alloc::format!("0x{}", crate::rlp_encoding::hex_string(bytes))
+}
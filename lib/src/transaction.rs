@@ -0,0 +1,119 @@
+use alloc::vec::Vec;
+use crate::mpt::{verify_proof_with_config, ProofVerifierConfig};
+use crate::rlp_encoding::encode_u64;
+use crate::types::{VerificationError, H256};
+
+#[cfg(feature = "std")]
+use crate::builder::MPTBuilder;
+
+/// Builds an Ethereum transaction trie (`RLP(txIndex) -> tx`)
+///
+/// Transactions are inserted as their EIP-2718 encoding, as they appear in the block body.
+#[cfg(feature = "std")]
+pub struct TransactionTrieBuilder {
+    builder: MPTBuilder,
+}
+
+#[cfg(feature = "std")]
+impl TransactionTrieBuilder {
+    pub fn new() -> Self {
+        Self {
+            builder: MPTBuilder::new(),
+        }
+    }
+
+    /// Insert a transaction at its index and return the new root
+    pub fn insert_tx(&mut self, tx_index: u64, tx_rlp: &[u8]) -> H256 {
+        self.builder.insert(&encode_u64(tx_index), tx_rlp)
+    }
+
+    /// Get the current transactions root (the empty trie root if nothing was inserted)
+    pub fn root(&self) -> H256 {
+        self.builder.root_or_empty()
+    }
+
+    /// Generate an inclusion proof for the transaction at `tx_index`
+    pub fn get_proof(&self, tx_index: u64) -> Option<Vec<Vec<u8>>> {
+        self.builder.get_proof(&encode_u64(tx_index))
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for TransactionTrieBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compute the `transactionsRoot` of a block from its transactions, in order
+#[cfg(feature = "std")]
+pub fn transactions_root(txs: &[Vec<u8>]) -> H256 {
+    let mut builder = TransactionTrieBuilder::new();
+    for (i, tx) in txs.iter().enumerate() {
+        builder.insert_tx(i as u64, tx);
+    }
+    builder.root()
+}
+
+/// Verify that `tx_rlp` is the transaction at `tx_index` under `tx_root`
+/// 
+/// Returns `VerificationError::ValueMismatch` if the proof is valid but shows another
+/// transaction at the index, and the proof's error if it is invalid.
+pub fn verify_tx_inclusion(
+    tx_root: &H256,
+    tx_index: u64,
+    tx_rlp: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<(), VerificationError> {
+    verify_proof_with_config(tx_root, &encode_u64(tx_index), tx_rlp, proof, &ProofVerifierConfig::default())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::test_utils::from_hex;
+
+    fn load_fixture() -> (Vec<Vec<u8>>, Vec<u8>) {
+        let fixture: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/fixtures/transactions.json")).unwrap();
+        let txs = fixture["transactions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tx| from_hex(tx.as_str().unwrap()))
+            .collect();
+        (txs, from_hex(fixture["transactionsRoot"].as_str().unwrap()))
+    }
+
+    #[test]
+    fn test_transactions_root_fixture() {
+        let (txs, expected) = load_fixture();
+        assert_eq!(txs.len(), 94);
+        assert_eq!(transactions_root(&txs).to_vec(), expected);
+    }
+
+    #[test]
+    fn test_verify_tx_inclusion() {
+        let (txs, _) = load_fixture();
+        let mut builder = TransactionTrieBuilder::new();
+        for (i, tx) in txs.iter().enumerate() {
+            builder.insert_tx(i as u64, tx);
+        }
+        let root = builder.root();
+
+        for i in [0u64, 1, 15, 16, 93] {
+            let proof = builder.get_proof(i).unwrap();
+            assert_eq!(verify_tx_inclusion(&root, i, &txs[i as usize], &proof), Ok(()));
+            // The same proof must not vouch for a different index or transaction
+            assert_eq!(
+                verify_tx_inclusion(&root, i + 1, &txs[i as usize], &proof),
+                Err(VerificationError::InvalidProof)
+            );
+            let other = &txs[(i as usize + 1) % txs.len()];
+            assert_eq!(
+                verify_tx_inclusion(&root, i, other, &proof),
+                Err(VerificationError::ValueMismatch)
+            );
+        }
+    }
+}
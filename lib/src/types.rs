@@ -1,31 +1,418 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
 use serde::{Deserialize, Serialize};
+use crate::rlp_encoding::{
+    encode_bytes, encode_hash_item, encode_list, encode_list_into, decode_list, decode_bytes, is_rlp_null, keccak256,
+    rlp_decode_integer, rlp_encode_bigint, rlp_encode_integer, RLP_EMPTY,
+};
+use crate::path::{encode_path, decode_path};
+use crate::mpt::{proof_size_bytes, verify_non_inclusion_proof, verify_proof, verify_state_transition};
 
 /// 32-byte hash type
 pub type H256 = [u8; 32];
 
+/// Shows an `H256` as its first 4 and last 2 bytes, e.g. `0xdeadbeef…cafe`
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct AbbrevHash(pub H256);
+
+impl fmt::Display for AbbrevHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x")?;
+        for byte in &self.0[..4] {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, "\u{2026}{:02x}{:02x}", self.0[30], self.0[31])
+    }
+}
+
+impl fmt::Debug for AbbrevHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Formats an `H256` in full with `{:x}` (`{:#x}` adds the `0x` prefix)
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct HexDisplay(pub H256);
+
+impl fmt::LowerHex for HexDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "0x")?;
+        }
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Serde helpers that encode an `H256` as a `"0x..."` hex string
+///
+/// Use with `#[serde(with = "h256_hex")]`. Only human-readable formats (e.g. JSON) get the
+/// string form; binary formats such as bincode keep the plain 32-byte array, so zkVM
+/// inputs are unaffected.
+#[cfg(feature = "serde-hex")]
+pub mod h256_hex {
+    use super::H256;
+    use alloc::string::String;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    pub fn serialize<S: Serializer>(value: &H256, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serde::Serialize::serialize(value, serializer);
+        }
+        let mut out = String::with_capacity(66);
+        out.push_str("0x");
+        for byte in value {
+            out.push(HEX[(byte >> 4) as usize] as char);
+            out.push(HEX[(byte & 0x0f) as usize] as char);
+        }
+        serializer.serialize_str(&out)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<H256, D::Error> {
+        if !deserializer.is_human_readable() {
+            return H256::deserialize(deserializer);
+        }
+        let s = String::deserialize(deserializer)?;
+        let digits = s.strip_prefix("0x").unwrap_or(&s).as_bytes();
+        if digits.len() != 64 {
+            return Err(D::Error::custom("expected 32 hex-encoded bytes"));
+        }
+        let mut out = [0u8; 32];
+        for (byte, pair) in out.iter_mut().zip(digits.chunks_exact(2)) {
+            let hi = (pair[0] as char).to_digit(16);
+            let lo = (pair[1] as char).to_digit(16);
+            match (hi, lo) {
+                (Some(hi), Some(lo)) => *byte = (hi << 4 | lo) as u8,
+                _ => return Err(D::Error::custom("invalid hex digit")),
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// Error decoding a trie node from RLP
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeDecodeError {
+    /// The data is not well-formed RLP
+    InvalidRlp(&'static str),
+    /// The list has neither 2 (leaf/extension) nor 17 (branch) items
+    InvalidItemCount(usize),
+    /// A child reference is neither empty nor a 32-byte hash
+    InvalidChild,
+    /// The node decoded fine but is not of the requested kind
+    UnexpectedKind,
+    /// A field does not fit its type (e.g. an account nonce longer than 8 bytes)
+    InvalidField(&'static str),
+}
+
+impl From<&'static str> for NodeDecodeError {
+    fn from(err: &'static str) -> Self {
+        NodeDecodeError::InvalidRlp(err)
+    }
+}
+
+/// Leaf node: the remaining key nibbles and the value
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeafNode {
+    pub path: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+/// Extension node: shared key nibbles and the hash of the single child
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtensionNode {
+    pub path: Vec<u8>,
+    pub child: H256,
+}
+
+/// Branch node: one optional child per nibble, plus the value of a key ending here
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchNode {
+    pub children: [Option<H256>; 16],
+    pub value: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for ExtensionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionNode")
+            .field("path", &self.path)
+            .field("child", &AbbrevHash(self.child))
+            .finish()
+    }
+}
+
+impl fmt::Debug for BranchNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BranchNode")
+            .field("children", &self.children.map(|child| child.map(AbbrevHash)))
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
 /// MPT Node types
+// Branch nodes dominate real proofs, so boxing them would add an allocation per node
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Node {
     /// Empty node
     Empty,
     
-    /// Leaf node: [encoded_path, value]
-    Leaf(Vec<u8>, Vec<u8>),
+    /// Leaf node
+    Leaf(LeafNode),
+    
+    /// Extension node
+    Extension(ExtensionNode),
+    
+    /// Branch node
+    Branch(BranchNode),
+}
+
+impl LeafNode {
+    /// RLP-encode as `[compact_path, value]`
+    pub fn to_rlp(&self) -> Vec<u8> {
+        encode_list(&[encode_bytes(&encode_path(&self.path, true)), encode_bytes(&self.value)])
+    }
+    
+    /// Decode from RLP, failing if the data is not a leaf
+    pub fn from_rlp(data: &[u8]) -> Result<Self, NodeDecodeError> {
+        match Node::from_rlp(data)? {
+            Node::Leaf(leaf) => Ok(leaf),
+            _ => Err(NodeDecodeError::UnexpectedKind),
+        }
+    }
+    
+    /// Keccak256 hash of the RLP encoding
+    pub fn hash(&self) -> H256 {
+        keccak256(&self.to_rlp())
+    }
+}
+
+impl ExtensionNode {
+    /// RLP-encode as `[compact_path, child_hash]`
+    pub fn to_rlp(&self) -> Vec<u8> {
+        encode_list(&[encode_bytes(&encode_path(&self.path, false)), encode_bytes(&self.child)])
+    }
     
-    /// Extension node: [encoded_path, next_hash]
-    Extension(Vec<u8>, H256),
+    /// Decode from RLP, failing if the data is not an extension
+    pub fn from_rlp(data: &[u8]) -> Result<Self, NodeDecodeError> {
+        match Node::from_rlp(data)? {
+            Node::Extension(extension) => Ok(extension),
+            _ => Err(NodeDecodeError::UnexpectedKind),
+        }
+    }
     
-    /// Branch node: 16 children + optional value
-    Branch([Option<H256>; 16], Option<Vec<u8>>),
+    /// Keccak256 hash of the RLP encoding
+    pub fn hash(&self) -> H256 {
+        keccak256(&self.to_rlp())
+    }
+}
+
+impl BranchNode {
+    /// RLP-encode as a 17-item list of child hashes (empty if absent) and the value
+    pub fn to_rlp(&self) -> Vec<u8> {
+        // Child hashes are encoded on the stack, so only the value and output allocate
+        let hashes = self.children.map(|child| encode_hash_item(&child.unwrap_or_default()));
+        let value = self.value.as_deref().map(encode_bytes);
+        let items: [&[u8]; 17] = core::array::from_fn(|i| match self.children.get(i) {
+            Some(Some(_)) => &hashes[i][..],
+            Some(None) => RLP_EMPTY,
+            None => value.as_deref().unwrap_or(RLP_EMPTY),
+        });
+        let mut encoded = Vec::new();
+        encode_list_into(&items, &mut encoded);
+        encoded
+    }
+    
+    /// Decode from RLP, failing if the data is not a branch
+    pub fn from_rlp(data: &[u8]) -> Result<Self, NodeDecodeError> {
+        match Node::from_rlp(data)? {
+            Node::Branch(branch) => Ok(branch),
+            _ => Err(NodeDecodeError::UnexpectedKind),
+        }
+    }
+    
+    /// Keccak256 hash of the RLP encoding
+    pub fn hash(&self) -> H256 {
+        keccak256(&self.to_rlp())
+    }
+}
+
+impl Node {
+    /// RLP-encode the node, referencing children by hash (`Empty` encodes as `0x80`)
+    pub fn to_rlp(&self) -> Vec<u8> {
+        match self {
+            Node::Empty => encode_bytes(&[]),
+            Node::Leaf(leaf) => leaf.to_rlp(),
+            Node::Extension(extension) => extension.to_rlp(),
+            Node::Branch(branch) => branch.to_rlp(),
+        }
+    }
+    
+    /// Decode any node kind from RLP
+    /// 
+    /// Children must be referenced by 32-byte hash; embedded (inline) children are rejected.
+    pub fn from_rlp(data: &[u8]) -> Result<Self, NodeDecodeError> {
+        if is_rlp_null(data) {
+            return Ok(Node::Empty);
+        }
+        
+        let items = decode_list(data)?;
+        match items.len() {
+            2 => {
+                let (path, is_leaf) = decode_path(&decode_bytes(&items[0])?);
+                let second = decode_bytes(&items[1])?;
+                if is_leaf {
+                    return Ok(Node::Leaf(LeafNode { path, value: second }));
+                }
+                let child = second.try_into().map_err(|_| NodeDecodeError::InvalidChild)?;
+                Ok(Node::Extension(ExtensionNode { path, child }))
+            }
+            17 => {
+                let mut children: [Option<H256>; 16] = Default::default();
+                for (child, item) in children.iter_mut().zip(&items) {
+                    let bytes = decode_bytes(item)?;
+                    if !bytes.is_empty() {
+                        *child = Some(bytes.try_into().map_err(|_| NodeDecodeError::InvalidChild)?);
+                    }
+                }
+                let value = decode_bytes(&items[16])?;
+                let value = if value.is_empty() { None } else { Some(value) };
+                Ok(Node::Branch(BranchNode { children, value }))
+            }
+            n => Err(NodeDecodeError::InvalidItemCount(n)),
+        }
+    }
+    
+    /// Keccak256 hash of the RLP encoding
+    pub fn hash(&self) -> H256 {
+        keccak256(&self.to_rlp())
+    }
+    
+    /// Hashes of the node's children, in nibble order for a branch
+    pub fn child_hashes(&self) -> Vec<H256> {
+        match self {
+            Node::Empty | Node::Leaf(_) => Vec::new(),
+            Node::Extension(extension) => vec![extension.child],
+            Node::Branch(branch) => branch.children.iter().flatten().copied().collect(),
+        }
+    }
+    
+    /// Whether a key ends at this node: always for a leaf, and for a branch with a value
+    pub fn has_value(&self) -> bool {
+        self.value().is_some()
+    }
+    
+    /// The value stored at this node, for a leaf or a branch with a value
+    pub fn value(&self) -> Option<&[u8]> {
+        match self {
+            Node::Leaf(leaf) => Some(&leaf.value),
+            Node::Branch(branch) => branch.value.as_deref(),
+            Node::Empty | Node::Extension(_) => None,
+        }
+    }
+}
+
+/// A `Node` whose `hash` is computed on first use and kept until the node is modified
+/// 
+/// Useful when a node may be changed several times, or discarded, before anyone needs
+/// its hash. Like `Node::hash`, every child is referenced by hash.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedNode {
+    node: Node,
+    cached_hash: core::cell::OnceCell<H256>,
+}
+
+impl CachedNode {
+    pub fn new(node: Node) -> Self {
+        Self { node, cached_hash: core::cell::OnceCell::new() }
+    }
+    
+    /// `Node::hash`, computed once
+    pub fn hash(&self) -> H256 {
+        *self.cached_hash.get_or_init(|| self.node.hash())
+    }
+    
+    /// Whether the hash has been computed since the last change
+    pub fn is_hashed(&self) -> bool {
+        self.cached_hash.get().is_some()
+    }
+    
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+    
+    /// Mutable access to the node, which drops the cached hash
+    pub fn node_mut(&mut self) -> &mut Node {
+        self.cached_hash.take();
+        &mut self.node
+    }
+    
+    pub fn into_node(self) -> Node {
+        self.node
+    }
+}
+
+#[cfg(feature = "alloy-rlp")]
+impl alloy_rlp::Encodable for Node {
+    fn encode(&self, out: &mut dyn alloy_rlp::BufMut) {
+        out.put_slice(&self.to_rlp());
+    }
+}
+
+/// Decodes one node as `Node::from_rlp` does, so embedded children are rejected
+#[cfg(feature = "alloy-rlp")]
+impl alloy_rlp::Decodable for Node {
+    fn decode(buf: &mut &[u8]) -> alloy_rlp::Result<Self> {
+        let len = crate::rlp_encoding::decode_item_len_at(buf, 0).map_err(|_| alloy_rlp::Error::InputTooShort)?;
+        let node = Node::from_rlp(&buf[..len]).map_err(|err| match err {
+            NodeDecodeError::InvalidRlp(msg) => alloy_rlp::Error::Custom(msg),
+            _ => alloy_rlp::Error::Custom("not a trie node"),
+        })?;
+        *buf = &buf[len..];
+        Ok(node)
+    }
+}
+
+/// Current proof input format; inputs with any other `version` are rejected
+pub const PROOF_FORMAT_VERSION: u8 = 1;
+
+/// The claim an `MPTProofInput` makes about its key
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProofType {
+    /// The key holds `value` under `root`; see `verify_proof`
+    #[default]
+    Inclusion,
+    /// The key is absent under `root` and `value` is empty; see `verify_non_inclusion_proof`
+    Exclusion,
+    /// Setting the key to `value` takes the trie from `root` to `new_root`; see
+    /// `verify_state_transition`
+    StateTransition {
+        #[cfg_attr(feature = "serde-hex", serde(with = "h256_hex"))]
+        new_root: H256,
+    },
 }
 
 /// Input for MPT proof verification
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MPTProofInput {
+    /// Format version, serialized first; see `PROOF_FORMAT_VERSION`
+    pub version: u8,
     pub key: Vec<u8>,
     pub value: Vec<u8>,
     pub proof: Vec<Vec<u8>>, // RLP-encoded nodes
+    #[cfg_attr(feature = "serde-hex", serde(with = "h256_hex"))]
     pub root: H256,
+    /// Serialized last, and `Inclusion` when missing from self-describing formats
+    #[serde(default)]
+    pub proof_type: ProofType,
 }
 
 /// Output from MPT proof verification
@@ -34,13 +421,20 @@ pub struct MPTVerificationResult {
     pub verified: bool,
     pub key: Vec<u8>,
     pub value: Vec<u8>,
+    #[cfg_attr(feature = "serde-hex", serde(with = "h256_hex"))]
     pub root: H256,
+    /// The claim that was verified, so a state transition commits its `new_root`
+    #[serde(default)]
+    pub proof_type: ProofType,
 }
 
 /// Batch proof input for multiple key-value pairs
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MPTBatchProofInput {
+    /// Format version, serialized first; see `PROOF_FORMAT_VERSION`
+    pub version: u8,
     pub proofs: Vec<MPTProofInput>,
+    #[cfg_attr(feature = "serde-hex", serde(with = "h256_hex"))]
     pub root: H256,
 }
 
@@ -49,6 +443,857 @@ pub struct MPTBatchProofInput {
 pub struct MPTBatchVerificationResult {
     pub all_verified: bool,
     pub individual_results: Vec<bool>,
+    #[cfg_attr(feature = "serde-hex", serde(with = "h256_hex"))]
     pub root: H256,
     pub count: usize,
 }
+
+/// Proofs of a key and of its neighbours in key order, from `MPTBuilder::get_sibling_proof`
+/// 
+/// A missing neighbour means the target is the first (or last) key of the trie.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SiblingProof {
+    pub target: MPTProofInput,
+    pub left_neighbor: Option<MPTProofInput>,
+    pub right_neighbor: Option<MPTProofInput>,
+}
+
+/// Proofs from several chains, each verified against its own root
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiChainProofInput {
+    pub chains: Vec<(String, MPTProofInput)>, // (chain name, proof)
+}
+
+/// Multi-chain verification result
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiChainVerificationResult {
+    pub all_verified: bool,
+    pub results: Vec<(String, MPTVerificationResult)>,
+}
+
+/// Outcome of `verify_truncated_proof`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProofResult {
+    /// The proof reaches the key; holds its value, empty if the key is absent
+    Complete(Vec<u8>),
+    /// The proof stops at the depth limit; holds the hash of the unverified node below
+    Partial(H256),
+}
+
+/// Error returned when verifying a proof input fails outright rather than with `false`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerificationError {
+    /// The input's `version` is not a format this crate understands
+    UnsupportedProofVersion(u8),
+    /// The account proof does not show `account` at `address` under `state_root`
+    InvalidAccountProof,
+    /// The storage proof is not valid for `slot` under the account's `storage_root`
+    InvalidStorageProof,
+    /// The proven slot value is not an RLP string of at most 32 bytes
+    InvalidStorageValue,
+    /// The proof is valid but the value it shows for the address is not an account
+    InvalidAccountRlp(NodeDecodeError),
+    /// The first proof node does not hash to the root
+    RootMismatch,
+    /// The proof does not lead to a value for the key: a node is malformed, a child is
+    /// missing or does not match its reference, or nodes follow the one holding the value
+    InvalidProof,
+    /// The proof is valid but shows a different value for the key
+    ValueMismatch,
+    /// The walk would visit more nodes than `max_proof_depth` allows
+    ProofTooDeep,
+    /// A child is embedded in its parent while `allow_inline_nodes` is off
+    InlineNodeNotAllowed,
+    /// A proof node is not canonical RLP while `strict_canonical_rlp` is on
+    NonCanonicalNode,
+}
+
+/// Reject any version other than `PROOF_FORMAT_VERSION`
+pub fn check_proof_version(version: u8) -> Result<(), VerificationError> {
+    if version == PROOF_FORMAT_VERSION {
+        Ok(())
+    } else {
+        Err(VerificationError::UnsupportedProofVersion(version))
+    }
+}
+
+/// A structural problem found by `MPTProofInput::validate_structure`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    /// `root` is all zeros
+    ZeroRoot,
+    /// `key` is empty
+    EmptyKey,
+    /// `proof` has no nodes
+    EmptyProof,
+    /// Proof node `index` is not an RLP list
+    InvalidNodeRlp(usize),
+    /// Proof node `index` has neither 2 nor 17 items
+    InvalidItemCount { index: usize, count: usize },
+    /// The first proof node does not hash to `root`
+    RootMismatch,
+    /// The last proof node is neither a leaf nor a branch holding a value
+    InvalidLastNode,
+}
+
+impl MPTProofInput {
+    /// Cheap structural checks to run before handing the input to the zkVM
+    ///
+    /// Checks shape only: the root, key and proof are non-empty, every node is a
+    /// 2- or 17-item list, the first node hashes to `root`, and for an inclusion proof
+    /// the last node is a leaf or a branch with a value. Passing does not mean the proof
+    /// verifies.
+    pub fn validate_structure(&self) -> Result<(), ValidationError> {
+        if self.root == [0u8; 32] {
+            return Err(ValidationError::ZeroRoot);
+        }
+        if self.key.is_empty() {
+            return Err(ValidationError::EmptyKey);
+        }
+        let last = self.proof.len().checked_sub(1).ok_or(ValidationError::EmptyProof)?;
+
+        for (index, node) in self.proof.iter().enumerate() {
+            // `decode_list` accepts a bare string as a one-item list
+            if node.first().is_none_or(|&prefix| prefix < 0xc0) {
+                return Err(ValidationError::InvalidNodeRlp(index));
+            }
+            let items = decode_list(node).map_err(|_| ValidationError::InvalidNodeRlp(index))?;
+            let count = items.len();
+            if count != 2 && count != 17 {
+                return Err(ValidationError::InvalidItemCount { index, count });
+            }
+            // Only an inclusion proof must end at the key's value
+            if index == last && self.proof_type == ProofType::Inclusion {
+                let has_value = match count {
+                    2 => decode_bytes(&items[0]).is_ok_and(|path| decode_path(&path).1),
+                    _ => decode_bytes(&items[16]).is_ok_and(|value| !value.is_empty()),
+                };
+                if !has_value {
+                    return Err(ValidationError::InvalidLastNode);
+                }
+            }
+        }
+
+        if keccak256(&self.proof[0]) != self.root {
+            return Err(ValidationError::RootMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verify the proof against `root` per its `proof_type`, after checking the format version
+    pub fn verify(&self) -> Result<bool, VerificationError> {
+        check_proof_version(self.version)?;
+        Ok(match self.proof_type {
+            ProofType::Inclusion => verify_proof(&self.root, &self.key, &self.value, &self.proof),
+            ProofType::Exclusion => {
+                self.value.is_empty() && verify_non_inclusion_proof(&self.root, &self.key, &self.proof)
+            }
+            ProofType::StateTransition { new_root } => {
+                verify_state_transition(&self.root, &new_root, &self.key, &self.value, &self.proof)
+            }
+        })
+    }
+
+    /// Raw size of the input: key, value, root and all proof nodes
+    pub fn size_bytes(&self) -> usize {
+        self.key.len() + self.value.len() + 32 + proof_size_bytes(&self.proof)
+    }
+
+    /// Size of `abi.encode(input)` for the Solidity struct
+    /// `(bytes key, bytes value, bytes[] proof, bytes32 root)`, excluding any selector
+    pub fn encoded_calldata_size(&self) -> usize {
+        fn dynamic_bytes(len: usize) -> usize {
+            32 + len.div_ceil(32) * 32
+        }
+
+        let proof: usize = self.proof.iter().map(|node| 32 + dynamic_bytes(node.len())).sum();
+        // Offset to the struct, then its four head words
+        32 + 4 * 32 + dynamic_bytes(self.key.len()) + dynamic_bytes(self.value.len()) + 32 + proof
+    }
+}
+
+impl MPTBatchProofInput {
+    /// Combined `size_bytes` of every proof in the batch
+    pub fn total_size_bytes(&self) -> usize {
+        self.proofs.iter().map(MPTProofInput::size_bytes).sum()
+    }
+
+    /// Verify every proof in the batch against the shared batch root
+    /// 
+    /// All entries must belong to the same trie: an entry whose own `root`
+    /// differs from `self.root` is reported as failed. An empty batch is
+    /// vacuously verified. Entries of an unsupported version, or all entries
+    /// if the batch's own version is unsupported, are reported as failed.
+    /// Each entry is checked per its own `proof_type`, so a batch can mix
+    /// inclusion, exclusion and state transition proofs.
+    pub fn verify_all(&self) -> MPTBatchVerificationResult {
+        let supported = check_proof_version(self.version).is_ok();
+        let individual_results: Vec<bool> = self
+            .proofs
+            .iter()
+            .map(|p| supported && p.root == self.root && p.verify() == Ok(true))
+            .collect();
+
+        MPTBatchVerificationResult {
+            all_verified: individual_results.iter().all(|&ok| ok),
+            individual_results,
+            root: self.root,
+            count: self.proofs.len(),
+        }
+    }
+
+    /// Distinct roots of the proofs in the batch, in order of first appearance
+    pub fn all_roots(&self) -> Vec<H256> {
+        let mut roots = Vec::new();
+        for proof in &self.proofs {
+            if !roots.contains(&proof.root) {
+                roots.push(proof.root);
+            }
+        }
+        roots
+    }
+
+    /// Group the proofs by their own `root`, one batch per root
+    /// 
+    /// Each batch keeps this batch's `version` and the order of its proofs, and has its
+    /// `root` set to the group's root, so `verify_all` checks every entry against it.
+    #[cfg(feature = "std")]
+    pub fn split_by_root(self) -> std::collections::HashMap<H256, MPTBatchProofInput> {
+        let mut batches = std::collections::HashMap::new();
+        for proof in self.proofs {
+            batches
+                .entry(proof.root)
+                .or_insert_with(|| MPTBatchProofInput { version: self.version, proofs: Vec::new(), root: proof.root })
+                .proofs
+                .push(proof);
+        }
+        batches
+    }
+}
+
+impl MultiChainProofInput {
+    /// Verify each chain's proof against that chain's own root
+    pub fn verify_all(&self) -> MultiChainVerificationResult {
+        let results: Vec<(String, MPTVerificationResult)> = self
+            .chains
+            .iter()
+            .map(|(name, p)| {
+                let result = MPTVerificationResult {
+                    verified: p.verify() == Ok(true),
+                    key: p.key.clone(),
+                    value: p.value.clone(),
+                    root: p.root,
+                    proof_type: p.proof_type,
+                };
+                (name.clone(), result)
+            })
+            .collect();
+
+        MultiChainVerificationResult {
+            all_verified: results.iter().all(|(_, r)| r.verified),
+            results,
+        }
+    }
+}
+
+/// Ethereum account as stored in the state trie: RLP([nonce, balance, storageRoot, codeHash])
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EthAccount {
+    pub nonce: u64,
+    pub balance: [u8; 32], // big-endian u256
+    #[cfg_attr(feature = "serde-hex", serde(with = "h256_hex"))]
+    pub storage_root: H256,
+    #[cfg_attr(feature = "serde-hex", serde(with = "h256_hex"))]
+    pub code_hash: H256,
+}
+
+impl EthAccount {
+    /// Encode the account as the RLP value stored in the state trie
+    pub fn to_rlp(&self) -> Vec<u8> {
+        let items = vec![
+            rlp_encode_integer(self.nonce),
+            rlp_encode_bigint(&self.balance),
+            encode_bytes(&self.storage_root),
+            encode_bytes(&self.code_hash),
+        ];
+        encode_list(&items)
+    }
+
+    /// Decode an account from its state trie RLP value
+    pub fn from_rlp(data: &[u8]) -> Result<Self, NodeDecodeError> {
+        let items = decode_list(data)?;
+        if items.len() != 4 {
+            return Err(NodeDecodeError::InvalidItemCount(items.len()));
+        }
+
+        // Integers are minimal big-endian, so each account has exactly one encoding
+        let nonce = rlp_decode_integer(&items[0]).map_err(NodeDecodeError::InvalidField)?;
+
+        let balance_bytes = decode_bytes(&items[1])?;
+        if balance_bytes.len() > 32 {
+            return Err(NodeDecodeError::InvalidField("balance overflows u256"));
+        }
+        if balance_bytes.first() == Some(&0) {
+            return Err(NodeDecodeError::InvalidField("balance has a leading zero"));
+        }
+        let mut balance = [0u8; 32];
+        balance[32 - balance_bytes.len()..].copy_from_slice(&balance_bytes);
+
+        let storage_root = decode_bytes(&items[2])?;
+        let code_hash = decode_bytes(&items[3])?;
+        if storage_root.len() != 32 || code_hash.len() != 32 {
+            return Err(NodeDecodeError::InvalidField("hash fields must be 32 bytes"));
+        }
+
+        let mut account = Self {
+            nonce,
+            balance,
+            storage_root: [0u8; 32],
+            code_hash: [0u8; 32],
+        };
+        account.storage_root.copy_from_slice(&storage_root);
+        account.code_hash.copy_from_slice(&code_hash);
+        Ok(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::h256_from_hex;
+
+    // Empty account: nonce 0, balance 0, empty storage root, keccak256("") code hash
+    const EMPTY_STORAGE_ROOT: &str = "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421";
+    const EMPTY_CODE_HASH: &str = "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470";
+
+    #[test]
+    fn test_abbrev_hash_display() {
+        let mut hash = [0u8; 32];
+        hash[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        hash[30..].copy_from_slice(&[0xca, 0xfe]);
+        
+        let abbrev = alloc::format!("{}", AbbrevHash(hash));
+        assert_eq!(abbrev, "0xdeadbeef\u{2026}cafe");
+        assert!(abbrev.contains("0xdeadbeef"));
+        assert_eq!(abbrev.chars().count(), 15);
+        
+        let extension = ExtensionNode { path: vec![1], child: hash };
+        assert!(alloc::format!("{:?}", Node::Extension(extension)).contains("child: 0xdeadbeef\u{2026}cafe"));
+        
+        let full = alloc::format!("{:x}", HexDisplay(hash));
+        assert_eq!(full.len(), 64);
+        assert!(full.starts_with("deadbeef000000") && full.ends_with("cafe"));
+        assert_eq!(alloc::format!("{:#x}", HexDisplay(hash)), alloc::format!("0x{}", full));
+    }
+
+    #[test]
+    fn test_node_structs_roundtrip() {
+        let leaf = LeafNode { path: vec![6, 4, 6, 15, 6, 7], value: b"puppy".to_vec() };
+        assert_eq!(LeafNode::from_rlp(&leaf.to_rlp()), Ok(leaf.clone()));
+        assert_eq!(leaf.hash(), keccak256(&leaf.to_rlp()));
+        
+        let extension = ExtensionNode { path: vec![1, 2, 3], child: [0xab; 32] };
+        assert_eq!(ExtensionNode::from_rlp(&extension.to_rlp()), Ok(extension.clone()));
+        
+        let mut children: [Option<H256>; 16] = Default::default();
+        children[0] = Some(leaf.hash());
+        children[15] = Some(extension.hash());
+        let branch = BranchNode { children, value: Some(b"verb".to_vec()) };
+        assert_eq!(BranchNode::from_rlp(&branch.to_rlp()), Ok(branch.clone()));
+        assert_eq!(Node::from_rlp(&branch.to_rlp()), Ok(Node::Branch(branch.clone())));
+        assert_eq!(Node::Branch(branch.clone()).hash(), branch.hash());
+        
+        assert_eq!(Node::from_rlp(&Node::Empty.to_rlp()), Ok(Node::Empty));
+    }
+    
+    #[test]
+    fn test_node_accessors() {
+        let leaf = Node::Leaf(LeafNode { path: vec![1], value: b"puppy".to_vec() });
+        assert!(leaf.child_hashes().is_empty());
+        assert!(leaf.has_value());
+        assert_eq!(leaf.value(), Some(&b"puppy"[..]));
+        
+        let extension = Node::Extension(ExtensionNode { path: vec![1, 2], child: [0xab; 32] });
+        assert_eq!(extension.child_hashes(), vec![[0xab; 32]]);
+        assert!(!extension.has_value());
+        assert_eq!(extension.value(), None);
+        
+        let mut children: [Option<H256>; 16] = Default::default();
+        children[9] = Some([9; 32]);
+        children[2] = Some([2; 32]);
+        let mut branch = BranchNode { children, value: None };
+        assert_eq!(Node::Branch(branch.clone()).child_hashes(), vec![[2; 32], [9; 32]]);
+        assert!(!Node::Branch(branch.clone()).has_value());
+        branch.value = Some(b"verb".to_vec());
+        assert!(Node::Branch(branch.clone()).has_value());
+        assert_eq!(Node::Branch(branch).value(), Some(&b"verb"[..]));
+        
+        assert!(Node::Empty.child_hashes().is_empty());
+        assert!(!Node::Empty.has_value());
+        assert_eq!(Node::Empty.value(), None);
+    }
+    
+    #[test]
+    fn test_cached_node_hash() {
+        let mut cached = CachedNode::new(Node::Extension(ExtensionNode { path: vec![1, 2], child: [0xab; 32] }));
+        assert!(!cached.is_hashed());
+        let hash = cached.hash();
+        assert!(cached.is_hashed());
+        assert_eq!(hash, cached.node().hash());
+        assert_eq!(cached.hash(), hash);
+        
+        // Changing the child invalidates the hash
+        if let Node::Extension(extension) = cached.node_mut() {
+            extension.child = [0xcd; 32];
+        }
+        assert!(!cached.is_hashed());
+        assert_ne!(cached.hash(), hash);
+        assert_eq!(cached.clone().into_node().hash(), cached.hash());
+    }
+    
+    #[cfg(feature = "alloy-rlp")]
+    #[test]
+    fn test_node_alloy_rlp_roundtrip() {
+        use alloy_rlp::{Decodable, Encodable};
+        
+        let leaf = Node::Leaf(LeafNode { path: vec![6, 4, 6, 15], value: b"puppy".to_vec() });
+        let mut children: [Option<H256>; 16] = Default::default();
+        children[3] = Some(leaf.hash());
+        let branch = Node::Branch(BranchNode { children, value: None });
+        
+        // Two nodes back to back in one buffer
+        let mut out = Vec::new();
+        leaf.encode(&mut out);
+        branch.encode(&mut out);
+        assert_eq!(out, [leaf.to_rlp(), branch.to_rlp()].concat());
+        assert_eq!(branch.length(), branch.to_rlp().len());
+        
+        let mut buf = &out[..];
+        assert_eq!(Node::decode(&mut buf), Ok(leaf));
+        assert_eq!(Node::decode(&mut buf), Ok(branch));
+        assert!(buf.is_empty());
+        assert!(Node::decode(&mut &[0xc3, 0x01, 0x02, 0x03][..]).is_err());
+        assert_eq!(Node::decode(&mut &[0xc5, 0x01][..]), Err(alloy_rlp::Error::InputTooShort));
+    }
+    
+    #[test]
+    fn test_node_decode_errors() {
+        let leaf = LeafNode { path: vec![1], value: b"v".to_vec() };
+        assert_eq!(ExtensionNode::from_rlp(&leaf.to_rlp()), Err(NodeDecodeError::UnexpectedKind));
+        assert_eq!(BranchNode::from_rlp(&leaf.to_rlp()), Err(NodeDecodeError::UnexpectedKind));
+        
+        // Extension whose child is not a 32-byte hash
+        let short_child = encode_list(&[encode_bytes(&encode_path(&[1], false)), encode_bytes(&[0xab; 31])]);
+        assert_eq!(Node::from_rlp(&short_child), Err(NodeDecodeError::InvalidChild));
+        
+        let three_items = encode_list(&[encode_bytes(b"a"), encode_bytes(b"b"), encode_bytes(b"c")]);
+        assert_eq!(Node::from_rlp(&three_items), Err(NodeDecodeError::InvalidItemCount(3)));
+        assert!(matches!(Node::from_rlp(&[0xc5, 0x83]), Err(NodeDecodeError::InvalidRlp(_))));
+    }
+
+    #[test]
+    fn test_empty_account_rlp() {
+        let account = EthAccount {
+            nonce: 0,
+            balance: [0u8; 32],
+            storage_root: h256_from_hex(EMPTY_STORAGE_ROOT),
+            code_hash: h256_from_hex(EMPTY_CODE_HASH),
+        };
+
+        let rlp = account.to_rlp();
+        assert_eq!(&rlp[..4], &[0xf8, 0x44, 0x80, 0x80]);
+        assert_eq!(rlp.len(), 70);
+        assert_eq!(EthAccount::from_rlp(&rlp).unwrap(), account);
+    }
+
+    #[test]
+    fn test_account_rlp_roundtrip() {
+        let mut balance = [0u8; 32];
+        balance[24..].copy_from_slice(&1_000_000_000_000_000_000u64.to_be_bytes());
+
+        let account = EthAccount {
+            nonce: 42,
+            balance,
+            storage_root: [0x11; 32],
+            code_hash: [0x22; 32],
+        };
+
+        let decoded = EthAccount::from_rlp(&account.to_rlp()).unwrap();
+        assert_eq!(decoded, account);
+    }
+
+    #[cfg(feature = "std")]
+    fn build_batch(entries: &[(&[u8], &[u8])]) -> MPTBatchProofInput {
+        let mut builder = crate::builder::MPTBuilder::new();
+        for (key, value) in entries {
+            builder.insert(key, value);
+        }
+        let root = builder.root().unwrap();
+
+        let proofs = entries
+            .iter()
+            .map(|(key, value)| MPTProofInput {
+                version: PROOF_FORMAT_VERSION,
+                key: key.to_vec(),
+                value: value.to_vec(),
+                proof: builder.get_proof(key).unwrap(),
+                root,
+                proof_type: ProofType::Inclusion,
+            })
+            .collect();
+
+        MPTBatchProofInput { version: PROOF_FORMAT_VERSION, proofs, root }
+    }
+
+    // Values are long enough that every leaf is referenced by hash
+    #[cfg(feature = "std")]
+    const BATCH_ENTRIES: [(&[u8], &[u8]); 3] = [
+        (b"alpha", b"the first value stored in the batch trie"),
+        (b"beta", b"the second value stored in the batch trie"),
+        (b"gamma", b"the third value stored in the batch trie"),
+    ];
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_batch_verify_all_valid() {
+        let batch = build_batch(&BATCH_ENTRIES);
+        let result = batch.verify_all();
+
+        assert!(result.all_verified);
+        assert_eq!(result.individual_results, vec![true, true, true]);
+        assert_eq!(result.count, 3);
+        assert_eq!(result.root, batch.root);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_batch_verify_all_invalid() {
+        let mut batch = build_batch(&BATCH_ENTRIES);
+        for p in &mut batch.proofs {
+            p.value = b"wrong".to_vec();
+        }
+        let result = batch.verify_all();
+
+        assert!(!result.all_verified);
+        assert_eq!(result.individual_results, vec![false, false, false]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_batch_verify_mixed() {
+        let mut batch = build_batch(&BATCH_ENTRIES);
+        batch.proofs[1].value = b"kitten".to_vec();
+        // An entry claiming a different root is rejected even if its proof is intact
+        batch.proofs[2].root = [0xff; 32];
+        let result = batch.verify_all();
+
+        assert!(!result.all_verified);
+        assert_eq!(result.individual_results, vec![true, false, false]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_batch_split_by_root() {
+        let keys: Vec<[u8; 2]> = (0..10u8).map(|i| [b'a', i]).chain((0..5u8).map(|i| [b'b', i])).collect();
+        let value: &[u8] = &[0x42; 40];
+        let entries: Vec<(&[u8], &[u8])> = keys.iter().map(|key| (&key[..], value)).collect();
+        let batch_a = build_batch(&entries[..10]);
+        let batch_b = build_batch(&entries[10..]);
+        let (root_a, root_b) = (batch_a.root, batch_b.root);
+
+        let mut proofs = batch_a.proofs;
+        // Interleave so grouping cannot rely on the input order
+        for (i, proof) in batch_b.proofs.into_iter().enumerate() {
+            proofs.insert(i * 2, proof);
+        }
+        let mixed = MPTBatchProofInput { version: PROOF_FORMAT_VERSION, proofs, root: root_a };
+        assert_eq!(mixed.all_roots(), vec![root_b, root_a]);
+        assert!(!mixed.verify_all().all_verified);
+
+        let split = mixed.split_by_root();
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[&root_a].proofs.len(), 10);
+        assert_eq!(split[&root_b].proofs.len(), 5);
+        for (root, batch) in &split {
+            assert_eq!(batch.root, *root);
+            assert!(batch.verify_all().all_verified);
+        }
+    }
+
+    #[test]
+    fn test_batch_verify_empty() {
+        let batch = MPTBatchProofInput { version: PROOF_FORMAT_VERSION, proofs: vec![], root: [0u8; 32] };
+        let result = batch.verify_all();
+
+        assert!(result.all_verified);
+        assert!(result.individual_results.is_empty());
+        assert_eq!(result.count, 0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_validate_structure() {
+        let valid = build_batch(&BATCH_ENTRIES).proofs[0].clone();
+        assert_eq!(valid.validate_structure(), Ok(()));
+
+        let mut input = valid.clone();
+        input.root = [0u8; 32];
+        assert_eq!(input.validate_structure(), Err(ValidationError::ZeroRoot));
+
+        let mut input = valid.clone();
+        input.key.clear();
+        assert_eq!(input.validate_structure(), Err(ValidationError::EmptyKey));
+
+        let mut input = valid.clone();
+        input.proof.clear();
+        assert_eq!(input.validate_structure(), Err(ValidationError::EmptyProof));
+
+        let mut input = valid.clone();
+        input.proof.push(vec![0x82, 0x01, 0x02]);
+        assert_eq!(input.validate_structure(), Err(ValidationError::InvalidNodeRlp(input.proof.len() - 1)));
+        input.proof.insert(1, vec![0xc5, 0x01]);
+        assert_eq!(input.validate_structure(), Err(ValidationError::InvalidNodeRlp(1)));
+
+        let mut input = valid.clone();
+        input.proof.insert(1, encode_list(&[vec![0x01], vec![0x02], vec![0x03]]));
+        assert_eq!(input.validate_structure(), Err(ValidationError::InvalidItemCount { index: 1, count: 3 }));
+
+        // Ends on the root branch, which holds no value
+        let mut input = valid.clone();
+        input.proof.truncate(1);
+        assert_eq!(input.validate_structure(), Err(ValidationError::InvalidLastNode));
+
+        let mut input = valid.clone();
+        input.root[0] ^= 1;
+        assert_eq!(input.validate_structure(), Err(ValidationError::RootMismatch));
+
+        // Structure only: a wrong value still passes
+        let mut input = valid;
+        input.value = b"wrong".to_vec();
+        assert_eq!(input.validate_structure(), Ok(()));
+        assert_eq!(input.verify(), Ok(false));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_unsupported_proof_version() {
+        let mut batch = build_batch(&BATCH_ENTRIES);
+        assert_eq!(batch.proofs[0].verify(), Ok(true));
+
+        let mut input = batch.proofs[0].clone();
+        input.version = 0xff;
+        assert_eq!(input.verify(), Err(VerificationError::UnsupportedProofVersion(255)));
+        input.version = 0;
+        assert_eq!(input.verify(), Err(VerificationError::UnsupportedProofVersion(0)));
+
+        batch.proofs[1].version = 0xff;
+        assert_eq!(batch.verify_all().individual_results, vec![true, false, true]);
+        batch.proofs[1].version = PROOF_FORMAT_VERSION;
+        batch.version = 2;
+        assert_eq!(batch.verify_all().individual_results, vec![false, false, false]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_proof_version_serialized_first() {
+        let input = build_batch(&BATCH_ENTRIES).proofs[0].clone();
+        let bytes = postcard::to_allocvec(&input).unwrap();
+        assert_eq!(bytes[0], PROOF_FORMAT_VERSION);
+        let decoded: MPTProofInput = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.version, PROOF_FORMAT_VERSION);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_proof_type_serde_roundtrip() {
+        let input = build_batch(&BATCH_ENTRIES).proofs[0].clone();
+        let types = [ProofType::Inclusion, ProofType::Exclusion, ProofType::StateTransition { new_root: [0x5a; 32] }];
+        for proof_type in types {
+            let input = MPTProofInput { proof_type, ..input.clone() };
+            let bytes = postcard::to_allocvec(&input).unwrap();
+            assert_eq!(postcard::from_bytes::<MPTProofInput>(&bytes).unwrap().proof_type, proof_type);
+            let json = serde_json::to_string(&input).unwrap();
+            assert_eq!(serde_json::from_str::<MPTProofInput>(&json).unwrap().proof_type, proof_type);
+        }
+        
+        // Inputs written before the field existed are inclusion proofs
+        let mut json = serde_json::to_value(&input).unwrap();
+        json.as_object_mut().unwrap().remove("proof_type");
+        assert_eq!(serde_json::from_value::<MPTProofInput>(json).unwrap().proof_type, ProofType::Inclusion);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_mixed_batch_verify_all() {
+        let mut builder = crate::builder::MPTBuilder::new();
+        for (key, value) in BATCH_ENTRIES {
+            builder.insert(key, value);
+        }
+        let mut batch = build_batch(&BATCH_ENTRIES);
+        let root = batch.root;
+        let template = batch.proofs[0].clone();
+        
+        let absent = MPTProofInput {
+            key: b"delta".to_vec(),
+            value: Vec::new(),
+            proof: builder.get_exclusion_proof(b"delta").unwrap(),
+            proof_type: ProofType::Exclusion,
+            ..template.clone()
+        };
+        let new_root = builder.clone().insert(b"delta", b"a new value");
+        let transition = MPTProofInput {
+            value: b"a new value".to_vec(),
+            proof_type: ProofType::StateTransition { new_root },
+            ..absent.clone()
+        };
+        batch.proofs.extend([absent.clone(), transition.clone()]);
+        assert_eq!(batch.verify_all().individual_results, vec![true; 5]);
+        
+        // Each claim is checked as what it says it is
+        let present = MPTProofInput { proof_type: ProofType::Exclusion, value: Vec::new(), ..template };
+        let wrong_root = MPTProofInput { proof_type: ProofType::StateTransition { new_root: root }, ..transition };
+        let wrong_value = MPTProofInput { value: b"x".to_vec(), ..absent };
+        batch.proofs = vec![present, wrong_root, wrong_value];
+        assert_eq!(batch.verify_all().individual_results, vec![false; 3]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_multichain_verify_all() {
+        // Two independent "chains", each simulated by a local trie
+        let mainnet = build_batch(&BATCH_ENTRIES[..2]);
+        let rollup = build_batch(&BATCH_ENTRIES[1..]);
+        assert_ne!(mainnet.root, rollup.root);
+
+        let mut input = MultiChainProofInput {
+            chains: vec![
+                ("mainnet".into(), mainnet.proofs[0].clone()),
+                ("rollup".into(), rollup.proofs[1].clone()),
+            ],
+        };
+
+        let result = input.verify_all();
+        assert!(result.all_verified);
+        assert_eq!(result.results.len(), 2);
+        assert_eq!(result.results[0].0, "mainnet");
+        assert_eq!(result.results[0].1.root, mainnet.root);
+        assert_eq!(result.results[1].1.root, rollup.root);
+
+        // A bad proof on one chain fails only that chain
+        input.chains[1].1.value = b"wrong".to_vec();
+        let result = input.verify_all();
+        assert!(!result.all_verified);
+        assert!(result.results[0].1.verified);
+        assert!(!result.results[1].1.verified);
+
+        // So does a proof checked against the other chain's root
+        input.chains[1].1 = rollup.proofs[1].clone();
+        input.chains[1].1.root = mainnet.root;
+        assert!(!input.verify_all().results[1].1.verified);
+    }
+
+    #[cfg(feature = "serde-hex")]
+    #[test]
+    fn test_h256_hex_serde_roundtrip() {
+        let input = build_batch(&BATCH_ENTRIES).proofs[0].clone();
+
+        let json = serde_json::to_value(&input).unwrap();
+        let root = json["root"].as_str().unwrap();
+        assert_eq!(root.len(), 66);
+        assert!(root.starts_with("0x"));
+
+        let decoded: MPTProofInput = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.root, input.root);
+        assert_eq!(decoded.proof, input.proof);
+
+        // Binary formats keep the raw 32-byte array
+        let bytes = postcard::to_allocvec(&input).unwrap();
+        let decoded: MPTProofInput = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.root, input.root);
+
+        let bad = serde_json::json!({ "version": 1, "key": [], "value": [], "proof": [], "root": "0x1234" });
+        assert!(serde_json::from_value::<MPTProofInput>(bad).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_proof_size_bytes() {
+        let mut builder = crate::builder::MPTBuilder::new();
+        for i in 0..10u8 {
+            builder.insert(&[i], &[i; 40]);
+        }
+        let root = builder.root().unwrap();
+
+        // All keys share nibble 0: extension -> branch -> leaf
+        let key = [1u8];
+        let proof = builder.get_proof(&key).unwrap();
+        assert_eq!(proof.len(), 3);
+
+        let size = proof_size_bytes(&proof);
+        assert!((200..=1200).contains(&size), "proof size {}", size);
+
+        let input = MPTProofInput { version: PROOF_FORMAT_VERSION, key: key.to_vec(), value: vec![1; 40], proof, root, proof_type: ProofType::Inclusion };
+        assert_eq!(input.size_bytes(), 1 + 40 + 32 + size);
+
+        let batch = MPTBatchProofInput { version: PROOF_FORMAT_VERSION, proofs: vec![input.clone(), input.clone()], root };
+        assert_eq!(batch.total_size_bytes(), 2 * input.size_bytes());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_encoded_calldata_size_matches_abi() {
+        use alloy_sol_types::{sol, SolValue};
+
+        sol! {
+            struct ProofCalldata {
+                bytes key;
+                bytes value;
+                bytes[] proof;
+                bytes32 root;
+            }
+        }
+
+        for input in build_batch(&BATCH_ENTRIES).proofs {
+            let encoded = ProofCalldata {
+                key: input.key.clone().into(),
+                value: input.value.clone().into(),
+                proof: input.proof.iter().cloned().map(Into::into).collect(),
+                root: input.root.into(),
+            }
+            .abi_encode();
+            assert_eq!(input.encoded_calldata_size(), encoded.len());
+        }
+    }
+
+    #[test]
+    fn test_account_from_rlp_invalid() {
+        assert!(matches!(EthAccount::from_rlp(&[]), Err(NodeDecodeError::InvalidRlp(_))));
+        let short = encode_list(&[encode_bytes(&[1]), encode_bytes(&[2])]);
+        assert_eq!(EthAccount::from_rlp(&short), Err(NodeDecodeError::InvalidItemCount(2)));
+        let long_nonce = encode_list(&[
+            encode_bytes(&[1; 9]),
+            encode_bytes(&[]),
+            encode_bytes(&[0x11; 32]),
+            encode_bytes(&[0x22; 32]),
+        ]);
+        assert!(matches!(EthAccount::from_rlp(&long_nonce), Err(NodeDecodeError::InvalidField(_))));
+    }
+
+    #[test]
+    fn test_account_from_rlp_non_canonical() {
+        let account = |nonce: &[u8], balance: &[u8]| {
+            EthAccount::from_rlp(&encode_list(&[
+                encode_bytes(nonce),
+                encode_bytes(balance),
+                encode_bytes(&[0x11; 32]),
+                encode_bytes(&[0x22; 32]),
+            ]))
+        };
+        assert_eq!(account(&[5], &[1]).unwrap().nonce, 5);
+        // Leading zeros and a zero byte for zero would give the account a second encoding
+        assert!(matches!(account(&[0, 5], &[1]), Err(NodeDecodeError::InvalidField(_))));
+        assert!(matches!(account(&[0], &[1]), Err(NodeDecodeError::InvalidField(_))));
+        assert!(matches!(account(&[5], &[0, 1]), Err(NodeDecodeError::InvalidField(_))));
+        assert!(matches!(account(&[5], &[0]), Err(NodeDecodeError::InvalidField(_))));
+    }
+}
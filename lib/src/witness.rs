@@ -0,0 +1,179 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use crate::rlp_encoding::keccak256;
+use crate::types::{Node, NodeDecodeError, H256};
+
+#[cfg(feature = "std")]
+use crate::builder::MPTBuilder;
+
+/// The kind of a trie node in a `WitnessNode`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NodeKind {
+    Leaf,
+    Extension,
+    Branch,
+}
+
+/// A trie node with its fields pre-decoded, so a circuit can read them without parsing RLP
+///
+/// `children` has 16 entries for a branch, one (the child hash) for an extension and none
+/// for a leaf. `path` holds the decoded nibbles of a leaf or extension.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessNode {
+    pub hash: H256,
+    pub rlp: Vec<u8>,
+    pub node_type: NodeKind,
+    pub children: Vec<Option<H256>>,
+    pub value: Option<Vec<u8>>,
+    pub path: Option<Vec<u8>>,
+}
+
+/// The deduplicated nodes on the proof paths of a set of keys, root first
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieWitness {
+    pub nodes: Vec<WitnessNode>,
+}
+
+impl WitnessNode {
+    /// Decode an RLP-encoded trie node, hashing it with keccak256
+    pub fn from_rlp(rlp: &[u8]) -> Result<Self, NodeDecodeError> {
+        let node = Node::from_rlp(rlp)?;
+        Self::from_node(keccak256(rlp), rlp.to_vec(), node).ok_or(NodeDecodeError::UnexpectedKind)
+    }
+
+    /// Flatten an already decoded node into witness fields (`None` for `Node::Empty`)
+    fn from_node(hash: H256, rlp: Vec<u8>, node: Node) -> Option<Self> {
+        let (node_type, children, value, path) = match node {
+            Node::Leaf(leaf) => (NodeKind::Leaf, Vec::new(), Some(leaf.value), Some(leaf.path)),
+            Node::Extension(extension) => {
+                (NodeKind::Extension, vec![Some(extension.child)], None, Some(extension.path))
+            }
+            Node::Branch(branch) => (NodeKind::Branch, branch.children.to_vec(), branch.value, None),
+            Node::Empty => return None,
+        };
+
+        Some(Self {
+            hash,
+            rlp,
+            node_type,
+            children,
+            value,
+            path,
+        })
+    }
+}
+
+/// Collect the nodes on the proof paths of `keys` into a witness
+///
+/// Nodes shared between paths appear once, in the order they are first visited. Nodes
+/// embedded in their parent are included as well, keyed by the keccak256 of their RLP
+/// like every other node. Keys without a proof contribute no nodes.
+#[cfg(feature = "std")]
+pub fn generate_witness(builder: &MPTBuilder, keys: &[&[u8]]) -> TrieWitness {
+    let mut seen = std::collections::HashSet::new();
+    let mut nodes = Vec::new();
+
+    for path in keys.iter().filter_map(|key| builder.path_nodes(key)) {
+        for (hash, rlp, node) in path {
+            if !seen.insert(hash) {
+                continue;
+            }
+            nodes.extend(WitnessNode::from_node(hash, rlp, node));
+        }
+    }
+
+    TrieWitness { nodes }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_generate_witness() {
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb-value-long-enough-to-be-hashed");
+        builder.insert(b"dog", b"puppy-value-long-enough-to-be-hashed");
+        builder.insert(b"doge", b"coin-value-long-enough-to-be-hashed");
+        builder.insert(b"horse", b"stallion-value-long-enough-to-be-hashed");
+        let root = builder.root().unwrap();
+
+        let keys: [&[u8]; 3] = [b"dog", b"horse", b"missing"];
+        let witness = generate_witness(&builder, &keys);
+
+        assert_eq!(witness.nodes[0].hash, root);
+        for node in &witness.nodes {
+            assert_eq!(node.hash, keccak256(&node.rlp));
+        }
+
+        // Shared nodes (e.g. the root) are included once
+        let mut hashes: Vec<H256> = witness.nodes.iter().map(|n| n.hash).collect();
+        hashes.sort();
+        hashes.dedup();
+        assert_eq!(hashes.len(), witness.nodes.len());
+
+        // Every proof node is in the witness, and child hashes link to included nodes
+        for key in &keys[..2] {
+            for rlp in builder.get_proof(key).unwrap() {
+                assert!(hashes.contains(&keccak256(&rlp)));
+            }
+        }
+        for node in &witness.nodes[1..] {
+            assert!(witness.nodes.iter().any(|n| n.children.contains(&Some(node.hash))));
+        }
+
+        let horse = witness
+            .nodes
+            .iter()
+            .find(|n| n.value.as_deref() == Some(&b"stallion-value-long-enough-to-be-hashed"[..]))
+            .unwrap();
+        assert_eq!(horse.node_type, NodeKind::Leaf);
+        assert!(horse.children.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_generate_witness_embedded_nodes() {
+        // Short values give nodes under 32 bytes, embedded in their parents
+        let mut builder = MPTBuilder::new();
+        builder.insert(b"do", b"verb");
+        builder.insert(b"dog", b"puppy");
+        builder.insert(b"doge", b"coin");
+
+        let witness = generate_witness(&builder, &[b"doge"]);
+        assert_eq!(witness.nodes.len(), builder.total_node_count());
+        assert!(witness.nodes.len() > builder.get_proof(b"doge").unwrap().len());
+        assert!(witness.nodes.iter().any(|n| n.value.as_deref() == Some(&b"coin"[..])));
+        for node in &witness.nodes {
+            assert_eq!(node.hash, keccak256(&node.rlp));
+        }
+    }
+
+    #[test]
+    fn test_witness_node_from_rlp() {
+        let child = [0xab; 32];
+        let branch = crate::rlp_encoding::encode_list(
+            &(0..17)
+                .map(|i| match i {
+                    3 => crate::rlp_encoding::encode_bytes(&child),
+                    16 => crate::rlp_encoding::encode_bytes(b"v"),
+                    _ => crate::rlp_encoding::encode_bytes(&[]),
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let node = WitnessNode::from_rlp(&branch).unwrap();
+        assert_eq!(node.node_type, NodeKind::Branch);
+        assert_eq!(node.hash, keccak256(&branch));
+        assert_eq!(node.children.len(), 16);
+        assert_eq!(node.children[3], Some(child));
+        assert_eq!(node.children.iter().flatten().count(), 1);
+        assert_eq!(node.value, Some(b"v".to_vec()));
+        assert_eq!(node.path, None);
+
+        assert_eq!(WitnessNode::from_rlp(&[0xc0]), Err(NodeDecodeError::InvalidItemCount(0)));
+        assert_eq!(WitnessNode::from_rlp(&[0x80]), Err(NodeDecodeError::UnexpectedKind));
+    }
+}
@@ -0,0 +1,10 @@
+//! Helpers shared by the integration tests
+
+/// Decode a hex test vector, with or without a `0x` prefix
+pub fn from_hex(s: &str) -> Vec<u8> {
+    let s = s.trim_start_matches("0x");
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
@@ -0,0 +1,85 @@
+//! RLP vectors from EIP-778 Ethereum Node Records.
+//!
+//! An ENR is the RLP list `[signature, seq, k, v, ...]` with its keys sorted. The record
+//! below is the example from the EIP (`enr:-IS4QHCYrYZ...`, base64url-decoded): a v4
+//! identity for 127.0.0.1, UDP port 30303. It exercises long lists and strings that
+//! trie nodes rarely need, such as a 64-byte signature and a long-list header.
+
+mod common;
+
+use common::from_hex;
+use mpt_lib::{decode_bytes, decode_list, decode_recursive, encode_bytes, encode_list, RlpValue};
+
+const EIP778_EXAMPLE: &str = "f884b8407098ad865b00a582051940cb9cf36836572411a47278783077011599ed5cd16b76f2635f4e234738f30813a89eb9137e3e3df5266e3a1f11df72ecf1145ccb9c01826964827634826970847f00000189736563703235366b31a103ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd31388375647082765f";
+
+/// The record's fields, each decoded from its RLP string
+fn fields(record: &[u8]) -> Vec<Vec<u8>> {
+    decode_list(record)
+        .unwrap()
+        .iter()
+        .map(|item| decode_bytes(item).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_enr_decode_eip778_example() {
+    let record = from_hex(EIP778_EXAMPLE);
+    assert_eq!(record.len(), 134);
+
+    let fields = fields(&record);
+    assert_eq!(fields.len(), 10);
+    assert_eq!(fields[0].len(), 64, "secp256k1 signature");
+    assert_eq!(fields[1], [0x01], "seq");
+
+    let pairs: Vec<(&[u8], &[u8])> = fields[2..].chunks(2).map(|kv| (&kv[0][..], &kv[1][..])).collect();
+    assert_eq!(
+        pairs,
+        [
+            (&b"id"[..], &b"v4"[..]),
+            (b"ip", &[127, 0, 0, 1]),
+            (b"secp256k1", &from_hex("03ca634cae0d49acb401d8a4c6b6fe8c55b70d115bf400769cc1400f3258cd3138")),
+            (b"udp", &30303u16.to_be_bytes()),
+        ]
+    );
+    assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0), "keys are sorted");
+}
+
+#[test]
+fn test_enr_encode_eip778_example() {
+    let record = from_hex(EIP778_EXAMPLE);
+    let items: Vec<Vec<u8>> = fields(&record).iter().map(|field| encode_bytes(field)).collect();
+    assert_eq!(encode_list(&items), record);
+
+    // The nested decoder sees the same flat list of strings
+    match decode_recursive(&record).unwrap() {
+        RlpValue::List(values) => {
+            assert_eq!(values.len(), 10);
+            assert!(values.iter().all(|value| matches!(value, RlpValue::Bytes(_))));
+        }
+        RlpValue::Bytes(_) => panic!("an ENR is a list"),
+    }
+}
+
+#[test]
+fn test_enr_malformed() {
+    let record = from_hex(EIP778_EXAMPLE);
+
+    // Truncated: the f884 header promises 132 bytes of payload
+    let truncated = &record[..record.len() - 1];
+    assert_eq!(decode_list(truncated), Err("Invalid RLP: payload too short"));
+    assert_eq!(decode_recursive(truncated), Err("Invalid RLP: insufficient data"));
+
+    // A long-list header without its length byte
+    assert_eq!(decode_list(&record[..1]), Err("Invalid RLP: insufficient length bytes"));
+
+    // The signature string claims more bytes than the record holds
+    let mut overlong = record.clone();
+    overlong[3] = 0xff;
+    assert_eq!(decode_list(&overlong), Err("Invalid RLP: insufficient data"));
+    assert_eq!(decode_bytes(&overlong[2..]), Err("Invalid RLP: insufficient data"));
+
+    // Extra bytes after the record
+    let mut trailing = record.clone();
+    trailing.push(0x80);
+    assert_eq!(decode_recursive(&trailing), Err("Invalid RLP: trailing bytes"));
+}
@@ -0,0 +1,23 @@
+//! Runs every trie fixture in `tests/fixtures/trie` against its expected root.
+//!
+//! Fixtures use the ethereum/tests `TrieTests` layout; drop more files into the
+//! directory to run them too.
+
+#![cfg(feature = "std")]
+
+use std::path::Path;
+use mpt_lib::MPTBuilder;
+
+#[test]
+fn test_trie_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/trie");
+    let mut count = 0;
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            assert!(MPTBuilder::verify_against_fixture(&path).unwrap(), "root mismatch in {}", path.display());
+            count += 1;
+        }
+    }
+    assert!(count >= 3, "found only {} fixtures in {}", count, dir.display());
+}
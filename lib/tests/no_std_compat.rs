@@ -0,0 +1,62 @@
+//! Exercises the core verification API using only `core` and `alloc`.
+//!
+//! Run with `cargo test -p mpt-lib --no-default-features --test no_std_compat` to make
+//! sure nothing outside the `std`-gated builder depends on the standard library.
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use mpt_lib::{
+    encode_bytes, encode_list, encode_path, keccak256, to_nibbles, verify_proof, MPTProofInput,
+    MPTVerificationResult, ProofType, PROOF_FORMAT_VERSION,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Build a single-leaf trie proof for `key -> value`
+fn single_leaf_proof(key: &[u8], value: &[u8]) -> (MPTProofInput, Vec<u8>) {
+    let nibbles = to_nibbles(key);
+    let leaf_rlp = encode_list(&[
+        encode_bytes(&encode_path(&nibbles, true)),
+        encode_bytes(value),
+    ]);
+    let root = keccak256(&leaf_rlp);
+
+    let input = MPTProofInput {
+        version: PROOF_FORMAT_VERSION,
+        key: key.to_vec(),
+        value: value.to_vec(),
+        proof: vec![leaf_rlp.clone()],
+        root,
+        proof_type: ProofType::Inclusion,
+    };
+    (input, leaf_rlp)
+}
+
+#[test]
+fn test_verify_proof_no_std() {
+    let (input, _) = single_leaf_proof(b"no_std", b"alloc only");
+
+    assert!(verify_proof(&input.root, &input.key, &input.value, &input.proof));
+    assert!(!verify_proof(&input.root, &input.key, b"other", &input.proof));
+}
+
+#[test]
+fn test_types_serializable_no_std() {
+    fn assert_serde<T: Serialize + DeserializeOwned>() {}
+
+    assert_serde::<MPTProofInput>();
+    assert_serde::<MPTVerificationResult>();
+
+    let (input, _) = single_leaf_proof(b"key", b"value");
+    let result = MPTVerificationResult {
+        verified: verify_proof(&input.root, &input.key, &input.value, &input.proof),
+        key: input.key,
+        value: input.value,
+        root: input.root,
+        proof_type: input.proof_type,
+    };
+    assert!(result.verified);
+}
@@ -0,0 +1,90 @@
+//! Reference vectors for hex-prefix path encoding and trie roots.
+//!
+//! The hex-prefix examples follow the Yellow Paper (Appendix C) and the Ethereum wiki's
+//! Patricia tree specification. The trie roots come from `TrieTests/trietest.json` in
+//! ethereum/tests. These tries have short nodes that must be embedded in their parent,
+//! so the roots only match when embedding is canonical.
+
+#![cfg(feature = "std")]
+
+mod common;
+
+use common::from_hex;
+use mpt_lib::{decode_path, encode_path, verify_proof, MPTBuilder, H256};
+
+fn root_of(entries: &[(&[u8], &[u8])]) -> H256 {
+    let mut builder = MPTBuilder::new();
+    for (key, value) in entries {
+        builder.insert(key, value);
+    }
+    let root = builder.root().unwrap();
+
+    // Every key must also prove against the canonical root
+    for (key, value) in entries {
+        let proof = builder.get_proof(key).unwrap();
+        assert!(verify_proof(&root, key, value, &proof), "proof for {:?}", key);
+    }
+    root
+}
+
+#[test]
+fn test_hex_prefix_vectors() {
+    let vectors: [(&[u8], bool, &str); 6] = [
+        (&[1, 2, 3, 4, 5], false, "112345"),
+        (&[0, 1, 2, 3, 4, 5], false, "00012345"),
+        (&[0, 0xf, 1, 0xc, 0xb, 8], true, "200f1cb8"),
+        (&[0xf, 1, 0xc, 0xb, 8], true, "3f1cb8"),
+        (&[1, 2, 3, 4, 5], true, "312345"),
+        (&[0, 1, 2, 3, 4, 5], true, "20012345"),
+    ];
+
+    for (nibbles, is_leaf, expected) in vectors {
+        let encoded = encode_path(nibbles, is_leaf);
+        assert_eq!(encoded, from_hex(expected), "encode {:?} leaf={}", nibbles, is_leaf);
+        assert_eq!(decode_path(&encoded), (nibbles.to_vec(), is_leaf));
+    }
+}
+
+#[test]
+fn test_trie_root_puppy() {
+    // The do/dog/doge/horse example from the Ethereum wiki
+    let root = root_of(&[
+        (b"do", b"verb"),
+        (b"horse", b"stallion"),
+        (b"doge", b"coin"),
+        (b"dog", b"puppy"),
+    ]);
+    assert_eq!(root.to_vec(), from_hex("5991bb8c6514148a29db676a14ac506cd2cd5775ace63c30a4fe457715e9ac84"));
+}
+
+#[test]
+fn test_trie_root_dogs() {
+    let root = root_of(&[(b"doe", b"reindeer"), (b"dog", b"puppy"), (b"dogglesworth", b"cat")]);
+    assert_eq!(root.to_vec(), from_hex("8aad789dff2f538bca5d8ea56e8abe10f4c7ba3a5dea95fea4cd6e7c3a1168d3"));
+}
+
+#[test]
+fn test_trie_root_small_values() {
+    let root = root_of(&[(b"be", b"e"), (b"dog", b"puppy"), (b"bed", b"d")]);
+    assert_eq!(root.to_vec(), from_hex("3f67c7a47520f79faa29255d2d3c084a7a6df0453116ed7232ff10277a8be68b"));
+}
+
+#[test]
+fn test_trie_root_foo() {
+    let root = root_of(&[(b"foo", b"bar"), (b"food", b"bass")]);
+    assert_eq!(root.to_vec(), from_hex("17beaa1648bafa633cda809c90c04af50fc8aed3cb40d16efbddee6fdf63c4c3"));
+}
+
+#[test]
+fn test_trie_root_testy() {
+    let root = root_of(&[(b"test", b"test"), (b"te", b"testy")]);
+    assert_eq!(root.to_vec(), from_hex("8452568af70d8d140f58d941338542f645fcca50094b20f3c3d8c3df49337928"));
+}
+
+#[test]
+fn test_trie_root_hex_keys() {
+    let (a, b) = (from_hex("0045"), from_hex("4500"));
+    let (va, vb) = (from_hex("0123456789"), from_hex("9876543210"));
+    let root = root_of(&[(&a, &va), (&b, &vb)]);
+    assert_eq!(root.to_vec(), from_hex("285505fcabe84badc8aa310e2aae17eddc7d120aabec8a476902c8184b3a3503"));
+}
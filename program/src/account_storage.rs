@@ -0,0 +1,21 @@
+//! Account storage program for SP1 zkVM.
+//!
+//! This program proves a storage slot value against a state root: the account proof
+//! shows the account under the state root, and the account's `storageRoot` anchors the
+//! storage proof the value is read from.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use mpt_lib::AccountProofPipeline;
+
+pub fn main() {
+    // Read the account and proofs from the host
+    let pipeline: AccountProofPipeline = sp1_zkvm::io::read();
+    
+    // Verify state root -> account -> storage slot
+    let result = pipeline.verify();
+    
+    // Commit { state_root, address, slot, value, verified }
+    sp1_zkvm::io::commit(&result);
+}
@@ -0,0 +1,55 @@
+//! MPT proof aggregation program for SP1 zkVM.
+//!
+//! This program verifies N single-key MPT proofs (produced by `mpt-program`) with SP1's
+//! recursive proof verification and commits one ABI-encoded `AggregatedMPTProofOutput`.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use alloy_sol_types::SolValue;
+use mpt_lib::{aggregate_root, AggregatedMPTProofOutput, MPTProofOutput, MPTVerificationResult, ProofType};
+use sha2::{Digest, Sha256};
+
+pub fn main() {
+    // Read the verifying key of the inner program and the public values of each inner proof
+    let vkey: [u32; 8] = sp1_zkvm::io::read();
+    let public_values: Vec<Vec<u8>> = sp1_zkvm::io::read();
+    
+    let mut proofs = Vec::with_capacity(public_values.len());
+    let mut roots = Vec::with_capacity(public_values.len());
+    
+    for values in &public_values {
+        // Verify the inner proof against its committed public values
+        let digest: [u8; 32] = Sha256::digest(values).into();
+        sp1_zkvm::lib::verify::verify_sp1_proof(&vkey, &digest);
+        
+        let result: MPTVerificationResult =
+            bincode::deserialize(values).expect("invalid inner public values");
+        assert!(result.verified, "inner MPT proof did not verify");
+        // `MPTProofOutput` claims `key` holds `value` under `root`, which only an inclusion
+        // proof shows; an exclusion or state transition result would be misreported
+        assert_eq!(result.proof_type, ProofType::Inclusion, "inner MPT proof is not an inclusion proof");
+        
+        roots.push(result.root);
+        proofs.push(MPTProofOutput {
+            verified: result.verified,
+            root: result.root.into(),
+            key: result.key.into(),
+            value: result.value.into(),
+        });
+    }
+    
+    // Commit the inner program's vkey so verifiers know which program was aggregated
+    let mut mpt_vkey = [0u8; 32];
+    for (chunk, word) in mpt_vkey.chunks_exact_mut(4).zip(vkey.iter()) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    
+    let output = AggregatedMPTProofOutput {
+        proofs,
+        aggregate_root: aggregate_root(&roots).into(),
+        mpt_vkey: mpt_vkey.into(),
+    };
+    
+    sp1_zkvm::io::commit_slice(&output.abi_encode());
+}
@@ -0,0 +1,19 @@
+//! Batch Merkle Patricia Trie proof verification program for SP1 zkVM.
+//!
+//! This program verifies every proof of an `MPTBatchProofInput` against the shared root.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use mpt_lib::MPTBatchProofInput;
+
+pub fn main() {
+    // Read the batch input from the host
+    let input: MPTBatchProofInput = sp1_zkvm::io::read();
+    
+    // Verify all proofs in the batch
+    let result = input.verify_all();
+    
+    // Commit the batch verification result
+    sp1_zkvm::io::commit(&result);
+}
@@ -0,0 +1,21 @@
+//! Block header chain-of-trust program for SP1 zkVM.
+//!
+//! This program proves a storage slot value from a block hash: the header must hash to
+//! the block hash, its `stateRoot` anchors the account proof, and the account's
+//! `storageRoot` anchors the storage proof.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use mpt_lib::BlockHeaderProofInput;
+
+pub fn main() {
+    // Read the header and proofs from the host
+    let input: BlockHeaderProofInput = sp1_zkvm::io::read();
+    
+    // Verify header -> account -> storage slot
+    let result = input.verify();
+    
+    // Commit { block_hash, address, slot, value, verified }
+    sp1_zkvm::io::commit(&result);
+}
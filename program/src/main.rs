@@ -5,26 +5,27 @@
 #![no_main]
 sp1_zkvm::entrypoint!(main);
 
-use mpt_lib::{MPTProofInput, MPTVerificationResult, verify_proof};
+use mpt_lib::{MPTProofInput, MPTVerificationResult};
 
 pub fn main() {
     // Read the proof input from the host
     let input: MPTProofInput = sp1_zkvm::io::read();
     
-    // Verify the MPT proof
-    let verified = verify_proof(
-        &input.root,
-        &input.key,
-        &input.value,
-        &input.proof,
-    );
+    // Verify the MPT proof (tracked so the host can report its cycle cost);
+    // `verify` branches on `proof_type` to check inclusion, exclusion or a state
+    // transition. An input of an unsupported format version does not verify
+    println!("cycle-tracker-report-start: verify_proof");
+    let verified = input.verify() == Ok(true);
+    println!("cycle-tracker-report-end: verify_proof");
     
-    // Create the verification result
+    // Create the verification result; the proof type (with a transition's new root)
+    // says which claim `verified` is about
     let result = MPTVerificationResult {
         verified,
         key: input.key,
         value: input.value,
         root: input.root,
+        proof_type: input.proof_type,
     };
     
     // Commit the verification result
@@ -0,0 +1,19 @@
+//! Multi-chain Merkle Patricia Trie proof verification program for SP1 zkVM.
+//!
+//! This program verifies one proof per chain, each against that chain's own root.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+use mpt_lib::MultiChainProofInput;
+
+pub fn main() {
+    // Read the per-chain proofs from the host
+    let input: MultiChainProofInput = sp1_zkvm::io::read();
+    
+    // Verify every chain's proof against its own root
+    let result = input.verify_all();
+    
+    // Commit the multi-chain verification result
+    sp1_zkvm::io::commit(&result);
+}
@@ -0,0 +1,95 @@
+//! Aggregate several single-key MPT proofs into one PLONK proof.
+//!
+//! Each input file is a compressed proof written by `mpt --prove --output <file>`:
+//! ```shell
+//! RUST_LOG=info cargo run --release --bin aggregate -- --proofs a.bin b.bin --output aggregated.bin
+//! ```
+
+use alloy_sol_types::SolType;
+use clap::Parser;
+use mpt_lib::AggregatedMPTProofOutput;
+use sp1_sdk::{include_elf, HashableKey, ProverClient, SP1Proof, SP1ProofWithPublicValues, SP1Stdin};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// The ELF of the single-key program whose proofs are being aggregated.
+pub const MPT_ELF: &[u8] = include_elf!("mpt-program");
+
+/// The ELF of the aggregation program.
+pub const MPT_AGGREGATE_ELF: &[u8] = include_elf!("mpt-aggregate-program");
+
+/// The arguments for the aggregate command.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct AggregateArgs {
+    /// Compressed proof files written by `mpt --prove --output`
+    #[arg(long, num_args = 1.., required = true)]
+    proofs: Vec<PathBuf>,
+
+    /// Where to write the bincode-serialized aggregated proof
+    #[arg(long)]
+    output: PathBuf,
+}
+
+fn main() {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+    dotenv::dotenv().ok();
+
+    // Parse the command line arguments.
+    let args = AggregateArgs::parse();
+
+    // Load the inner proofs.
+    let proofs: Vec<SP1ProofWithPublicValues> = args
+        .proofs
+        .iter()
+        .map(|path| {
+            let bytes = std::fs::read(path).expect("failed to read proof file");
+            bincode::deserialize(&bytes).expect("invalid proof file")
+        })
+        .collect();
+    println!("Aggregating {} proofs...", proofs.len());
+
+    // Setup the prover client.
+    let client = ProverClient::from_env();
+    let (_, mpt_vk) = client.setup(MPT_ELF);
+    let (pk, vk) = client.setup(MPT_AGGREGATE_ELF);
+
+    // Setup the inputs: the inner vkey, each proof's public values, then the proofs themselves.
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&mpt_vk.hash_u32());
+    let public_values: Vec<Vec<u8>> = proofs.iter().map(|p| p.public_values.to_vec()).collect();
+    stdin.write(&public_values);
+
+    for (proof, path) in proofs.into_iter().zip(&args.proofs) {
+        let SP1Proof::Compressed(inner) = proof.proof else {
+            panic!("{} is not a compressed proof", path.display());
+        };
+        stdin.write_proof(*inner, mpt_vk.vk.clone());
+    }
+
+    // Generate the aggregated PLONK proof.
+    let start = Instant::now();
+    let proof = client
+        .prove(&pk, &stdin)
+        .plonk()
+        .run()
+        .expect("failed to generate aggregated proof");
+    println!("\nSuccessfully generated aggregated proof in {:.2?}!", start.elapsed());
+
+    // Verify the proof.
+    client.verify(&proof, &vk).expect("failed to verify aggregated proof");
+    println!("Successfully verified aggregated proof!");
+
+    let output = AggregatedMPTProofOutput::abi_decode(proof.public_values.as_slice())
+        .expect("invalid aggregated public values");
+    println!("\nAggregated Proof Output:");
+    println!("  Proofs: {}", output.proofs.len());
+    println!("  Aggregate root: {}", output.aggregate_root);
+    println!("  MPT vkey: {}", output.mpt_vkey);
+    println!("  Aggregation vkey: {}", vk.bytes32());
+
+    let bytes = bincode::serialize(&proof).expect("failed to serialize proof");
+    std::fs::write(&args.output, bytes).expect("failed to write proof");
+    println!("Proof written to {}", args.output.display());
+}
@@ -0,0 +1,149 @@
+//! Prove many MPT key-value pairs with a single SP1 proof.
+//!
+//! The keys file is a JSON array of `{"key": "0x..", "value": "0x.."}` objects:
+//! ```shell
+//! RUST_LOG=info cargo run --release --bin batch -- --keys-file keys.json --execute
+//! ```
+//! or
+//! ```shell
+//! RUST_LOG=info cargo run --release --bin batch -- --keys-file keys.json --prove --output proof.bin
+//! ```
+
+use clap::Parser;
+use mpt_lib::{
+    MPTBatchProofInput, MPTBatchVerificationResult, MPTBuilder, MPTProofInput, ProofType,
+    PROOF_FORMAT_VERSION,
+};
+use serde::Deserialize;
+use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
+pub const MPT_BATCH_ELF: &[u8] = include_elf!("mpt-batch-program");
+
+/// The arguments for the batch command.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct BatchArgs {
+    /// JSON file with the key-value pairs to prove
+    #[arg(long)]
+    keys_file: PathBuf,
+
+    #[arg(long)]
+    execute: bool,
+
+    #[arg(long)]
+    prove: bool,
+
+    /// Where to write the bincode-serialized proof in `--prove` mode
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+/// A single entry of the keys file.
+#[derive(Debug, Deserialize)]
+struct KeyValueEntry {
+    key: String,
+    value: String,
+}
+
+fn decode_hex(s: &str) -> Vec<u8> {
+    hex::decode(s.trim_start_matches("0x")).expect("invalid hex string in keys file")
+}
+
+fn main() {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+    dotenv::dotenv().ok();
+
+    // Parse the command line arguments.
+    let args = BatchArgs::parse();
+
+    if args.execute == args.prove {
+        eprintln!("Error: You must specify either --execute or --prove");
+        std::process::exit(1);
+    }
+
+    // Load the key-value pairs.
+    let contents = std::fs::read_to_string(&args.keys_file).expect("failed to read keys file");
+    let entries: Vec<KeyValueEntry> = serde_json::from_str(&contents).expect("invalid keys file");
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = entries
+        .iter()
+        .map(|e| (decode_hex(&e.key), decode_hex(&e.value)))
+        .collect();
+
+    if entries.is_empty() {
+        eprintln!("Error: The keys file contains no entries");
+        std::process::exit(1);
+    }
+
+    // Build the trie from all entries.
+    println!("Building MPT with {} entries...", entries.len());
+    let mut builder = MPTBuilder::new();
+    for (key, value) in &entries {
+        builder.insert(key, value);
+    }
+    let root = builder.root().expect("Root should exist");
+    println!("  Root: {}", hex::encode(root));
+
+    // Generate a proof for every entry.
+    let proofs = entries
+        .into_iter()
+        .map(|(key, value)| {
+            let proof = builder.get_proof(&key).expect("Failed to generate proof");
+            MPTProofInput { version: PROOF_FORMAT_VERSION, key, value, proof, root, proof_type: ProofType::Inclusion }
+        })
+        .collect::<Vec<_>>();
+    let count = proofs.len();
+    let input = MPTBatchProofInput { version: PROOF_FORMAT_VERSION, proofs, root };
+
+    // Setup the inputs.
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&input);
+
+    // Setup the prover client.
+    let client = ProverClient::from_env();
+
+    if args.execute {
+        // Execute the program
+        let (mut output, report) = client.execute(MPT_BATCH_ELF, &stdin).run().unwrap();
+        println!("\nProgram executed successfully.");
+
+        let result: MPTBatchVerificationResult = output.read();
+        println!("\nBatch Verification Result:");
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+
+        // Record the number of cycles executed.
+        let cycles = report.total_instruction_count();
+        println!("\nNumber of cycles: {}", cycles);
+        println!("Cycles per key: {}", cycles / count as u64);
+    } else {
+        // Setup the program for proving.
+        let (pk, vk) = client.setup(MPT_BATCH_ELF);
+
+        // Generate a single proof covering the whole batch
+        let start = Instant::now();
+        let mut proof = client
+            .prove(&pk, &stdin)
+            .run()
+            .expect("failed to generate proof");
+        let elapsed = start.elapsed();
+
+        println!("\nSuccessfully generated proof in {:.2?}!", elapsed);
+
+        let result: MPTBatchVerificationResult = proof.public_values.read();
+        println!("\nBatch Verification Result:");
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+
+        // Verify the proof.
+        client.verify(&proof, &vk).expect("failed to verify proof");
+        println!("\nSuccessfully verified proof!");
+
+        if let Some(path) = args.output {
+            let bytes = bincode::serialize(&proof).expect("failed to serialize proof");
+            std::fs::write(&path, bytes).expect("failed to write proof");
+            println!("Proof written to {}", path.display());
+        }
+    }
+}
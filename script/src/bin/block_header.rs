@@ -0,0 +1,145 @@
+//! Prove a storage slot value against an Ethereum block hash.
+//!
+//! The input file holds the block hash, the RLP-encoded block header and the
+//! `eth_getProof` response for the account and slot at that block:
+//! ```json
+//! { "block_hash": "0x..", "rlp_header": "0x..", "proof": { "address": "0x..", "accountProof": [..], ... } }
+//! ```
+//! Only the first entry of `storageProof` is used.
+//! ```shell
+//! RUST_LOG=info cargo run --release --bin block-header -- --input-file proof.json --execute
+//! ```
+//! or
+//! ```shell
+//! RUST_LOG=info cargo run --release --bin block-header -- --input-file proof.json --prove
+//! ```
+
+use clap::Parser;
+use mpt_lib::{
+    account_address_to_state_key, BlockHeaderProofInput, BlockHeaderProofOutput,
+    EIP1186AccountProofResponse, MPTProofInput, ProofType, H256, PROOF_FORMAT_VERSION,
+};
+use serde::Deserialize;
+use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+use std::path::PathBuf;
+
+/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
+pub const MPT_BLOCK_HEADER_ELF: &[u8] = include_elf!("mpt-block-header-program");
+
+/// The arguments for the block-header command.
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct BlockHeaderArgs {
+    /// JSON file with the block hash, RLP header and `eth_getProof` response
+    #[arg(long)]
+    input_file: PathBuf,
+
+    #[arg(long)]
+    execute: bool,
+
+    #[arg(long)]
+    prove: bool,
+}
+
+/// The input file.
+#[derive(Debug, Deserialize)]
+struct InputFile {
+    block_hash: String,
+    rlp_header: String,
+    proof: EIP1186AccountProofResponse,
+}
+
+fn decode_hex(s: &str) -> Vec<u8> {
+    hex::decode(s.trim_start_matches("0x")).expect("invalid hex string in input file")
+}
+
+fn build_input(file: InputFile) -> BlockHeaderProofInput {
+    let proof = file.proof;
+    let address = proof.address().expect("invalid address");
+    let account = proof.account().expect("invalid account fields");
+    let storage_proof = MPTProofInput::from_eth_storage_proof(&proof, 0).expect("invalid storage proof");
+    // Reject a malformed RPC response before any zkVM work
+    storage_proof.validate_structure().expect("malformed storage proof");
+    let slot: H256 = decode_hex(&format!("{:0>64}", proof.storage_proof[0].key.trim_start_matches("0x")))
+        .try_into()
+        .expect("slot does not fit in 32 bytes");
+    let state_root = [0u8; 32]; // Taken from the header inside the program
+
+    BlockHeaderProofInput {
+        rlp_header: decode_hex(&file.rlp_header),
+        block_hash: decode_hex(&file.block_hash).try_into().expect("block hash must be 32 bytes"),
+        address,
+        slot,
+        account_proof: MPTProofInput {
+            version: PROOF_FORMAT_VERSION,
+            key: account_address_to_state_key(&address).to_vec(),
+            value: account.to_rlp(),
+            proof: proof.account_proof_nodes().expect("invalid account proof"),
+            root: state_root,
+            proof_type: ProofType::Inclusion,
+        },
+        storage_proof,
+    }
+}
+
+fn main() {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+    dotenv::dotenv().ok();
+
+    // Parse the command line arguments.
+    let args = BlockHeaderArgs::parse();
+
+    if args.execute == args.prove {
+        eprintln!("Error: You must specify either --execute or --prove");
+        std::process::exit(1);
+    }
+
+    // Load the header and proofs.
+    let contents = std::fs::read_to_string(&args.input_file).expect("failed to read input file");
+    let file: InputFile = serde_json::from_str(&contents).expect("invalid input file");
+    let input = build_input(file);
+
+    // Check the chain locally before running the zkVM.
+    let local = input.verify();
+    println!("Local verification: {}", local.verified);
+
+    // Setup the inputs.
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&input);
+
+    // Setup the prover client.
+    let client = ProverClient::from_env();
+
+    if args.execute {
+        // Execute the program
+        let (mut output, report) = client.execute(MPT_BLOCK_HEADER_ELF, &stdin).run().unwrap();
+        println!("\nProgram executed successfully.");
+
+        let result: BlockHeaderProofOutput = output.read();
+        println!("\nBlock Header Proof Result:");
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+
+        // Record the number of cycles executed.
+        println!("\nNumber of cycles: {}", report.total_instruction_count());
+    } else {
+        // Setup the program for proving.
+        let (pk, vk) = client.setup(MPT_BLOCK_HEADER_ELF);
+
+        // Generate the proof
+        let mut proof = client
+            .prove(&pk, &stdin)
+            .run()
+            .expect("failed to generate proof");
+
+        println!("\nSuccessfully generated proof!");
+
+        let result: BlockHeaderProofOutput = proof.public_values.read();
+        println!("\nBlock Header Proof Result:");
+        println!("{}", serde_json::to_string_pretty(&result).unwrap());
+
+        // Verify the proof.
+        client.verify(&proof, &vk).expect("failed to verify proof");
+        println!("\nSuccessfully verified proof!");
+    }
+}
@@ -0,0 +1,142 @@
+//! Measure `verify_proof` cycles in the SP1 zkVM across proof depths 3-15.
+//!
+//! Executes the single-key program on proofs of every depth from 3 to 15, with short and
+//! long values and 8- and 32-byte keys, and reads the cycles of its `verify_proof`
+//! cycle-tracker region. It then fits the linear cost model
+//!
+//! ```text
+//! cycles = base + per_key_byte * key_len + depth * (per_node + per_node_byte * avg_node_size)
+//! ```
+//!
+//! by least squares and prints the coefficients with the worst relative error of the fit.
+//! Run it in release mode:
+//! ```shell
+//! RUST_LOG=info cargo run --release --bin cycles
+//! ```
+
+use mpt_lib::{MPTBuilder, MPTProofInput, MPTVerificationResult, ProofType, PROOF_FORMAT_VERSION};
+use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+
+/// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
+pub const MPT_ELF: &[u8] = include_elf!("mpt-program");
+
+/// One execution of the program: the model inputs and the measured cycles.
+struct Sample {
+    key_len: usize,
+    depth: usize,
+    avg_node_size: usize,
+    cycles: u64,
+}
+
+impl Sample {
+    /// The model's regressors, in coefficient order
+    fn features(&self) -> [f64; 4] {
+        let depth = self.depth as f64;
+        [1.0, self.key_len as f64, depth, depth * self.avg_node_size as f64]
+    }
+}
+
+/// A proof of `depth` nodes for the all-zero key: `depth - 1` branches, each with one
+/// sibling leaf diverging at the next nibble, followed by the key's own leaf.
+fn proof_of_depth(depth: usize, key_len: usize, value_len: usize) -> MPTProofInput {
+    let value = vec![0xab; value_len];
+    let key = vec![0u8; key_len];
+
+    let mut builder = MPTBuilder::new();
+    builder.insert(&key, &value);
+    for nibble in 0..depth - 1 {
+        let mut sibling = key.clone();
+        sibling[nibble / 2] = if nibble % 2 == 0 { 0x10 } else { 0x01 };
+        builder.insert(&sibling, &value);
+    }
+
+    let proof = builder.get_proof(&key).unwrap();
+    assert_eq!(proof.len(), depth, "unexpected proof depth");
+
+    MPTProofInput {
+        version: PROOF_FORMAT_VERSION,
+        key,
+        value,
+        proof,
+        root: builder.root().unwrap(),
+        proof_type: ProofType::Inclusion,
+    }
+}
+
+/// Least-squares coefficients via the normal equations, solved by Gaussian elimination
+fn fit(samples: &[Sample]) -> [f64; 4] {
+    let mut system = [[0.0f64; 5]; 4];
+    for sample in samples {
+        let x = sample.features();
+        for i in 0..4 {
+            for j in 0..4 {
+                system[i][j] += x[i] * x[j];
+            }
+            system[i][4] += x[i] * sample.cycles as f64;
+        }
+    }
+
+    for col in 0..4 {
+        let pivot = (col..4)
+            .max_by(|&a, &b| system[a][col].abs().total_cmp(&system[b][col].abs()))
+            .unwrap();
+        system.swap(col, pivot);
+        let pivot_row = system[col];
+        for (row, equation) in system.iter_mut().enumerate() {
+            if row != col {
+                let factor = equation[col] / pivot_row[col];
+                for (cell, pivot) in equation.iter_mut().zip(pivot_row).skip(col) {
+                    *cell -= factor * pivot;
+                }
+            }
+        }
+    }
+
+    std::array::from_fn(|i| system[i][4] / system[i][i])
+}
+
+fn main() {
+    // Setup the logger.
+    sp1_sdk::utils::setup_logger();
+    dotenv::dotenv().ok();
+
+    let client = ProverClient::from_env();
+    let mut samples = Vec::new();
+
+    println!("depth  key  avg_node  cycles");
+    for key_len in [8, 32] {
+        for value_len in [40, 200] {
+            for depth in 3..=15 {
+                let input = proof_of_depth(depth, key_len, value_len);
+                let avg_node_size = input.proof.iter().map(Vec::len).sum::<usize>() / depth;
+
+                let mut stdin = SP1Stdin::new();
+                stdin.write(&input);
+                let (mut output, report) = client.execute(MPT_ELF, &stdin).run().unwrap();
+                let result: MPTVerificationResult = output.read();
+                assert!(result.verified, "depth {} proof did not verify", depth);
+
+                let cycles = report.cycle_tracker["verify_proof"];
+                println!("{:>5}  {:>3}  {:>8}  {}", depth, key_len, avg_node_size, cycles);
+                samples.push(Sample { key_len, depth, avg_node_size, cycles });
+            }
+        }
+    }
+
+    let [base, per_key_byte, per_node, per_node_byte] = fit(&samples);
+    let worst_error = samples
+        .iter()
+        .map(|sample| {
+            let x = sample.features();
+            let estimate = base * x[0] + per_key_byte * x[1] + per_node * x[2] + per_node_byte * x[3];
+            (estimate - sample.cycles as f64).abs() / sample.cycles as f64
+        })
+        .fold(0.0, f64::max);
+
+    println!();
+    println!("base:          {:.0}", base);
+    println!("per_key_byte:  {:.0}", per_key_byte);
+    println!("per_node:      {:.0}", per_node);
+    println!("per_node_byte: {:.0}", per_node_byte);
+    println!("worst relative error over {} runs: {:.1}%", samples.len(), worst_error * 100.0);
+}
@@ -11,7 +11,7 @@
 //! ```
 
 use clap::{Parser, ValueEnum};
-use mpt_lib::{MPTProofInput, MPTVerificationResult};
+use mpt_lib::{MPTProofInput, MPTVerificationResult, ProofType, PROOF_FORMAT_VERSION};
 use serde::{Deserialize, Serialize};
 use sp1_sdk::{
     include_elf, HashableKey, ProverClient, SP1ProofWithPublicValues, SP1Stdin, SP1VerifyingKey,
@@ -64,10 +64,12 @@ fn main() {
 
     // Create a sample MPT proof input
     let input = MPTProofInput {
+        version: PROOF_FORMAT_VERSION,
         key: hex::decode("1234").unwrap(),
         value: b"test_value".to_vec(),
         proof: vec![],
         root: [0u8; 32],
+        proof_type: ProofType::Inclusion,
     };
 
     // Setup the inputs.
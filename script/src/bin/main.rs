@@ -10,8 +10,9 @@
 //! ```
 
 use clap::Parser;
-use mpt_lib::{MPTProofInput, MPTVerificationResult, MPTBuilder};
+use mpt_lib::{MPTProofInput, MPTVerificationResult, MPTBuilder, ProofType, PROOF_FORMAT_VERSION};
 use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+use std::path::PathBuf;
 
 /// The ELF (executable and linkable format) file for the Succinct RISC-V zkVM.
 pub const MPT_ELF: &[u8] = include_elf!("mpt-program");
@@ -25,6 +26,14 @@ struct Args {
 
     #[arg(long)]
     prove: bool,
+
+    /// Write a compressed (aggregatable) proof to this file in `--prove` mode
+    #[arg(long)]
+    output: Option<PathBuf>,
+
+    /// Write proofs of every inserted key to this JSON file for external verifiers
+    #[arg(long)]
+    export_proofs: Option<PathBuf>,
 }
 
 fn main() {
@@ -56,6 +65,12 @@ fn main() {
     let root = builder.root().expect("Root should exist");
     println!("  Root: {}", hex::encode(root));
     
+    if let Some(path) = &args.export_proofs {
+        let keys: [&[u8]; 4] = [b"do", b"dog", b"doge", b"horse"];
+        builder.export_proofs_to_file(path, &keys).expect("Failed to export proofs");
+        println!("  Proofs exported to {}", path.display());
+    }
+    
     // Generate a proof for one of the keys
     let key = b"dog";
     let value = b"puppy";
@@ -72,10 +87,12 @@ fn main() {
     
     // Create MPT proof input
     let input = MPTProofInput {
+        version: PROOF_FORMAT_VERSION,
         key: key.to_vec(),
         value: value.to_vec(),
         proof,
         root,
+        proof_type: ProofType::Inclusion,
     };
 
     // Setup the inputs.
@@ -102,15 +119,21 @@ fn main() {
 
         // Record the number of cycles executed.
         println!("\nNumber of cycles: {}", report.total_instruction_count());
+
+        // Cycles measured in the program's cycle-tracker regions
+        let mut regions: Vec<_> = report.cycle_tracker.iter().collect();
+        regions.sort();
+        for (name, cycles) in regions {
+            println!("  {}: {} cycles", name, cycles);
+        }
     } else {
         // Setup the program for proving.
         let (pk, vk) = client.setup(MPT_ELF);
 
-        // Generate the proof
-        let mut proof = client
-            .prove(&pk, &stdin)
-            .run()
-            .expect("failed to generate proof");
+        // Generate the proof (compressed when it will be saved for aggregation)
+        let prover = client.prove(&pk, &stdin);
+        let prover = if args.output.is_some() { prover.compressed() } else { prover };
+        let mut proof = prover.run().expect("failed to generate proof");
 
         println!("\nSuccessfully generated proof!");
 
@@ -124,5 +147,11 @@ fn main() {
         // Verify the proof.
         client.verify(&proof, &vk).expect("failed to verify proof");
         println!("\nSuccessfully verified proof!");
+
+        if let Some(path) = args.output {
+            let bytes = bincode::serialize(&proof).expect("failed to serialize proof");
+            std::fs::write(&path, bytes).expect("failed to write proof");
+            println!("Proof written to {}", path.display());
+        }
     }
 }
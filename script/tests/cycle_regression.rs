@@ -0,0 +1,85 @@
+//! Cycle-count regression check for `verify_proof` in the SP1 zkVM.
+//!
+//! Executes the guest program on a 5-node proof and compares the cycles measured in its
+//! `verify_proof` cycle-tracker region against the committed
+//! `tests/verify_proof_cycles.baseline`, failing if they grew by more than 10%. The
+//! measurement is written to the temp dir; copy it into the baseline file to update it.
+//!
+//! ```shell
+//! cargo test --release -p mpt-script --test cycle_regression -- --ignored --nocapture
+//! ```
+
+use mpt_lib::{MPTBuilder, MPTProofInput, MPTVerificationResult, ProofType, PROOF_FORMAT_VERSION};
+use sp1_sdk::{include_elf, ProverClient, SP1Stdin};
+
+const MPT_ELF: &[u8] = include_elf!("mpt-program");
+
+const BASELINE: &str = include_str!("verify_proof_cycles.baseline");
+
+/// The recorded cycle count: the first line of the baseline file that is not a `#` comment
+fn baseline_cycles() -> Option<u64> {
+    BASELINE
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.parse().expect("baseline must be a cycle count"))
+}
+
+/// Build a trie where the proof for the all-zero key has exactly 5 nodes:
+/// four branches (diverging at nibbles 0..3) followed by a leaf.
+fn five_node_proof() -> MPTProofInput {
+    let value = b"a value long enough for every leaf to be hashed".to_vec();
+    let keys: [[u8; 4]; 5] = [
+        [0x00, 0x00, 0x00, 0x00],
+        [0x10, 0x00, 0x00, 0x00],
+        [0x01, 0x00, 0x00, 0x00],
+        [0x00, 0x10, 0x00, 0x00],
+        [0x00, 0x01, 0x00, 0x00],
+    ];
+
+    let mut builder = MPTBuilder::new();
+    for key in &keys {
+        builder.insert(key, &value);
+    }
+
+    let proof = builder.get_proof(&keys[0]).unwrap();
+    assert_eq!(proof.len(), 5);
+
+    MPTProofInput {
+        version: PROOF_FORMAT_VERSION,
+        key: keys[0].to_vec(),
+        value,
+        proof,
+        root: builder.root().unwrap(),
+        proof_type: ProofType::Inclusion,
+    }
+}
+
+#[test]
+#[ignore = "executes the SP1 guest program"]
+fn verify_proof_cycle_baseline() {
+    let input = five_node_proof();
+
+    let mut stdin = SP1Stdin::new();
+    stdin.write(&input);
+
+    let client = ProverClient::from_env();
+    let (mut output, report) = client.execute(MPT_ELF, &stdin).run().unwrap();
+    let result: MPTVerificationResult = output.read();
+    assert!(result.verified);
+
+    let measured = report.cycle_tracker["verify_proof"];
+    let measured_path = std::env::temp_dir().join("verify_proof_cycles.measured");
+    std::fs::write(&measured_path, format!("{}\n", measured)).unwrap();
+    println!("verify_proof: {} cycles (written to {})", measured, measured_path.display());
+
+    let baseline = baseline_cycles().unwrap_or_else(|| {
+        panic!("no baseline recorded; add {} to tests/verify_proof_cycles.baseline", measured)
+    });
+    assert!(
+        measured <= baseline + baseline / 10,
+        "verify_proof regressed: {} cycles vs baseline {}",
+        measured,
+        baseline
+    );
+}